@@ -0,0 +1,480 @@
+//! Maps a [DynOp] and the types of its arguments to the statically typed operation that implements it.
+//!
+//! The arguments are already serialized, or evaluated, when an operation is built. They are passed as typed [DataId]s, which are
+//! operations themselves. The same table is used when assembling a [DynOperation](super::DynOperation), so an operation
+//! passes type checking exactly if there is a typed implementation.
+
+use std::marker::PhantomData;
+
+use glam::{
+    BVec2, BVec3, BVec4, IVec2, IVec3, IVec4, Mat2, Mat3, Mat4, UVec2, UVec3, UVec4, Vec2, Vec3,
+    Vec4,
+};
+use rspirv::spirv::Word;
+
+use super::{DynDataId, DynOp};
+use crate::{
+    operations::*,
+    spv_fi::{IntoSpvType, SpvType},
+    BoxOperation, DataId, Evaluator, Operation, SerializeError, Serializer,
+};
+
+///Operation with type-erased output and without input.
+pub(crate) trait ErasedOperation {
+    ///Serializes the operation, returns the result id.
+    fn serialize(&mut self, serializer: &mut Serializer) -> Result<Word, SerializeError>;
+    ///Evaluates the operation, returns the result id.
+    fn evaluate(&mut self, evaluator: &mut Evaluator) -> Word;
+    ///Returns the type of the result.
+    fn ty(&self) -> SpvType;
+}
+
+impl<T: IntoSpvType, O: Operation<Input = (), Output = DataId<T>>> ErasedOperation for O {
+    fn serialize(&mut self, serializer: &mut Serializer) -> Result<Word, SerializeError> {
+        Ok(Operation::serialize(self, serializer, ())?.id)
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator) -> Word {
+        Operation::evaluate(self, evaluator, ()).id
+    }
+
+    fn ty(&self) -> SpvType {
+        T::into_spv_type()
+    }
+}
+
+///Returns the already calculated argument `id` as typed operation.
+fn arg<T: Clone + 'static>(id: &DynDataId) -> BoxOperation<(), T> {
+    Box::new(DataId::<T>::from(id.id))
+}
+
+///Returns the typed operation `$op` for the first of the rust types in `$list` that matches the SpvType `$ty`. Within `$op` the
+/// matching rust type is available as `$t`. Returns `None` from the calling function if no type matches.
+macro_rules! typed {
+    ($ty:expr, floats, |$t:ident| $op:expr) => {
+        typed!($ty, [f32, Vec2, Vec3, Vec4], |$t| $op)
+    };
+    ($ty:expr, float_vecs, |$t:ident| $op:expr) => {
+        typed!($ty, [Vec2, Vec3, Vec4], |$t| $op)
+    };
+    ($ty:expr, numbers, |$t:ident| $op:expr) => {
+        typed!(
+            $ty,
+            [f32, Vec2, Vec3, Vec4, i32, IVec2, IVec3, IVec4, u32, UVec2, UVec3, UVec4],
+            |$t| $op
+        )
+    };
+    ($ty:expr, bools, |$t:ident| $op:expr) => {
+        typed!($ty, [bool, BVec2, BVec3, BVec4], |$t| $op)
+    };
+    ($ty:expr, bool_vecs, |$t:ident| $op:expr) => {
+        typed!($ty, [BVec2, BVec3, BVec4], |$t| $op)
+    };
+    ($ty:expr, matrices, |$t:ident| $op:expr) => {
+        typed!($ty, [Mat2, Mat3, Mat4], |$t| $op)
+    };
+    ($ty:expr, all, |$t:ident| $op:expr) => {
+        typed!(
+            $ty,
+            [
+                bool, f32, i32, u32, Vec2, Vec3, Vec4, IVec2, IVec3, IVec4, UVec2, UVec3, UVec4,
+                BVec2, BVec3, BVec4, Mat2, Mat3, Mat4
+            ],
+            |$t| $op
+        )
+    };
+    ($ty:expr, [$($rty:ty),+], |$t:ident| $op:expr) => {{
+        let ty: &SpvType = $ty;
+        $(
+            if *ty == <$rty>::into_spv_type() {
+                #[allow(dead_code)]
+                type $t = $rty;
+                return Some(Box::new($op));
+            }
+        )+
+        None
+    }};
+}
+
+///Returns the typed operation of a matrix operation, where `$m` is the matrix and `$v` the column vector type.
+macro_rules! typed_matrix {
+    ($mty:expr, $vty:expr, |$m:ident, $v:ident| $op:expr) => {{
+        let (mty, vty): (&SpvType, &SpvType) = ($mty, $vty);
+        typed_matrix!(@pair mty, vty, $m, $v, $op, Mat2, Vec2);
+        typed_matrix!(@pair mty, vty, $m, $v, $op, Mat3, Vec3);
+        typed_matrix!(@pair mty, vty, $m, $v, $op, Mat4, Vec4);
+        None
+    }};
+    (@pair $mty:ident, $vty:ident, $m:ident, $v:ident, $op:expr, $rm:ty, $rv:ty) => {
+        if *$mty == <$rm>::into_spv_type() && *$vty == <$rv>::into_spv_type() {
+            #[allow(dead_code)]
+            type $m = $rm;
+            #[allow(dead_code)]
+            type $v = $rv;
+            return Some(Box::new($op));
+        }
+    };
+}
+
+///Returns the typed operation of a float vector operation that outputs a vector with `len` components.
+macro_rules! typed_vec_len {
+    ($len:expr, |$t:ident| $op:expr) => {
+        match $len {
+            2 => typed!(&Vec2::into_spv_type(), [Vec2], |$t| $op),
+            3 => typed!(&Vec3::into_spv_type(), [Vec3], |$t| $op),
+            4 => typed!(&Vec4::into_spv_type(), [Vec4], |$t| $op),
+            _ => None,
+        }
+    };
+}
+
+///Returns the number of components of a vector type, or `None` if `ty` is no vector.
+pub(crate) fn vector_len(ty: &SpvType) -> Option<u32> {
+    match ty {
+        SpvType::Vec { num_elements, .. } => Some(*num_elements),
+        _ => None,
+    }
+}
+
+///Builds the typed operation of `op` for the already calculated `args`. Returns `None` if there is no implementation for the
+/// argument types.
+pub(crate) fn build(op: &DynOp, args: &[DynDataId]) -> Option<Box<dyn ErasedOperation>> {
+    let f32_ty = f32::into_spv_type();
+    match (op, args) {
+        (DynOp::Constant(value), []) => Some(value.constant()),
+        (DynOp::Variable { name, default }, []) => Some(default.variable(name)),
+
+        //Vector-scalar arithmetic
+        (DynOp::Add, [v, s]) if s.ty == f32_ty && vector_len(&v.ty).is_some() => {
+            typed!(&v.ty, float_vecs, |V| {
+                VectorPlusScalar::<(), V> {
+                    vector: arg(v),
+                    scalar: arg(s),
+                }
+            })
+        }
+        (DynOp::Add, [s, v]) if s.ty == f32_ty && vector_len(&v.ty).is_some() => {
+            typed!(&v.ty, float_vecs, |V| {
+                VectorPlusScalar::<(), V> {
+                    vector: arg(v),
+                    scalar: arg(s),
+                }
+            })
+        }
+        (DynOp::Sub, [v, s]) if s.ty == f32_ty && vector_len(&v.ty).is_some() => {
+            typed!(&v.ty, float_vecs, |V| {
+                VectorMinusScalar::<(), V> {
+                    vector: arg(v),
+                    scalar: arg(s),
+                }
+            })
+        }
+        (DynOp::Mul, [v, s]) if s.ty == f32_ty && vector_len(&v.ty).is_some() => {
+            typed!(&v.ty, float_vecs, |V| {
+                VectorTimesScalar::<(), V> {
+                    vector: arg(v),
+                    scalar: arg(s),
+                }
+            })
+        }
+        (DynOp::Mul, [s, v]) if s.ty == f32_ty && vector_len(&v.ty).is_some() => {
+            typed!(&v.ty, float_vecs, |V| {
+                VectorTimesScalar::<(), V> {
+                    vector: arg(v),
+                    scalar: arg(s),
+                }
+            })
+        }
+        (DynOp::Div, [v, s]) if s.ty == f32_ty && vector_len(&v.ty).is_some() => {
+            typed!(&v.ty, float_vecs, |V| {
+                VectorDividedByScalar::<(), V> {
+                    vector: arg(v),
+                    scalar: arg(s),
+                }
+            })
+        }
+
+        //Linear algebra
+        (DynOp::Mul, [a, b]) if a.ty == b.ty && matches!(a.ty, SpvType::Matrix { .. }) => {
+            typed!(&a.ty, matrices, |M| MatrixTimesMatrix::<M, ()> {
+                a: arg(a),
+                b: arg(b)
+            })
+        }
+        (DynOp::Mul, [m, v]) if matches!(m.ty, SpvType::Matrix { .. }) => {
+            typed_matrix!(&m.ty, &v.ty, |M, V| MatrixTimesVector::<M, V, ()> {
+                matrix: arg(m),
+                vector: arg(v)
+            })
+        }
+        (DynOp::Mul, [v, m]) if matches!(m.ty, SpvType::Matrix { .. }) => {
+            typed_matrix!(&m.ty, &v.ty, |M, V| VectorTimesMatrix::<M, V, ()> {
+                vector: arg(v),
+                matrix: arg(m)
+            })
+        }
+        (DynOp::Transpose, [m]) => {
+            typed!(&m.ty, matrices, |M| Transpose::<M, ()> { inner: arg(m) })
+        }
+        (DynOp::Determinant, [m]) => {
+            typed!(&m.ty, matrices, |M| Determinant::<M, ()> { inner: arg(m) })
+        }
+        (DynOp::Inverse, [m]) => {
+            typed!(&m.ty, matrices, |M| MatrixInverse::<M, ()> {
+                inner: arg(m)
+            })
+        }
+
+        //Arithmetic of equally typed arguments
+        (
+            DynOp::Add
+            | DynOp::Sub
+            | DynOp::Mul
+            | DynOp::Div
+            | DynOp::Remainder
+            | DynOp::Modulo
+            | DynOp::Min
+            | DynOp::Max
+            | DynOp::Less
+            | DynOp::LessEqual
+            | DynOp::Greater
+            | DynOp::GreaterEqual
+            | DynOp::Equal
+            | DynOp::NotEqual
+            | DynOp::And
+            | DynOp::Or
+            | DynOp::ArcTangent2
+            | DynOp::Pow
+            | DynOp::Step
+            | DynOp::Dot
+            | DynOp::Distance
+            | DynOp::Cross
+            | DynOp::Reflect,
+            [a, b],
+        ) if a.ty != b.ty => None,
+        (DynOp::Add, [a, b]) => typed!(&a.ty, numbers, |T| Addition::<(), T> {
+            a: arg(a),
+            b: arg(b)
+        }),
+        (DynOp::Sub, [a, b]) => typed!(&a.ty, numbers, |T| Subtraction::<(), T> {
+            minuent: arg(a),
+            subtrahend: arg(b)
+        }),
+        (DynOp::Mul, [a, b]) => typed!(&a.ty, numbers, |T| Multiplication::<(), T> {
+            a: arg(a),
+            b: arg(b)
+        }),
+        (DynOp::Div, [a, b]) => typed!(&a.ty, numbers, |T| Division::<(), T> {
+            dividend: arg(a),
+            divisor: arg(b)
+        }),
+        (DynOp::Remainder, [a, b]) => typed!(&a.ty, numbers, |T| Remainder::<(), T> {
+            dividend: arg(a),
+            divisor: arg(b)
+        }),
+        (DynOp::Modulo, [a, b]) => typed!(&a.ty, numbers, |T| Modulo::<(), T> {
+            dividend: arg(a),
+            divisor: arg(b)
+        }),
+        (DynOp::Min, [a, b]) => typed!(&a.ty, numbers, |T| Min::<(), T> {
+            a: arg(a),
+            b: arg(b)
+        }),
+        (DynOp::Max, [a, b]) => typed!(&a.ty, numbers, |T| Max::<(), T> {
+            a: arg(a),
+            b: arg(b)
+        }),
+        (DynOp::Negate, [a]) => typed!(
+            &a.ty,
+            [f32, Vec2, Vec3, Vec4, i32, IVec2, IVec3, IVec4],
+            |T| Negate::<(), T> { inner: arg(a) }
+        ),
+        (DynOp::Abs, [a]) => typed!(&a.ty, numbers, |T| Abs::<(), T> { inner: arg(a) }),
+        (DynOp::Square, [a]) => typed!(&a.ty, floats, |T| Square::<(), T> { inner: arg(a) }),
+        (DynOp::Sqrt, [a]) => typed!(&a.ty, [f32], |T| Sqrt::<()> { inner: arg(a) }),
+        (DynOp::Sine, [a]) => typed!(&a.ty, [f32], |T| Sine::<()> { inner: arg(a) }),
+        (DynOp::Cosine, [a]) => typed!(&a.ty, [f32], |T| Cosine::<()> { inner: arg(a) }),
+        (DynOp::Tangent, [a]) => typed!(&a.ty, [f32], |T| Tangent::<()> { inner: arg(a) }),
+
+        //Extended float operations
+        (DynOp::Floor, [a]) => typed!(&a.ty, floats, |T| Floor::<(), T> { inner: arg(a) }),
+        (DynOp::Ceil, [a]) => typed!(&a.ty, floats, |T| Ceil::<(), T> { inner: arg(a) }),
+        (DynOp::Fract, [a]) => typed!(&a.ty, floats, |T| Fract::<(), T> { inner: arg(a) }),
+        (DynOp::Round, [a]) => typed!(&a.ty, floats, |T| Round::<(), T> { inner: arg(a) }),
+        (DynOp::Trunc, [a]) => typed!(&a.ty, floats, |T| Trunc::<(), T> { inner: arg(a) }),
+        (DynOp::Sign, [a]) => typed!(&a.ty, floats, |T| Sign::<(), T> { inner: arg(a) }),
+        (DynOp::Exp, [a]) => typed!(&a.ty, floats, |T| Exp::<(), T> { inner: arg(a) }),
+        (DynOp::Exp2, [a]) => typed!(&a.ty, floats, |T| Exp2::<(), T> { inner: arg(a) }),
+        (DynOp::Log, [a]) => typed!(&a.ty, floats, |T| Log::<(), T> { inner: arg(a) }),
+        (DynOp::Log2, [a]) => typed!(&a.ty, floats, |T| Log2::<(), T> { inner: arg(a) }),
+        (DynOp::InverseSqrt, [a]) => {
+            typed!(&a.ty, floats, |T| InverseSqrt::<(), T> { inner: arg(a) })
+        }
+        (DynOp::ArcSine, [a]) => typed!(&a.ty, floats, |T| ArcSine::<(), T> { inner: arg(a) }),
+        (DynOp::ArcCosine, [a]) => {
+            typed!(&a.ty, floats, |T| ArcCosine::<(), T> { inner: arg(a) })
+        }
+        (DynOp::ArcTangent, [a]) => {
+            typed!(&a.ty, floats, |T| ArcTangent::<(), T> { inner: arg(a) })
+        }
+        (DynOp::ArcTangent2, [y, x]) => typed!(&y.ty, floats, |T| ArcTangent2::<(), T> {
+            y: arg(y),
+            x: arg(x)
+        }),
+        (DynOp::Pow, [b, e]) => typed!(&b.ty, floats, |T| Pow::<(), T> {
+            base: arg(b),
+            exponent: arg(e)
+        }),
+        (DynOp::Step, [edge, x]) => typed!(&x.ty, floats, |T| Step::<(), T> {
+            edge: arg(edge),
+            x: arg(x)
+        }),
+        (DynOp::Clamp, [x, min, max]) if x.ty == min.ty && x.ty == max.ty => {
+            typed!(&x.ty, floats, |T| Clamp::<(), T> {
+                inner: arg(x),
+                min: arg(min),
+                max: arg(max)
+            })
+        }
+        (DynOp::Mix, [a, b, t]) if a.ty == b.ty && a.ty == t.ty => {
+            typed!(&a.ty, floats, |T| Mix::<(), T> {
+                a: arg(a),
+                b: arg(b),
+                t: arg(t)
+            })
+        }
+        (DynOp::SmoothStep, [e0, e1, x]) if x.ty == e0.ty && x.ty == e1.ty => {
+            typed!(&x.ty, floats, |T| SmoothStep::<(), T> {
+                edge0: arg(e0),
+                edge1: arg(e1),
+                x: arg(x)
+            })
+        }
+        (DynOp::Fma, [a, b, c]) if a.ty == b.ty && a.ty == c.ty => {
+            typed!(&a.ty, floats, |T| Fma::<(), T> {
+                a: arg(a),
+                b: arg(b),
+                c: arg(c)
+            })
+        }
+
+        //Vector operations
+        (DynOp::Dot, [a, b]) => typed!(&a.ty, float_vecs, |V| Dot::<V, ()> {
+            a: arg(a),
+            b: arg(b)
+        }),
+        (DynOp::Distance, [a, b]) => typed!(&a.ty, float_vecs, |V| Distance::<V, ()> {
+            a: arg(a),
+            b: arg(b)
+        }),
+        (DynOp::Cross, [a, b]) => typed!(&a.ty, [Vec3], |V| Cross::<()> {
+            a: arg(a),
+            b: arg(b)
+        }),
+        (DynOp::Reflect, [i, n]) => typed!(&i.ty, float_vecs, |V| Reflect::<V, ()> {
+            incident: arg(i),
+            normal: arg(n)
+        }),
+        (DynOp::Refract, [i, n, eta]) if i.ty == n.ty && eta.ty == f32_ty => {
+            typed!(&i.ty, float_vecs, |V| Refract::<V, ()> {
+                incident: arg(i),
+                normal: arg(n),
+                eta: arg(eta)
+            })
+        }
+        (DynOp::FaceForward, [n, i, r]) if n.ty == i.ty && n.ty == r.ty => {
+            typed!(&n.ty, float_vecs, |V| FaceForward::<V, ()> {
+                normal: arg(n),
+                incident: arg(i),
+                reference: arg(r)
+            })
+        }
+        (DynOp::Length, [v]) => typed!(&v.ty, float_vecs, |V| Length::<V, ()> { inner: arg(v) }),
+        (DynOp::Normalize, [v]) => {
+            typed!(&v.ty, float_vecs, |V| Normalize::<V, ()> { inner: arg(v) })
+        }
+        (DynOp::Element(element), [v]) if *element < vector_len(&v.ty)? => {
+            typed!(&v.ty, float_vecs, |V| VecSelectElement::<V, ()> {
+                element: *element,
+                inner: arg(v)
+            })
+        }
+        (DynOp::Construct, elements) if elements.iter().all(|e| e.ty == f32_ty) => {
+            typed_vec_len!(elements.len(), |V| VecConstruct::<V, ()>::new(
+                elements.iter().map(arg).collect()
+            ))
+        }
+        (DynOp::Splat(len), [s]) if s.ty == f32_ty => {
+            typed_vec_len!(*len, |V| Splat::<V, ()>::new(arg(s)))
+        }
+        (DynOp::Swizzle(components), [v]) => {
+            let len = vector_len(&v.ty)?;
+            if components.iter().any(|c| *c >= len) {
+                return None;
+            }
+            match components.len() {
+                2 => typed!(&v.ty, float_vecs, |V| Swizzle::<V, Vec2, ()> {
+                    components: components.clone(),
+                    inner: arg(v),
+                    ty: PhantomData
+                }),
+                3 => typed!(&v.ty, float_vecs, |V| Swizzle::<V, Vec3, ()> {
+                    components: components.clone(),
+                    inner: arg(v),
+                    ty: PhantomData
+                }),
+                4 => typed!(&v.ty, float_vecs, |V| Swizzle::<V, Vec4, ()> {
+                    components: components.clone(),
+                    inner: arg(v),
+                    ty: PhantomData
+                }),
+                _ => None,
+            }
+        }
+
+        //Comparison and logic
+        (DynOp::Less, [a, b]) => typed!(&a.ty, numbers, |T| Less::<(), T> {
+            a: arg(a),
+            b: arg(b)
+        }),
+        (DynOp::LessEqual, [a, b]) => typed!(&a.ty, numbers, |T| LessEqual::<(), T> {
+            a: arg(a),
+            b: arg(b)
+        }),
+        (DynOp::Greater, [a, b]) => typed!(&a.ty, numbers, |T| Greater::<(), T> {
+            a: arg(a),
+            b: arg(b)
+        }),
+        (DynOp::GreaterEqual, [a, b]) => typed!(&a.ty, numbers, |T| GreaterEqual::<(), T> {
+            a: arg(a),
+            b: arg(b)
+        }),
+        (DynOp::Equal, [a, b]) => typed!(&a.ty, numbers, |T| Equal::<(), T> {
+            a: arg(a),
+            b: arg(b)
+        }),
+        (DynOp::NotEqual, [a, b]) => typed!(&a.ty, numbers, |T| NotEqual::<(), T> {
+            a: arg(a),
+            b: arg(b)
+        }),
+        (DynOp::And, [a, b]) => typed!(&a.ty, bools, |B| And::<(), B> {
+            a: arg(a),
+            b: arg(b)
+        }),
+        (DynOp::Or, [a, b]) => typed!(&a.ty, bools, |B| Or::<(), B> {
+            a: arg(a),
+            b: arg(b)
+        }),
+        (DynOp::Not, [a]) => typed!(&a.ty, bools, |B| Not::<(), B> { inner: arg(a) }),
+        (DynOp::Any, [a]) => typed!(&a.ty, bool_vecs, |B| Any::<(), B> { inner: arg(a) }),
+        (DynOp::All, [a]) => typed!(&a.ty, bool_vecs, |B| All::<(), B> { inner: arg(a) }),
+        (DynOp::Select, [c, t, f]) if c.ty == SpvType::Bool && t.ty == f.ty => {
+            typed!(&t.ty, all, |T| Select::<(), T> {
+                condition: arg(c),
+                true_branch: arg(t),
+                false_branch: arg(f)
+            })
+        }
+        _ => None,
+    }
+}
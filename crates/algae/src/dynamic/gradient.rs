@@ -0,0 +1,547 @@
+use std::f32::consts::LN_2;
+
+use fxhash::FxHashMap;
+use glam::{Vec2, Vec3, Vec4};
+
+use super::{DynError, DynOp, DynOperation, DynValue};
+use crate::spv_fi::{IntoSpvType, SpvType};
+
+///Builds the analytic gradient of a scalar [DynOperation] with respect to one of its `f32` or float vector variables.
+///
+/// The gradient is calculated symbolically in forward mode. Each component of the variable is seeded with a unit vector,
+/// and the derivative of every node is derived from its arguments and their derivatives. The result is a new operation graph
+/// of the variable's type, that can be serialized and evaluated like any other graph. Sub graphs that do not depend on the
+/// variable are shared with the original graph, therefore the serializer only emits them once. Derivatives that are needed
+/// several times, like the one of the argument of `normalize`, are bound via [Let](DynOp::Let), so the gradient grows
+/// polynomially with the depth of the graph.
+///
+/// Piecewise constant operations like `floor` or `step` have a derivative of zero, operations like `min`, `clamp` or
+/// `abs` use the derivative of the branch that is taken.
+///
+/// # Example
+/// ```rust
+/// use algae::{dynamic::{DynValue, Gradient, Parser}, glam::Vec3, Evaluator};
+///
+/// let sphere = Parser::new().with_variable("p", Vec3::ZERO).parse("length(p) - 1.0").unwrap();
+/// let mut normal = Gradient::new("p").apply(&sphere).unwrap();
+///
+/// let mut evaluator = Evaluator::new();
+/// evaluator.set_variable("p", Vec3::new(0.0, 3.0, 0.0));
+/// assert_eq!(evaluator.evaluate_dyn(&mut normal), DynValue::Vec3(Vec3::Y));
+/// ```
+pub struct Gradient {
+    variable: String,
+}
+
+impl Gradient {
+    ///Creates the gradient with respect to the variable `variable`.
+    pub fn new(variable: &str) -> Self {
+        Gradient {
+            variable: String::from(variable),
+        }
+    }
+
+    ///Builds the gradient of `operation`, which must result in a `f32`.
+    pub fn apply(&self, operation: &DynOperation) -> Result<DynOperation, DynError> {
+        let f32_ty = f32::into_spv_type();
+        if operation.ty != f32_ty {
+            return Err(DynError::TypeMismatch {
+                expected: f32_ty,
+                found: operation.ty.clone(),
+            });
+        }
+
+        let variable = find_variable(operation, &self.variable)
+            .ok_or_else(|| DynError::UnknownVariable(self.variable.clone()))?;
+        let len = float_len(&variable.ty).ok_or_else(|| DynError::UnsupportedTypes {
+            op: Box::new(variable.op.clone()),
+            arguments: vec![variable.ty.clone()],
+        })?;
+
+        let next_id = max_binding(operation).map_or(0, |id| id + 1);
+        let mut partials = (0..len)
+            .map(|component| {
+                let mut derivative = Derivative {
+                    variable: &self.variable,
+                    seed: unit(len, component),
+                    bindings: FxHashMap::default(),
+                    next_id,
+                };
+                Ok(derivative
+                    .of(operation)?
+                    .unwrap_or_else(|| DynOperation::constant(0.0f32)))
+            })
+            .collect::<Result<Vec<_>, DynError>>()?;
+
+        if len == 1 {
+            Ok(partials.remove(0))
+        } else {
+            DynOperation::new(DynOp::Construct, partials)
+        }
+    }
+}
+
+///Returns the first variable node named `name`.
+fn find_variable<'a>(operation: &'a DynOperation, name: &str) -> Option<&'a DynOperation> {
+    match &operation.op {
+        DynOp::Variable { name: n, .. } if n == name => Some(operation),
+        _ => operation.args.iter().find_map(|a| find_variable(a, name)),
+    }
+}
+
+///Returns the largest id that is bound within `operation`.
+fn max_binding(operation: &DynOperation) -> Option<u64> {
+    let id = match &operation.op {
+        DynOp::Let(id) | DynOp::Binding { id, .. } => Some(*id),
+        _ => None,
+    };
+    operation
+        .args
+        .iter()
+        .filter_map(max_binding)
+        .chain(id)
+        .max()
+}
+
+///Returns the number of components of `f32` and float vectors.
+fn float_len(ty: &SpvType) -> Option<usize> {
+    [
+        f32::into_spv_type(),
+        Vec2::into_spv_type(),
+        Vec3::into_spv_type(),
+        Vec4::into_spv_type(),
+    ]
+    .iter()
+    .position(|t| t == ty)
+    .map(|p| p + 1)
+}
+
+///Value with `len` components, where `component` is one and all others are zero.
+fn unit(len: usize, component: usize) -> DynValue {
+    let mut c = [0.0; 4];
+    c[component] = 1.0;
+    match len {
+        1 => DynValue::F32(c[0]),
+        2 => DynValue::Vec2(Vec2::new(c[0], c[1])),
+        3 => DynValue::Vec3(Vec3::new(c[0], c[1], c[2])),
+        _ => DynValue::Vec4(Vec4::from(c)),
+    }
+}
+
+///Constant of the float type `ty`, where each component is `value`.
+fn constant_like(value: f32, ty: &SpvType) -> DynOperation {
+    match float_len(ty) {
+        Some(2) => DynOperation::constant(Vec2::splat(value)),
+        Some(3) => DynOperation::constant(Vec3::splat(value)),
+        Some(4) => DynOperation::constant(Vec4::splat(value)),
+        _ => DynOperation::constant(value),
+    }
+}
+
+fn op(op: DynOp, args: Vec<DynOperation>) -> Result<DynOperation, DynError> {
+    DynOperation::new(op, args)
+}
+
+fn c(value: f32) -> DynOperation {
+    DynOperation::constant(value)
+}
+
+///`a + b`, where `None` is zero.
+fn sum(a: Option<DynOperation>, b: Option<DynOperation>) -> Result<Option<DynOperation>, DynError> {
+    match (a, b) {
+        (Some(a), Some(b)) => op(DynOp::Add, vec![a, b]).map(Some),
+        (a, b) => Ok(a.or(b)),
+    }
+}
+
+///`a - b`, where `None` is zero.
+fn difference(
+    a: Option<DynOperation>,
+    b: Option<DynOperation>,
+) -> Result<Option<DynOperation>, DynError> {
+    match (a, b) {
+        (Some(a), Some(b)) => op(DynOp::Sub, vec![a, b]).map(Some),
+        (None, Some(b)) => op(DynOp::Negate, vec![b]).map(Some),
+        (a, None) => Ok(a),
+    }
+}
+
+///Applies `f` to the derivative `d`, if it is not zero.
+fn map(
+    d: &Option<DynOperation>,
+    f: impl FnOnce(DynOperation) -> Result<DynOperation, DynError>,
+) -> Result<Option<DynOperation>, DynError> {
+    d.clone().map(f).transpose()
+}
+
+///Calculates the derivative of nodes in the direction of `seed`.
+struct Derivative<'a> {
+    variable: &'a str,
+    seed: DynValue,
+    ///Binding of the derivative of each bound value, by the id of the value. `None` if the value does not depend on the variable.
+    bindings: FxHashMap<u64, Option<DynOperation>>,
+    ///Smallest id that is neither used by the original graph, nor by a derivative.
+    next_id: u64,
+}
+
+impl<'a> Derivative<'a> {
+    ///Returns the derivative of `node`, or `None` if it does not depend on the variable.
+    fn of(&mut self, node: &DynOperation) -> Result<Option<DynOperation>, DynError> {
+        match &node.op {
+            DynOp::Variable { name, .. } if name == self.variable && node.ty == self.seed.ty() => {
+                return Ok(Some(DynOperation::constant(self.seed)));
+            }
+            DynOp::Constant(_) | DynOp::Variable { .. } => return Ok(None),
+            DynOp::Binding { id, .. } => return Ok(self.bindings.get(id).cloned().flatten()),
+            DynOp::Let(id) => return self.of_let(node, *id),
+            _ => {}
+        }
+        //Boolean, integer and matrix results are constant with respect to a float variable
+        let len = match float_len(&node.ty) {
+            Some(len) => len,
+            None => return Ok(None),
+        };
+
+        let mut args = node.args.clone();
+        let mut ds = args
+            .iter()
+            .map(|a| self.of(a))
+            .collect::<Result<Vec<_>, _>>()?;
+        if ds.iter().all(Option::is_none) {
+            return Ok(None);
+        }
+
+        //Splat scalars that are mixed with vectors, so that all arguments of component-wise operations are of the same type.
+        if len > 1 && is_componentwise(&node.op) {
+            for (arg, d) in args.iter_mut().zip(ds.iter_mut()) {
+                if float_len(&arg.ty) == Some(1) {
+                    *arg = op(DynOp::Splat(len as u32), vec![arg.clone()])?;
+                    *d = map(d, |d| op(DynOp::Splat(len as u32), vec![d]))?;
+                }
+            }
+        }
+
+        let d = self.rule(node, args, ds)?;
+
+        //Scalar derivatives of vectors, for instance of `v + s` where only `s` depends on the variable.
+        match d {
+            Some(d) if len > 1 && float_len(&d.ty) == Some(1) => {
+                op(DynOp::Splat(len as u32), vec![d]).map(Some)
+            }
+            d => Ok(d),
+        }
+    }
+
+    ///Derivative of `let id = value; body`, which is `let d = dvalue; let id = value; dbody`. Within `dbody` the derivative
+    /// of each binding of `id` is a binding of `d`.
+    fn of_let(&mut self, node: &DynOperation, id: u64) -> Result<Option<DynOperation>, DynError> {
+        let (value, body) = (&node.args[0], &node.args[1]);
+        let d_value = match self.of(value)? {
+            Some(d) => Some((self.binding(&d.ty)?, d)),
+            None => None,
+        };
+
+        let d_binding = d_value.as_ref().map(|((_, binding), _)| binding.clone());
+        let shadowed = self.bindings.insert(id, d_binding);
+        let d_body = self.of(body);
+        match shadowed {
+            Some(shadowed) => self.bindings.insert(id, shadowed),
+            None => self.bindings.remove(&id),
+        };
+
+        d_body?
+            .map(|d_body| {
+                let inner = op(DynOp::Let(id), vec![value.clone(), d_body])?;
+                match d_value {
+                    Some(((d_id, _), d_value)) => op(DynOp::Let(d_id), vec![d_value, inner]),
+                    None => Ok(inner),
+                }
+            })
+            .transpose()
+    }
+
+    ///Creates a binding of type `ty` with a new id. Returns the id and the binding.
+    fn binding(&mut self, ty: &SpvType) -> Result<(u64, DynOperation), DynError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let binding = op(DynOp::Binding { id, ty: ty.clone() }, Vec::new())?;
+        Ok((id, binding))
+    }
+
+    ///Binds the derivative `d` and calls `f` with its binding, so that `f` can use the derivative several times without
+    /// duplicating its graph.
+    fn shared(
+        &mut self,
+        d: DynOperation,
+        f: impl FnOnce(DynOperation) -> Result<DynOperation, DynError>,
+    ) -> Result<DynOperation, DynError> {
+        let (id, binding) = self.binding(&d.ty)?;
+        op(DynOp::Let(id), vec![d, f(binding)?])
+    }
+
+    fn rule(
+        &mut self,
+        node: &DynOperation,
+        args: Vec<DynOperation>,
+        ds: Vec<Option<DynOperation>>,
+    ) -> Result<Option<DynOperation>, DynError> {
+        use DynOp::*;
+
+        //Derivative of argument `i`, zero if it does not depend on the variable
+        let dz = |i: usize| {
+            ds[i]
+                .clone()
+                .unwrap_or_else(|| constant_like(0.0, &args[i].ty))
+        };
+        let a = || args[0].clone();
+        let b = || args[1].clone();
+        let da = &ds[0];
+        let db = ds.get(1).cloned().flatten();
+
+        let d = match &node.op {
+            Add => sum(da.clone(), db)?,
+            Sub => difference(da.clone(), db)?,
+            Mul => sum(
+                map(da, |d| op(Mul, vec![d, b()]))?,
+                map(&db, |d| op(Mul, vec![a(), d]))?,
+            )?,
+            //d/b - a * db / (b * b)
+            Div => difference(
+                map(da, |d| op(Div, vec![d, b()]))?,
+                map(&db, |d| {
+                    op(Div, vec![op(Mul, vec![a(), d])?, op(Mul, vec![b(), b()])?])
+                })?,
+            )?,
+            //a - b * trunc(a / b), or floor for the modulo
+            Remainder | Modulo => {
+                let round = if node.op == Remainder { Trunc } else { Floor };
+                let quotient = op(round, vec![op(Div, vec![a(), b()])?])?;
+                difference(da.clone(), map(&db, |d| op(Mul, vec![d, quotient]))?)?
+            }
+            Negate => map(da, |d| op(Negate, vec![d]))?,
+            //step(b, a) is 1 where a >= b
+            Min => Some(op(Mix, vec![dz(0), dz(1), op(Step, vec![b(), a()])?])?),
+            Max => Some(op(Mix, vec![dz(1), dz(0), op(Step, vec![b(), a()])?])?),
+            Abs => map(da, |d| op(Mul, vec![op(Sign, vec![a()])?, d]))?,
+            Square => map(da, |d| op(Mul, vec![op(Mul, vec![c(2.0), a()])?, d]))?,
+            Sqrt => map(da, |d| {
+                op(Div, vec![d, op(Mul, vec![c(2.0), op(Sqrt, vec![a()])?])?])
+            })?,
+            Sine => map(da, |d| op(Mul, vec![op(Cosine, vec![a()])?, d]))?,
+            Cosine => map(da, |d| {
+                op(Mul, vec![op(Negate, vec![op(Sine, vec![a()])?])?, d])
+            })?,
+            Tangent => map(da, |d| {
+                let cos = op(Cosine, vec![a()])?;
+                op(Div, vec![d, op(Mul, vec![cos.clone(), cos])?])
+            })?,
+            Floor | Ceil | Round | Trunc | Sign | Step => None,
+            Fract => da.clone(),
+            Exp => map(da, |d| op(Mul, vec![op(Exp, vec![a()])?, d]))?,
+            Exp2 => map(da, |d| {
+                let factor = op(Mul, vec![op(Exp2, vec![a()])?, c(LN_2)])?;
+                op(Mul, vec![factor, d])
+            })?,
+            Log => map(da, |d| op(Div, vec![d, a()]))?,
+            Log2 => map(da, |d| op(Div, vec![d, op(Mul, vec![a(), c(LN_2)])?]))?,
+            //-0.5 * inversesqrt(a) / a
+            InverseSqrt => map(da, |d| {
+                let factor = op(
+                    Div,
+                    vec![op(Mul, vec![c(-0.5), op(InverseSqrt, vec![a()])?])?, a()],
+                )?;
+                op(Mul, vec![factor, d])
+            })?,
+            ArcSine | ArcCosine => {
+                //1 / sqrt(1 - a²)
+                let one_minus_sq = op(
+                    Sub,
+                    vec![constant_like(1.0, &args[0].ty), op(Mul, vec![a(), a()])?],
+                )?;
+                let factor = op(InverseSqrt, vec![one_minus_sq])?;
+                let d = map(da, |d| op(Mul, vec![factor, d]))?;
+                if node.op == ArcCosine {
+                    map(&d, |d| op(Negate, vec![d]))?
+                } else {
+                    d
+                }
+            }
+            ArcTangent => map(da, |d| {
+                let one_plus_sq = op(
+                    Add,
+                    vec![constant_like(1.0, &args[0].ty), op(Mul, vec![a(), a()])?],
+                )?;
+                op(Div, vec![d, one_plus_sq])
+            })?,
+            //(x * dy - y * dx) / (x² + y²)
+            ArcTangent2 => {
+                let (y, x) = (a(), b());
+                let numerator = difference(
+                    map(da, |d| op(Mul, vec![x.clone(), d]))?,
+                    map(&db, |d| op(Mul, vec![y.clone(), d]))?,
+                )?;
+                let denominator = op(
+                    Add,
+                    vec![op(Mul, vec![x.clone(), x])?, op(Mul, vec![y.clone(), y])?],
+                )?;
+                map(&numerator, |n| op(Div, vec![n, denominator]))?
+            }
+            Pow => match &db {
+                //b * pow(a, b - 1)
+                None => map(da, |d| {
+                    let exponent = op(Sub, vec![b(), constant_like(1.0, &args[1].ty)])?;
+                    let factor = op(Mul, vec![b(), op(Pow, vec![a(), exponent])?])?;
+                    op(Mul, vec![factor, d])
+                })?,
+                //pow(a, b) * (db * log(a) + b * da / a)
+                Some(db) => {
+                    let inner = sum(
+                        Some(op(Mul, vec![db.clone(), op(Log, vec![a()])?])?),
+                        map(da, |d| op(Div, vec![op(Mul, vec![b(), d])?, a()]))?,
+                    )?;
+                    map(&inner, |i| op(Mul, vec![op(Pow, vec![a(), b()])?, i]))?
+                }
+            },
+            //The derivative of the bound that is active, or of x.
+            Clamp => {
+                let (x, min, max) = (a(), b(), args[2].clone());
+                let below = op(Step, vec![x.clone(), min])?;
+                let above = op(Step, vec![max, x])?;
+                let inner = op(Mix, vec![dz(0), dz(1), below])?;
+                Some(op(Mix, vec![inner, dz(2), above])?)
+            }
+            //da * (1 - t) + db * t + (b - a) * dt
+            Mix => {
+                let t = args[2].clone();
+                let one_minus_t = op(Sub, vec![constant_like(1.0, &t.ty), t.clone()])?;
+                let blend = sum(
+                    map(da, |d| op(Mul, vec![d, one_minus_t]))?,
+                    map(&db, |d| op(Mul, vec![d, t]))?,
+                )?;
+                sum(
+                    blend,
+                    map(&ds[2], |d| op(Mul, vec![op(Sub, vec![b(), a()])?, d]))?,
+                )?
+            }
+            //6 * t * (1 - t) * du, where t = clamp(u, 0, 1) and u = (x - e0) / (e1 - e0).
+            //The factor is zero wherever u is clamped, therefore the clamp needs no derivative of its own.
+            SmoothStep => {
+                let ty = &args[2].ty;
+                let (e0, e1, x) = (a(), b(), args[2].clone());
+                let width = op(Sub, vec![e1, e0.clone()])?;
+                let u = op(Div, vec![op(Sub, vec![x, e0])?, width.clone()])?;
+                //(dx - (1 - u) * de0 - u * de1) / (e1 - e0)
+                let one_minus_u = op(Sub, vec![constant_like(1.0, ty), u.clone()])?;
+                let numerator = difference(
+                    difference(ds[2].clone(), map(da, |d| op(Mul, vec![one_minus_u, d]))?)?,
+                    map(&db, |d| op(Mul, vec![u.clone(), d]))?,
+                )?;
+                map(&numerator, |n| {
+                    let t = op(
+                        Clamp,
+                        vec![u, constant_like(0.0, ty), constant_like(1.0, ty)],
+                    )?;
+                    let one_minus_t = op(Sub, vec![constant_like(1.0, ty), t.clone()])?;
+                    let factor = op(
+                        Mul,
+                        vec![op(Mul, vec![constant_like(6.0, ty), t])?, one_minus_t],
+                    )?;
+                    op(Mul, vec![factor, op(Div, vec![n, width])?])
+                })?
+            }
+            //da * b + a * db + dc
+            Fma => sum(
+                sum(
+                    map(da, |d| op(Mul, vec![d, b()]))?,
+                    map(&db, |d| op(Mul, vec![a(), d]))?,
+                )?,
+                ds[2].clone(),
+            )?,
+            Dot => sum(
+                map(da, |d| op(Dot, vec![d, b()]))?,
+                map(&db, |d| op(Dot, vec![a(), d]))?,
+            )?,
+            //dot(a - b, da - db) / length(a - b)
+            Distance => map(&difference(da.clone(), db)?, |d| {
+                let v = op(Sub, vec![a(), b()])?;
+                op(
+                    Div,
+                    vec![op(Dot, vec![v.clone(), d])?, op(Length, vec![v])?],
+                )
+            })?,
+            //dot(a, da) / length(a)
+            Length => map(da, |d| {
+                op(Div, vec![op(Dot, vec![a(), d])?, op(Length, vec![a()])?])
+            })?,
+            //(da - n * dot(n, da)) / length(a)
+            Normalize => map(da, |d| {
+                self.shared(d, |d| {
+                    let n = op(Normalize, vec![a()])?;
+                    let projected = op(Mul, vec![n.clone(), op(Dot, vec![n, d.clone()])?])?;
+                    op(
+                        Div,
+                        vec![op(Sub, vec![d, projected])?, op(Length, vec![a()])?],
+                    )
+                })
+            })?,
+            Cross => sum(
+                map(da, |d| op(Cross, vec![d, b()]))?,
+                map(&db, |d| op(Cross, vec![a(), d]))?,
+            )?,
+            //reflect(di, n) - 2 * (dot(dn, i) * n + dot(n, i) * dn), for i - 2 * dot(n, i) * n, which is linear in i
+            Reflect => {
+                let (i, n) = (a(), b());
+                let d_normal = map(&db, |d| {
+                    self.shared(d, |d| {
+                        let scaled = op(
+                            Add,
+                            vec![
+                                op(Mul, vec![op(Dot, vec![d.clone(), i.clone()])?, n.clone()])?,
+                                op(Mul, vec![op(Dot, vec![n.clone(), i])?, d])?,
+                            ],
+                        )?;
+                        op(Mul, vec![c(2.0), scaled])
+                    })
+                })?;
+                difference(map(da, |d| op(Reflect, vec![d, n]))?, d_normal)?
+            }
+            //dn * (dot(nref, i) < 0 ? 1 : -1)
+            FaceForward => map(da, |d| {
+                let condition = op(Less, vec![op(Dot, vec![args[2].clone(), b()])?, c(0.0)])?;
+                let sign = op(Select, vec![condition, c(1.0), c(-1.0)])?;
+                op(Mul, vec![sign, d])
+            })?,
+            Element(_) | Splat(_) | Swizzle(_) => map(da, |d| op(node.op.clone(), vec![d]))?,
+            Construct => Some(op(Construct, (0..args.len()).map(dz).collect())?),
+            Select => Some(op(Select, vec![a(), dz(1), dz(2)])?),
+            other => {
+                return Err(DynError::NotDifferentiable {
+                    op: Box::new(other.clone()),
+                })
+            }
+        };
+        Ok(d)
+    }
+}
+
+///True for operations that are applied per component, and accept `f32` arguments for vectors.
+fn is_componentwise(op: &DynOp) -> bool {
+    matches!(
+        op,
+        DynOp::Add
+            | DynOp::Sub
+            | DynOp::Mul
+            | DynOp::Div
+            | DynOp::Remainder
+            | DynOp::Modulo
+            | DynOp::Min
+            | DynOp::Max
+            | DynOp::Pow
+            | DynOp::ArcTangent2
+            | DynOp::Clamp
+            | DynOp::Mix
+            | DynOp::Step
+            | DynOp::SmoothStep
+            | DynOp::Fma
+    )
+}
@@ -0,0 +1,569 @@
+//! Type-erased operations, whose structure and types are only known at runtime. For instance a function that is built in a node editor.
+//!
+//! A [DynOperation] is a node in an operation graph. It consists of a [DynOp] that describes what is calculated and its argument
+//! nodes. The result type of each node is checked when the node is assembled via [DynOperation::new]. Afterwards the graph can
+//! be serialized and evaluated like any other [Operation]. Each node is implemented by the statically typed operation of the
+//! same name, so both behave exactly the same.
+//!
+//! # Example
+//! ```rust
+//! use algae::{dynamic::{DynOp, DynOperation, DynValue}, glam::Vec3, Evaluator};
+//!
+//! //length(p) - radius
+//! let mut sphere = DynOperation::new(
+//!     DynOp::Sub,
+//!     vec![
+//!         DynOperation::new(DynOp::Length, vec![DynOperation::variable("p", Vec3::ZERO)]).unwrap(),
+//!         DynOperation::constant(1.0f32),
+//!     ],
+//! )
+//! .unwrap();
+//!
+//! let mut evaluator = Evaluator::new();
+//! evaluator.set_variable("p", Vec3::new(0.0, 3.0, 0.0));
+//! assert_eq!(evaluator.evaluate_dyn(&mut sphere), DynValue::F32(2.0));
+//! ```
+//!
+//! A result that is needed several times can be bound via [DynOp::Let] and accessed via [DynOp::Binding], instead of
+//! duplicating its sub graph.
+//!
+//! # Saving and loading
+//! With the `serialization` feature enabled, operation graphs can be saved to, and loaded from any serde data format, like RON or JSON.
+//! Each node is saved as its operation and arguments, including all constant values, variable names and default values.
+//! Loaded nodes are type checked again, like when assembling them via [DynOperation::new].
+//!
+//! # Parsing
+//! Operation graphs can also be parsed from formulas like `length(p) - 1.0` via a [Parser]. See the [parser] module for the
+//! supported grammar.
+//!
+//! # Derivatives
+//! [Gradient] builds the analytic gradient of a scalar operation graph with respect to one of its variables. The gradient is
+//! a graph itself, for instance to calculate the exact normal of a signed distance field.
+
+use std::{any::type_name, error::Error, marker::PhantomData};
+
+use fxhash::FxHashMap;
+use rspirv::spirv::Word;
+
+use crate::{
+    spv_fi::{IntoSpvType, SpvType},
+    DataId, Evaluator, Operation, SerializeError, Serializer,
+};
+
+mod dispatch;
+mod gradient;
+pub mod parser;
+mod value;
+pub use gradient::Gradient;
+pub use parser::{ParseError, ParseErrorKind, Parser};
+pub use value::DynValue;
+
+///Operation of a [DynOperation] node. The arguments are the node's child nodes.
+///
+/// Unless noted otherwise an operation is implemented by the typed operation of the same name, for the same types.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize, serde::Deserialize))]
+pub enum DynOp {
+    ///The constant value. Takes no arguments.
+    Constant(DynValue),
+    ///Runtime variable with the given name and default value. Takes no arguments.
+    Variable {
+        name: String,
+        default: DynValue,
+    },
+    ///Calculates the first argument once and binds it to the id for the second argument, which is the result. Within the
+    /// second argument the value is accessed via [Binding](DynOp::Binding) nodes of the same id, therefore it can be used several
+    /// times without duplicating its sub graph. An inner `Let` of the same id shadows the outer one.
+    Let(u64),
+    ///The value of type `ty` that is bound to `id` by an enclosing [Let](DynOp::Let). Takes no arguments.
+    Binding {
+        id: u64,
+        ty: SpvType,
+    },
+
+    ///`a + b`. Also adds a `f32` to each component of a float vector.
+    Add,
+    ///`a - b`. Also subtracts a `f32` from each component of a float vector.
+    Sub,
+    ///`a * b`. Also scales a float vector by a `f32`, and implements the matrix-matrix, matrix-vector and vector-matrix products.
+    Mul,
+    ///`a / b`. Also divides each component of a float vector by a `f32`.
+    Div,
+    Remainder,
+    Modulo,
+    ///`-a`
+    Negate,
+    Min,
+    Max,
+    Abs,
+    Square,
+    Sqrt,
+    Sine,
+    Cosine,
+    Tangent,
+
+    Floor,
+    Ceil,
+    Fract,
+    Round,
+    Trunc,
+    Sign,
+    Exp,
+    Exp2,
+    Log,
+    Log2,
+    InverseSqrt,
+    ArcSine,
+    ArcCosine,
+    ArcTangent,
+    ///`atan2(y, x)`
+    ArcTangent2,
+    ///`pow(base, exponent)`
+    Pow,
+    ///`step(edge, x)`
+    Step,
+    ///`clamp(x, min, max)`
+    Clamp,
+    ///`mix(a, b, t)`
+    Mix,
+    ///`smoothstep(edge0, edge1, x)`
+    SmoothStep,
+    ///`fma(a, b, c)`
+    Fma,
+
+    Dot,
+    Distance,
+    Cross,
+    ///`reflect(incident, normal)`
+    Reflect,
+    ///`refract(incident, normal, eta)`
+    Refract,
+    ///`faceforward(normal, incident, reference)`
+    FaceForward,
+    Length,
+    Normalize,
+    ///Selects the given component of a float vector.
+    Element(u32),
+    ///Constructs a float vector with one `f32` argument per component.
+    Construct,
+    ///Constructs a float vector of the given length, where each component is the `f32` argument.
+    Splat(u32),
+    ///Rearranges the components of a float vector, see [Swizzle](crate::operations::Swizzle).
+    Swizzle(Vec<u32>),
+
+    Transpose,
+    Determinant,
+    Inverse,
+
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+    And,
+    Or,
+    Not,
+    Any,
+    All,
+    ///`select(condition, true_value, false_value)`. There is no dynamic `IfElse`, since the arguments of a node are always
+    /// calculated before the node itself.
+    Select,
+}
+
+impl DynOp {
+    ///Returns the number of arguments the operation takes, or `None` if it takes a variable number of arguments.
+    pub fn arity(&self) -> Option<usize> {
+        match self {
+            DynOp::Constant(_) | DynOp::Variable { .. } | DynOp::Binding { .. } => Some(0),
+            DynOp::Construct => None,
+            DynOp::Negate
+            | DynOp::Abs
+            | DynOp::Square
+            | DynOp::Sqrt
+            | DynOp::Sine
+            | DynOp::Cosine
+            | DynOp::Tangent
+            | DynOp::Floor
+            | DynOp::Ceil
+            | DynOp::Fract
+            | DynOp::Round
+            | DynOp::Trunc
+            | DynOp::Sign
+            | DynOp::Exp
+            | DynOp::Exp2
+            | DynOp::Log
+            | DynOp::Log2
+            | DynOp::InverseSqrt
+            | DynOp::ArcSine
+            | DynOp::ArcCosine
+            | DynOp::ArcTangent
+            | DynOp::Length
+            | DynOp::Normalize
+            | DynOp::Element(_)
+            | DynOp::Splat(_)
+            | DynOp::Swizzle(_)
+            | DynOp::Transpose
+            | DynOp::Determinant
+            | DynOp::Inverse
+            | DynOp::Not
+            | DynOp::Any
+            | DynOp::All => Some(1),
+            DynOp::Clamp
+            | DynOp::Mix
+            | DynOp::SmoothStep
+            | DynOp::Fma
+            | DynOp::Refract
+            | DynOp::FaceForward
+            | DynOp::Select => Some(3),
+            _ => Some(2),
+        }
+    }
+}
+
+///Errors that can occur while assembling a [DynOperation].
+#[derive(Clone, Debug)]
+pub enum DynError {
+    ///Occurs if an operation gets the wrong number of arguments.
+    ArgumentCount {
+        op: Box<DynOp>,
+        expected: usize,
+        found: usize,
+    },
+    ///Occurs if an operation is not implemented for the types of its arguments, for instance when adding a `f32` to an `i32`, or
+    /// when swizzling a component that the vector does not have.
+    UnsupportedTypes {
+        op: Box<DynOp>,
+        arguments: Vec<SpvType>,
+    },
+    ///Occurs if a [DynOperation] is converted into a typed operation, but it results in another type.
+    TypeMismatch { expected: SpvType, found: SpvType },
+    ///Occurs if an operation is differentiated with respect to a variable it does not contain.
+    UnknownVariable(String),
+    ///Occurs if the derivative of an operation is not known.
+    NotDifferentiable { op: Box<DynOp> },
+}
+
+impl std::fmt::Display for DynError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DynError::ArgumentCount {
+                op,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Operation {:?} takes {} arguments, but got {}",
+                op, expected, found
+            ),
+            DynError::UnsupportedTypes { op, arguments } => write!(
+                f,
+                "Operation {:?} is not implemented for arguments of type {:?}",
+                op, arguments
+            ),
+            DynError::TypeMismatch { expected, found } => write!(
+                f,
+                "Expected operation of type {:?}, but found {:?}",
+                expected, found
+            ),
+            DynError::UnknownVariable(name) => write!(f, "Unknown variable {}", name),
+            DynError::NotDifferentiable { op } => {
+                write!(f, "Operation {:?} is not differentiable", op)
+            }
+        }
+    }
+}
+
+impl Error for DynError {}
+
+///Type-erased data id. Counterpart to [DataId] that carries its type at runtime.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DynDataId {
+    pub id: Word,
+    pub ty: SpvType,
+}
+
+///Node of a type-erased operation graph. See the [module level documentation](self).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serialization", serde(try_from = "DynNode"))]
+pub struct DynOperation {
+    op: DynOp,
+    args: Vec<DynOperation>,
+    ///Derived from `op` and `args`, therefore not saved.
+    #[cfg_attr(feature = "serialization", serde(skip))]
+    ty: SpvType,
+}
+
+///Unchecked [DynOperation] as it is loaded. Converted via [DynOperation::new].
+#[cfg(feature = "serialization")]
+#[derive(serde::Deserialize)]
+struct DynNode {
+    op: DynOp,
+    args: Vec<DynOperation>,
+}
+
+#[cfg(feature = "serialization")]
+impl TryFrom<DynNode> for DynOperation {
+    type Error = DynError;
+    fn try_from(node: DynNode) -> Result<Self, Self::Error> {
+        DynOperation::new(node.op, node.args)
+    }
+}
+
+impl DynOperation {
+    ///Assembles the node that calculates `op` of `args`. Returns an error if `op` is not implemented for the argument's types.
+    pub fn new(op: DynOp, args: Vec<DynOperation>) -> Result<Self, DynError> {
+        if let Some(expected) = op.arity() {
+            if args.len() != expected {
+                return Err(DynError::ArgumentCount {
+                    op: Box::new(op),
+                    expected,
+                    found: args.len(),
+                });
+            }
+        }
+
+        match &op {
+            DynOp::Binding { ty, .. } => {
+                return Ok(DynOperation {
+                    ty: ty.clone(),
+                    op,
+                    args,
+                })
+            }
+            DynOp::Let(id) => {
+                let (value, body) = (&args[0], &args[1]);
+                if let Some(found) = mismatched_binding(body, *id, &value.ty) {
+                    return Err(DynError::TypeMismatch {
+                        expected: value.ty.clone(),
+                        found: found.clone(),
+                    });
+                }
+                return Ok(DynOperation {
+                    ty: body.ty.clone(),
+                    op,
+                    args,
+                });
+            }
+            _ => {}
+        }
+
+        //Type check by looking up the typed implementation
+        let arg_ids: Vec<DynDataId> = args
+            .iter()
+            .map(|a| DynDataId {
+                id: 0,
+                ty: a.ty.clone(),
+            })
+            .collect();
+        match dispatch::build(&op, &arg_ids) {
+            Some(typed) => Ok(DynOperation {
+                ty: typed.ty(),
+                op,
+                args,
+            }),
+            None => Err(DynError::UnsupportedTypes {
+                op: Box::new(op),
+                arguments: arg_ids.into_iter().map(|a| a.ty).collect(),
+            }),
+        }
+    }
+
+    ///Creates a node that returns the constant `value`.
+    pub fn constant(value: impl Into<DynValue>) -> Self {
+        let value = value.into();
+        DynOperation {
+            ty: value.ty(),
+            op: DynOp::Constant(value),
+            args: Vec::new(),
+        }
+    }
+
+    ///Creates a node that loads the runtime variable `name`, or its `default` value if it is not set.
+    pub fn variable(name: &str, default: impl Into<DynValue>) -> Self {
+        let default = default.into();
+        DynOperation {
+            ty: default.ty(),
+            op: DynOp::Variable {
+                name: String::from(name),
+                default,
+            },
+            args: Vec::new(),
+        }
+    }
+
+    ///Returns the node's operation.
+    pub fn op(&self) -> &DynOp {
+        &self.op
+    }
+
+    ///Returns the node's arguments.
+    pub fn args(&self) -> &[DynOperation] {
+        &self.args
+    }
+
+    ///Returns the type of the node's result.
+    pub fn ty(&self) -> &SpvType {
+        &self.ty
+    }
+
+    ///Converts the node into a statically typed operation. Returns an error if the node does not result in a `T`.
+    pub fn into_typed<I, T: IntoSpvType>(self) -> Result<DynTyped<I, T>, DynError> {
+        let expected = T::into_spv_type();
+        if self.ty != expected {
+            return Err(DynError::TypeMismatch {
+                expected,
+                found: self.ty,
+            });
+        }
+
+        Ok(DynTyped {
+            inner: self,
+            ty: PhantomData,
+        })
+    }
+
+    ///Serializes the node, where `bindings` are the values that are bound by the enclosing [Let](DynOp::Let)s.
+    fn serialize_bound(
+        &mut self,
+        serializer: &mut Serializer,
+        bindings: &mut FxHashMap<u64, DynDataId>,
+    ) -> Result<DynDataId, SerializeError> {
+        match &self.op {
+            DynOp::Binding { id, .. } => return bound(bindings, *id),
+            DynOp::Let(id) => {
+                let id = *id;
+                let value = self.args[0].serialize_bound(serializer, bindings)?;
+                let shadowed = bindings.insert(id, value);
+                let result = self.args[1].serialize_bound(serializer, bindings);
+                unbind(bindings, id, shadowed);
+                return result;
+            }
+            _ => {}
+        }
+
+        let args = self
+            .args
+            .iter_mut()
+            .map(|a| a.serialize_bound(serializer, bindings))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut typed =
+            dispatch::build(&self.op, &args).expect("DynOperation was not type checked");
+        Ok(DynDataId {
+            id: typed.serialize(serializer)?,
+            ty: self.ty.clone(),
+        })
+    }
+
+    ///Evaluates the node, where `bindings` are the values that are bound by the enclosing [Let](DynOp::Let)s.
+    fn evaluate_bound(
+        &mut self,
+        evaluator: &mut Evaluator,
+        bindings: &mut FxHashMap<u64, DynDataId>,
+    ) -> DynDataId {
+        match &self.op {
+            DynOp::Binding { id, .. } => {
+                return bound(bindings, *id).unwrap_or_else(|e| panic!("{}", e))
+            }
+            DynOp::Let(id) => {
+                let id = *id;
+                let value = self.args[0].evaluate_bound(evaluator, bindings);
+                let shadowed = bindings.insert(id, value);
+                let result = self.args[1].evaluate_bound(evaluator, bindings);
+                unbind(bindings, id, shadowed);
+                return result;
+            }
+            _ => {}
+        }
+
+        let args: Vec<DynDataId> = self
+            .args
+            .iter_mut()
+            .map(|a| a.evaluate_bound(evaluator, bindings))
+            .collect();
+        let mut typed =
+            dispatch::build(&self.op, &args).expect("DynOperation was not type checked");
+        DynDataId {
+            id: typed.evaluate(evaluator),
+            ty: self.ty.clone(),
+        }
+    }
+}
+
+///Returns the type of the first binding of `id` within `node` that is not of type `ty`. Bindings within the body of an inner
+/// [Let](DynOp::Let) of the same id are skipped, since they refer to that value.
+fn mismatched_binding<'a>(node: &'a DynOperation, id: u64, ty: &SpvType) -> Option<&'a SpvType> {
+    match &node.op {
+        DynOp::Binding { id: i, ty: t } if *i == id && t != ty => Some(t),
+        DynOp::Let(i) if *i == id => mismatched_binding(&node.args[0], id, ty),
+        _ => node.args.iter().find_map(|a| mismatched_binding(a, id, ty)),
+    }
+}
+
+///Returns the value that is bound to `id`.
+fn bound(bindings: &FxHashMap<u64, DynDataId>, id: u64) -> Result<DynDataId, SerializeError> {
+    bindings
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| SerializeError::UnknownResult {
+            operation: type_name::<DynOperation>().to_string(),
+            name: format!("binding {}", id),
+        })
+}
+
+///Removes the binding of `id` after the body of its [Let](DynOp::Let), and restores the `shadowed` outer binding.
+fn unbind(bindings: &mut FxHashMap<u64, DynDataId>, id: u64, shadowed: Option<DynDataId>) {
+    match shadowed {
+        Some(value) => bindings.insert(id, value),
+        None => bindings.remove(&id),
+    };
+}
+
+impl Operation for DynOperation {
+    type Input = ();
+    type Output = DynDataId;
+
+    fn serialize(
+        &mut self,
+        serializer: &mut Serializer,
+        _input: Self::Input,
+    ) -> Result<Self::Output, SerializeError> {
+        self.serialize_bound(serializer, &mut FxHashMap::default())
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, _input: Self::Input) -> Self::Output {
+        self.evaluate_bound(evaluator, &mut FxHashMap::default())
+    }
+}
+
+///A [DynOperation] that is known to result in a `T`. Can be used within statically typed operations, the input `I` is ignored.
+/// Created via [DynOperation::into_typed].
+pub struct DynTyped<I, T> {
+    inner: DynOperation,
+    ty: PhantomData<(I, T)>,
+}
+
+impl<I, T> DynTyped<I, T> {
+    ///Returns the type-erased operation.
+    pub fn into_inner(self) -> DynOperation {
+        self.inner
+    }
+}
+
+impl<I, T> Operation for DynTyped<I, T> {
+    type Input = I;
+    type Output = DataId<T>;
+
+    fn serialize(
+        &mut self,
+        serializer: &mut Serializer,
+        _input: Self::Input,
+    ) -> Result<Self::Output, SerializeError> {
+        Ok(DataId::from(self.inner.serialize(serializer, ())?.id))
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, _input: Self::Input) -> Self::Output {
+        DataId::from(self.inner.evaluate(evaluator, ()).id)
+    }
+}
@@ -0,0 +1,607 @@
+//! Runtime parser of formulas like `length(max(abs(p) - b, 0.0)) + min(max(d.x, d.y), 0.0)`.
+//!
+//! The grammar, from the lowest to the highest precedence:
+//! - `a + b`, `a - b`
+//! - `a * b`, `a / b`, `a % b`
+//! - `-a`
+//! - `a.x`, `a.xzy`: component access and swizzles, using `xyzw` or `rgba`.
+//! - Numbers like `1`, `0.5` or `1e-3`, which are `f32` constants, variables, function calls like `max(a, b)`, `(a)` and `|a|`
+//!   for the absolute value.
+//!
+//! Functions use GLSL's names, `vec2`, `vec3` and `vec4` construct a vector from one `f32` per component, or splat a single `f32`.
+//!
+//! Parentheses, absolute values, function calls and negations can be nested at most 256 levels deep.
+
+use std::error::Error;
+
+use fxhash::FxHashMap;
+
+use super::{dispatch::vector_len, DynError, DynOp, DynOperation, DynValue};
+use crate::spv_fi::IntoSpvType;
+
+///Kind of a [ParseError].
+#[derive(Clone, Debug)]
+pub enum ParseErrorKind {
+    ///A character that is not part of the grammar.
+    UnexpectedCharacter(char),
+    ///A token that is not valid at its position. Contains a description of what was expected instead.
+    UnexpectedToken {
+        found: String,
+        expected: &'static str,
+    },
+    ///The formula ended, but more was expected.
+    UnexpectedEnd { expected: &'static str },
+    ///A number that can not be parsed into a `f32`.
+    InvalidNumber(String),
+    ///A variable that was not declared via [Parser::with_variable].
+    UnknownVariable(String),
+    ///A function that does not exist.
+    UnknownFunction(String),
+    ///A swizzle or component access with characters other than `xyzw` or `rgba`.
+    InvalidSwizzle(String),
+    ///The parsed operation does not type check.
+    Type(DynError),
+    ///Parentheses, absolute values, function calls or negations are nested deeper than the given limit.
+    TooDeep(usize),
+}
+
+///Error that occurs while parsing a formula. `line` and `column` are 1-based and point to the character where the error occurred.
+#[derive(Clone, Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        match &self.kind {
+            ParseErrorKind::UnexpectedCharacter(c) => write!(f, "Unexpected character '{}'", c),
+            ParseErrorKind::UnexpectedToken { found, expected } => {
+                write!(f, "Expected {}, found '{}'", expected, found)
+            }
+            ParseErrorKind::UnexpectedEnd { expected } => {
+                write!(f, "Expected {}, found end of formula", expected)
+            }
+            ParseErrorKind::InvalidNumber(n) => write!(f, "Invalid number '{}'", n),
+            ParseErrorKind::UnknownVariable(name) => write!(f, "Unknown variable '{}'", name),
+            ParseErrorKind::UnknownFunction(name) => write!(f, "Unknown function '{}'", name),
+            ParseErrorKind::InvalidSwizzle(s) => write!(f, "Invalid swizzle '{}'", s),
+            ParseErrorKind::Type(e) => write!(f, "{}", e),
+            ParseErrorKind::TooDeep(limit) => {
+                write!(f, "Formula is nested deeper than {} levels", limit)
+            }
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(String),
+    Ident(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Dot,
+    Comma,
+    Pipe,
+    OpenParen,
+    CloseParen,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(s) | Token::Ident(s) => write!(f, "{}", s),
+            Token::Plus => write!(f, "+"),
+            Token::Minus => write!(f, "-"),
+            Token::Star => write!(f, "*"),
+            Token::Slash => write!(f, "/"),
+            Token::Percent => write!(f, "%"),
+            Token::Dot => write!(f, "."),
+            Token::Comma => write!(f, ","),
+            Token::Pipe => write!(f, "|"),
+            Token::OpenParen => write!(f, "("),
+            Token::CloseParen => write!(f, ")"),
+        }
+    }
+}
+
+///Token and its line and column.
+type Spanned = (Token, usize, usize);
+
+fn tokenize(source: &str) -> Result<Vec<Spanned>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    let (mut line, mut column) = (1, 1);
+
+    while let Some(&(offset, c)) = chars.peek() {
+        let (start_line, start_column) = (line, column);
+        let token = match c {
+            '\n' => {
+                chars.next();
+                line += 1;
+                column = 1;
+                continue;
+            }
+            c if c.is_whitespace() => {
+                chars.next();
+                column += 1;
+                continue;
+            }
+            //Numbers, including ones with a leading dot like `.5`
+            c if c.is_ascii_digit()
+                || (c == '.' && source[offset + 1..].starts_with(|c: char| c.is_ascii_digit())) =>
+            {
+                let mut number = String::new();
+                while let Some(&(_, c)) = chars.peek() {
+                    //Exponent sign, like 1e-3
+                    let is_exp_sign = (c == '-' || c == '+') && number.ends_with(['e', 'E']);
+                    if c.is_ascii_alphanumeric() || c == '.' || is_exp_sign {
+                        number.push(c);
+                        chars.next();
+                        column += 1;
+                    } else {
+                        break;
+                    }
+                }
+                Token::Number(number)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(&(_, c)) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' {
+                        ident.push(c);
+                        chars.next();
+                        column += 1;
+                    } else {
+                        break;
+                    }
+                }
+                Token::Ident(ident)
+            }
+            _ => {
+                let token = match c {
+                    '+' => Token::Plus,
+                    '-' => Token::Minus,
+                    '*' => Token::Star,
+                    '/' => Token::Slash,
+                    '%' => Token::Percent,
+                    '.' => Token::Dot,
+                    ',' => Token::Comma,
+                    '|' => Token::Pipe,
+                    '(' => Token::OpenParen,
+                    ')' => Token::CloseParen,
+                    _ => {
+                        return Err(ParseError {
+                            line,
+                            column,
+                            kind: ParseErrorKind::UnexpectedCharacter(c),
+                        })
+                    }
+                };
+                chars.next();
+                column += 1;
+                token
+            }
+        };
+        tokens.push((token, start_line, start_column));
+    }
+
+    Ok(tokens)
+}
+
+///Returns the operation of the function `name`.
+fn function(name: &str) -> Option<DynOp> {
+    let f = match name {
+        "abs" => DynOp::Abs,
+        "sqrt" => DynOp::Sqrt,
+        "inversesqrt" => DynOp::InverseSqrt,
+        "sin" => DynOp::Sine,
+        "cos" => DynOp::Cosine,
+        "tan" => DynOp::Tangent,
+        "asin" => DynOp::ArcSine,
+        "acos" => DynOp::ArcCosine,
+        "atan" => DynOp::ArcTangent,
+        "atan2" => DynOp::ArcTangent2,
+        "floor" => DynOp::Floor,
+        "ceil" => DynOp::Ceil,
+        "fract" => DynOp::Fract,
+        "round" => DynOp::Round,
+        "trunc" => DynOp::Trunc,
+        "sign" => DynOp::Sign,
+        "exp" => DynOp::Exp,
+        "exp2" => DynOp::Exp2,
+        "log" => DynOp::Log,
+        "log2" => DynOp::Log2,
+        "pow" => DynOp::Pow,
+        "mod" => DynOp::Modulo,
+        "min" => DynOp::Min,
+        "max" => DynOp::Max,
+        "step" => DynOp::Step,
+        "clamp" => DynOp::Clamp,
+        "mix" => DynOp::Mix,
+        "smoothstep" => DynOp::SmoothStep,
+        "fma" => DynOp::Fma,
+        "dot" => DynOp::Dot,
+        "cross" => DynOp::Cross,
+        "distance" => DynOp::Distance,
+        "length" => DynOp::Length,
+        "normalize" => DynOp::Normalize,
+        "reflect" => DynOp::Reflect,
+        "refract" => DynOp::Refract,
+        "faceforward" => DynOp::FaceForward,
+        "transpose" => DynOp::Transpose,
+        "determinant" => DynOp::Determinant,
+        "inverse" => DynOp::Inverse,
+        _ => return None,
+    };
+    Some(f)
+}
+
+///Splats `f32` arguments of functions that GLSL overloads for mixed vector and scalar arguments, like `max(vec3, float)`, to the
+/// length of the vector arguments.
+fn broadcast(
+    op: &DynOp,
+    args: Vec<DynOperation>,
+    (line, column): (usize, usize),
+) -> Result<Vec<DynOperation>, ParseError> {
+    if !matches!(
+        op,
+        DynOp::Min
+            | DynOp::Max
+            | DynOp::Clamp
+            | DynOp::Mix
+            | DynOp::Step
+            | DynOp::SmoothStep
+            | DynOp::Modulo
+    ) {
+        return Ok(args);
+    }
+
+    let f32_ty = f32::into_spv_type();
+    let len = match args.iter().find_map(|a| vector_len(a.ty())) {
+        Some(len) => len,
+        None => return Ok(args),
+    };
+    args.into_iter()
+        .map(|a| {
+            if a.ty() == &f32_ty {
+                DynOperation::new(DynOp::Splat(len), vec![a]).map_err(|e| ParseError {
+                    line,
+                    column,
+                    kind: ParseErrorKind::Type(e),
+                })
+            } else {
+                Ok(a)
+            }
+        })
+        .collect()
+}
+
+///Converts a swizzle like `xzy` into component indices.
+fn swizzle_components(swizzle: &str) -> Option<Vec<u32>> {
+    swizzle
+        .chars()
+        .map(|c| match c {
+            'x' | 'r' => Some(0),
+            'y' | 'g' => Some(1),
+            'z' | 'b' => Some(2),
+            'w' | 'a' => Some(3),
+            _ => None,
+        })
+        .collect()
+}
+
+///Parses formulas into [DynOperation]s. Variables that can be used within the formulas must be declared up front.
+///
+/// # Example
+/// ```rust
+/// use algae::{dynamic::{DynValue, Parser}, glam::Vec2, Evaluator};
+///
+/// let parser = Parser::new().with_variable("p", Vec2::ZERO).with_variable("r", 1.0f32);
+/// let mut circle = parser.parse("length(p) - r").unwrap();
+///
+/// let mut evaluator = Evaluator::new();
+/// evaluator.set_variable("p", Vec2::new(3.0, 4.0));
+/// assert_eq!(evaluator.evaluate_dyn(&mut circle), DynValue::F32(4.0));
+///
+/// let error = parser.parse("length(p) -\n  radius").unwrap_err();
+/// assert_eq!((error.line, error.column), (2, 3));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Parser {
+    variables: FxHashMap<String, DynValue>,
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Parser::default()
+    }
+
+    ///Declares the variable `name`. Its type is the type of `default`, which is also the value that is used if the variable is
+    /// not set at runtime.
+    #[must_use]
+    pub fn with_variable(mut self, name: &str, default: impl Into<DynValue>) -> Self {
+        self.variables.insert(String::from(name), default.into());
+        self
+    }
+
+    ///Parses `source` into a type checked operation.
+    pub fn parse(&self, source: &str) -> Result<DynOperation, ParseError> {
+        let tokens = tokenize(source)?;
+        let mut state = ParseState {
+            parser: self,
+            tokens: &tokens,
+            position: 0,
+            end: end_position(source),
+            depth: 0,
+        };
+        let operation = state.expression()?;
+        match state.tokens.get(state.position) {
+            Some(token) => Err(state.unexpected(token, "end of formula")),
+            None => Ok(operation),
+        }
+    }
+}
+
+///Returns the line and column right after the last character of `source`.
+fn end_position(source: &str) -> (usize, usize) {
+    let line = source.lines().count().max(1) + source.ends_with('\n') as usize;
+    let column = if source.ends_with('\n') {
+        1
+    } else {
+        source
+            .lines()
+            .last()
+            .map(|l| l.chars().count())
+            .unwrap_or(0)
+            + 1
+    };
+    (line, column)
+}
+
+///Maximum nesting depth of a formula. Each level recurses a few times, therefore deeper formulas could overflow the stack.
+const MAX_DEPTH: usize = 256;
+
+///Recursive descent parser over the tokens of one formula.
+struct ParseState<'a> {
+    parser: &'a Parser,
+    tokens: &'a [Spanned],
+    position: usize,
+    end: (usize, usize),
+    ///Number of nested expressions and negations that are currently parsed.
+    depth: usize,
+}
+
+impl<'a> ParseState<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.position).map(|(t, _, _)| t)
+    }
+
+    fn next(&mut self, expected: &'static str) -> Result<&'a Spanned, ParseError> {
+        let token = self.tokens.get(self.position).ok_or(ParseError {
+            line: self.end.0,
+            column: self.end.1,
+            kind: ParseErrorKind::UnexpectedEnd { expected },
+        })?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, token: Token, expected: &'static str) -> Result<(), ParseError> {
+        let next = self.next(expected)?;
+        if next.0 == token {
+            Ok(())
+        } else {
+            Err(self.unexpected(next, expected))
+        }
+    }
+
+    fn unexpected(&self, (token, line, column): &Spanned, expected: &'static str) -> ParseError {
+        ParseError {
+            line: *line,
+            column: *column,
+            kind: ParseErrorKind::UnexpectedToken {
+                found: token.to_string(),
+                expected,
+            },
+        }
+    }
+
+    ///Enters a nested expression or negation. Returns an error at the current token if this exceeds [MAX_DEPTH]. Must be
+    /// followed by [leave](Self::leave) once the nested part is parsed.
+    fn enter(&mut self) -> Result<(), ParseError> {
+        self.depth += 1;
+        if self.depth <= MAX_DEPTH {
+            return Ok(());
+        }
+        let (line, column) = self
+            .tokens
+            .get(self.position)
+            .map(|(_, line, column)| (*line, *column))
+            .unwrap_or(self.end);
+        Err(ParseError {
+            line,
+            column,
+            kind: ParseErrorKind::TooDeep(MAX_DEPTH),
+        })
+    }
+
+    fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    ///Assembles the operation and reports type errors at `(line, column)`.
+    fn assemble(
+        &self,
+        op: DynOp,
+        args: Vec<DynOperation>,
+        (line, column): (usize, usize),
+    ) -> Result<DynOperation, ParseError> {
+        DynOperation::new(op, args).map_err(|e| ParseError {
+            line,
+            column,
+            kind: ParseErrorKind::Type(e),
+        })
+    }
+
+    fn expression(&mut self) -> Result<DynOperation, ParseError> {
+        self.enter()?;
+        let mut lhs = self.term()?;
+        while let Some(op) = match self.peek() {
+            Some(Token::Plus) => Some(DynOp::Add),
+            Some(Token::Minus) => Some(DynOp::Sub),
+            _ => None,
+        } {
+            let (_, line, column) = self.next("operator")?;
+            let rhs = self.term()?;
+            lhs = self.assemble(op, vec![lhs, rhs], (*line, *column))?;
+        }
+        self.leave();
+        Ok(lhs)
+    }
+
+    fn term(&mut self) -> Result<DynOperation, ParseError> {
+        let mut lhs = self.unary()?;
+        while let Some(op) = match self.peek() {
+            Some(Token::Star) => Some(DynOp::Mul),
+            Some(Token::Slash) => Some(DynOp::Div),
+            Some(Token::Percent) => Some(DynOp::Remainder),
+            _ => None,
+        } {
+            let (_, line, column) = self.next("operator")?;
+            let rhs = self.unary()?;
+            lhs = self.assemble(op, vec![lhs, rhs], (*line, *column))?;
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<DynOperation, ParseError> {
+        if self.peek() == Some(&Token::Minus) {
+            let (_, line, column) = self.next("-")?;
+            self.enter()?;
+            let inner = self.unary()?;
+            self.leave();
+            return self.assemble(DynOp::Negate, vec![inner], (*line, *column));
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<DynOperation, ParseError> {
+        let mut inner = self.primary()?;
+        while self.peek() == Some(&Token::Dot) {
+            self.next(".")?;
+            let next = self.next("swizzle")?;
+            let (swizzle, line, column) = match next {
+                (Token::Ident(swizzle), line, column) => (swizzle, *line, *column),
+                _ => return Err(self.unexpected(next, "swizzle")),
+            };
+            let components = swizzle_components(swizzle).ok_or_else(|| ParseError {
+                line,
+                column,
+                kind: ParseErrorKind::InvalidSwizzle(swizzle.clone()),
+            })?;
+            let op = match components.as_slice() {
+                [component] => DynOp::Element(*component),
+                _ => DynOp::Swizzle(components),
+            };
+            inner = self.assemble(op, vec![inner], (line, column))?;
+        }
+        Ok(inner)
+    }
+
+    fn primary(&mut self) -> Result<DynOperation, ParseError> {
+        let next = self.next("value")?;
+        let (line, column) = (next.1, next.2);
+        match &next.0 {
+            Token::Number(number) => match number.parse::<f32>() {
+                Ok(value) => Ok(DynOperation::constant(value)),
+                Err(_) => Err(ParseError {
+                    line,
+                    column,
+                    kind: ParseErrorKind::InvalidNumber(number.clone()),
+                }),
+            },
+            Token::OpenParen => {
+                let inner = self.expression()?;
+                self.expect(Token::CloseParen, ")")?;
+                Ok(inner)
+            }
+            Token::Pipe => {
+                let inner = self.expression()?;
+                self.expect(Token::Pipe, "|")?;
+                self.assemble(DynOp::Abs, vec![inner], (line, column))
+            }
+            Token::Ident(name) if self.peek() == Some(&Token::OpenParen) => {
+                self.next("(")?;
+                let mut args = Vec::new();
+                if self.peek() != Some(&Token::CloseParen) {
+                    args.push(self.expression()?);
+                    while self.peek() == Some(&Token::Comma) {
+                        self.next(",")?;
+                        args.push(self.expression()?);
+                    }
+                }
+                self.expect(Token::CloseParen, ")")?;
+                self.call(name, args, (line, column))
+            }
+            Token::Ident(name) => match self.parser.variables.get(name) {
+                Some(default) => Ok(DynOperation::variable(name, *default)),
+                None => Err(ParseError {
+                    line,
+                    column,
+                    kind: ParseErrorKind::UnknownVariable(name.clone()),
+                }),
+            },
+            _ => Err(self.unexpected(next, "value")),
+        }
+    }
+
+    fn call(
+        &self,
+        name: &str,
+        args: Vec<DynOperation>,
+        position: (usize, usize),
+    ) -> Result<DynOperation, ParseError> {
+        let op = match (name, args.len()) {
+            ("vec2", 1) => DynOp::Splat(2),
+            ("vec3", 1) => DynOp::Splat(3),
+            ("vec4", 1) => DynOp::Splat(4),
+            ("vec2" | "vec3" | "vec4", _) => DynOp::Construct,
+            _ => match function(name) {
+                Some(op) => op,
+                None => {
+                    return Err(ParseError {
+                        line: position.0,
+                        column: position.1,
+                        kind: ParseErrorKind::UnknownFunction(String::from(name)),
+                    })
+                }
+            },
+        };
+        let args = broadcast(&op, args, position)?;
+        let expected_len = name
+            .strip_prefix("vec")
+            .and_then(|n| n.parse::<usize>().ok());
+        let operation = self.assemble(op, args, position)?;
+        //Construct takes any number of arguments, make sure that `vecN` results in N components.
+        match (expected_len, vector_len(operation.ty())) {
+            (Some(expected), Some(found)) if expected != found as usize => Err(ParseError {
+                line: position.0,
+                column: position.1,
+                kind: ParseErrorKind::Type(DynError::ArgumentCount {
+                    op: Box::new(DynOp::Construct),
+                    expected,
+                    found: found as usize,
+                }),
+            }),
+            _ => Ok(operation),
+        }
+    }
+}
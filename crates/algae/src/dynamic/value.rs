@@ -0,0 +1,124 @@
+use glam::{
+    BVec2, BVec3, BVec4, IVec2, IVec3, IVec4, Mat2, Mat3, Mat4, UVec2, UVec3, UVec4, Vec2, Vec3,
+    Vec4,
+};
+
+use super::{dispatch::ErasedOperation, DynDataId};
+use crate::{
+    operations::{Constant, Variable},
+    spv_fi::{IntoSpvType, SpvType},
+    DataId, Evaluator,
+};
+
+///Declares [DynValue] with one variant per supported rust type.
+macro_rules! impl_dyn_value {
+    ($($(#[$attr:meta])* $variant:ident($rty:ty)),+) => {
+        ///Runtime typed value of a [DynOperation](super::DynOperation), for instance a constant, or the default value of a variable.
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        #[cfg_attr(feature = "serialization", derive(serde::Serialize, serde::Deserialize))]
+        pub enum DynValue {
+            $($(#[$attr])* $variant($rty)),+
+        }
+
+        impl DynValue {
+            ///Returns the SpirV type of the value.
+            pub fn ty(&self) -> SpvType {
+                match self {
+                    $(DynValue::$variant(_) => <$rty>::into_spv_type()),+
+                }
+            }
+
+            ///Returns the typed constant operation of this value.
+            pub(crate) fn constant(&self) -> Box<dyn ErasedOperation> {
+                match self {
+                    $(DynValue::$variant(v) => Box::new(Constant::<(), $rty>::new(*v))),+
+                }
+            }
+
+            ///Returns the typed variable operation with this value as default.
+            pub(crate) fn variable(&self, name: &str) -> Box<dyn ErasedOperation> {
+                match self {
+                    $(DynValue::$variant(v) => Box::new(Variable::<(), $rty>::new(name, *v))),+
+                }
+            }
+
+            ///Loads the value at `id` from `evaluator`.
+            ///
+            /// # Panics
+            /// Panics if `id` was not created by this evaluator, or its type is none of the supported types.
+            pub(crate) fn load(evaluator: &Evaluator, id: &DynDataId) -> Self {
+                $(
+                    if id.ty == <$rty>::into_spv_type() {
+                        return DynValue::$variant(evaluator.load(DataId::<$rty>::from(id.id)));
+                    }
+                )+
+                panic!("Unsupported dynamic value type {:?}", id.ty)
+            }
+        }
+
+        $(
+            impl From<$rty> for DynValue {
+                fn from(value: $rty) -> Self {
+                    DynValue::$variant(value)
+                }
+            }
+        )+
+    };
+}
+
+impl_dyn_value!(
+    Bool(bool),
+    F32(f32),
+    I32(i32),
+    U32(u32),
+    Vec2(Vec2),
+    Vec3(Vec3),
+    Vec4(Vec4),
+    IVec2(IVec2),
+    IVec3(IVec3),
+    IVec4(IVec4),
+    UVec2(UVec2),
+    UVec3(UVec3),
+    UVec4(UVec4),
+    #[cfg_attr(feature = "serialization", serde(with = "bvec2"))]
+    BVec2(BVec2),
+    #[cfg_attr(feature = "serialization", serde(with = "bvec3"))]
+    BVec3(BVec3),
+    #[cfg_attr(feature = "serialization", serde(with = "bvec4"))]
+    BVec4(BVec4),
+    Mat2(Mat2),
+    Mat3(Mat3),
+    Mat4(Mat4)
+);
+
+///Saves glam's boolean vectors as arrays, since glam does not implement serde for them.
+#[cfg(feature = "serialization")]
+macro_rules! impl_bvec_serde {
+    ($module:ident, $vecty:ident, $ne:expr, $($idx:tt),+) => {
+        mod $module {
+            use glam::$vecty;
+            use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+            pub fn serialize<S: Serializer>(
+                value: &$vecty,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                let elements: [bool; $ne] = (*value).into();
+                elements.serialize(serializer)
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<$vecty, D::Error> {
+                <[bool; $ne]>::deserialize(deserializer).map(|e| <$vecty>::new($(e[$idx]),+))
+            }
+        }
+    };
+}
+
+#[cfg(feature = "serialization")]
+impl_bvec_serde!(bvec2, BVec2, 2, 0, 1);
+#[cfg(feature = "serialization")]
+impl_bvec_serde!(bvec3, BVec3, 3, 0, 1, 2);
+#[cfg(feature = "serialization")]
+impl_bvec_serde!(bvec4, BVec4, 4, 0, 1, 2, 3);
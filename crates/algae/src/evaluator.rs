@@ -0,0 +1,110 @@
+//! CPU side evaluation of algae functions.
+
+use std::{any::Any, hash::BuildHasherDefault};
+
+use fxhash::FxHashMap;
+
+use crate::{
+    dynamic::{DynOperation, DynValue},
+    DataId, Operation,
+};
+
+///Runtime evaluator of a algae function. Counterpart to the [Serializer](crate::Serializer). Instead of emitting SpirV code
+/// each operation is executed on the CPU immediately. The results are saved within the evaluator, the `DataId`s returned
+/// by [Operation::evaluate] are indices into this storage.
+///
+/// Variables are set by name via [set_variable](Evaluator::set_variable). If a variable is not set, or set with another type,
+/// its default value is used, similar to a variable that is not part of a SpirV function interface.
+pub struct Evaluator {
+    values: Vec<Box<dyn Any>>,
+    variables: FxHashMap<String, Box<dyn Any>>,
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Evaluator {
+    pub fn new() -> Self {
+        Evaluator {
+            values: Vec::new(),
+            variables: FxHashMap::with_capacity_and_hasher(2, BuildHasherDefault::default()),
+        }
+    }
+
+    ///Sets the runtime value of the variable `name`. Overwrites the value if the variable was set before.
+    pub fn set_variable<T: Any>(&mut self, name: &str, value: T) {
+        self.variables.insert(String::from(name), Box::new(value));
+    }
+
+    ///Removes all variable values.
+    pub fn clear_variables(&mut self) {
+        self.variables.clear();
+    }
+
+    ///Tries to find a variable of type `T` with the given name. Returns the data id at which the value is stored if one is found.
+    /// Otherwise the variables defined default value is stored there.
+    pub fn get_variable<T: Any + Clone>(&mut self, name: &str, default_value: T) -> DataId<T> {
+        let value = match self.variables.get(name).and_then(|v| v.downcast_ref::<T>()) {
+            Some(v) => v.clone(),
+            None => {
+                #[cfg(feature = "logging")]
+                log::warn!(
+                    "Could not find variable \"{}\" in evaluator, falling back to default value",
+                    name
+                );
+                default_value
+            }
+        };
+
+        self.store(value)
+    }
+
+    ///Saves `value` in the evaluator and returns its data id.
+    pub fn store<T: Any>(&mut self, value: T) -> DataId<T> {
+        self.values.push(Box::new(value));
+        DataId::from((self.values.len() - 1) as u32)
+    }
+
+    ///Loads the value at `id`.
+    ///
+    /// # Panics
+    /// Panics if `id` was not created by this evaluator.
+    pub fn load<T: Any + Clone>(&self, id: DataId<T>) -> T {
+        self.values
+            .get(id.id as usize)
+            .and_then(|v| v.downcast_ref::<T>())
+            .expect("DataId does not belong to this evaluator")
+            .clone()
+    }
+
+    ///Evaluates `operation` for the given input and returns the result. Intermediate values that where created while
+    /// evaluating are discarded afterwards.
+    pub fn evaluate<I, O: Any + Clone>(
+        &mut self,
+        operation: &mut dyn Operation<Input = I, Output = DataId<O>>,
+        input: I,
+    ) -> O {
+        let watermark = self.values.len();
+        let result = operation.evaluate(self, input);
+        let value = self.load(result);
+        self.values.truncate(watermark);
+        value
+    }
+
+    ///Evaluates the type-erased `operation` and returns the result. Works like [evaluate](Evaluator::evaluate).
+    pub fn evaluate_dyn(&mut self, operation: &mut DynOperation) -> DynValue {
+        let watermark = self.values.len();
+        let result = operation.evaluate(self, ());
+        let value = DynValue::load(self, &result);
+        self.values.truncate(watermark);
+        value
+    }
+
+    ///Removes all stored values. Invalidates all data ids created so far.
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+}
@@ -0,0 +1,293 @@
+//! Short hand constructors for the operation trees that signed distance fields and noise are built from. All trees work
+//! on already calculated [DataId]s, therefore their input is `()`.
+use std::convert::Infallible;
+
+use glam::Vec2;
+
+use crate::{
+    operations::{
+        Abs, Addition, And, ArcTangent2, BitwiseAnd, BitwiseXor, Clamp, Constant, Convert, Cosine,
+        Division, Dot, Floor, Greater, GreaterEqual, Length, Less, Max, Min, Mix, Modulo,
+        Multiplication, Negate, Not, Or, Round, Select, ShiftRight, Sign, Sine, Splat, Sqrt,
+        Subtraction, Swizzle, VecConstruct, VecSelectElement, VectorTimesMatrix, VectorTimesScalar,
+    },
+    spv_fi::IntoSpvType,
+    BoxOperation, DataId, Evaluator, Operation, SerializeError, Serializer,
+};
+
+pub(crate) type Expr<T> = BoxOperation<(), T>;
+
+///Implemented by every operation `O` that maps the inputs of the expression to a value of type `T`.
+pub(crate) trait ExprOf<T>: Operation<Input = (), Output = DataId<T>> + 'static {}
+impl<T, O: Operation<Input = (), Output = DataId<T>> + 'static> ExprOf<T> for O {}
+
+///Either a [Serializer] or an [Evaluator]. Binding an expression calculates it once, the resulting [DataId] can then be
+/// used any number of times without duplicating the expression. Only serializing can fail, the evaluator's error is
+/// [Infallible].
+pub(crate) trait Context {
+    type Error;
+    fn bind<T: 'static>(&mut self, expr: Expr<T>) -> Result<DataId<T>, Self::Error>;
+}
+
+impl Context for Serializer<'_, '_> {
+    type Error = SerializeError;
+    fn bind<T: 'static>(&mut self, mut expr: Expr<T>) -> Result<DataId<T>, SerializeError> {
+        expr.serialize(self, ())
+    }
+}
+
+impl Context for Evaluator {
+    type Error = Infallible;
+    fn bind<T: 'static>(&mut self, mut expr: Expr<T>) -> Result<DataId<T>, Infallible> {
+        Ok(expr.evaluate(self, ()))
+    }
+}
+
+///Unwraps the result of an evaluation.
+pub(crate) fn evaluated<T>(result: Result<T, Infallible>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(never) => match never {},
+    }
+}
+
+pub(crate) fn value<T: Clone + 'static>(id: DataId<T>) -> Expr<T> {
+    Box::new(id)
+}
+
+pub(crate) fn constant<T: IntoSpvType + Clone + 'static>(value: T) -> Expr<T> {
+    Box::new(Constant::new(value))
+}
+
+pub(crate) fn add<T: 'static>(a: Expr<T>, b: Expr<T>) -> Expr<T>
+where
+    Addition<(), T>: ExprOf<T>,
+{
+    Box::new(Addition { a, b })
+}
+
+pub(crate) fn sub<T: 'static>(minuent: Expr<T>, subtrahend: Expr<T>) -> Expr<T>
+where
+    Subtraction<(), T>: ExprOf<T>,
+{
+    Box::new(Subtraction {
+        minuent,
+        subtrahend,
+    })
+}
+
+pub(crate) fn mul<T: 'static>(a: Expr<T>, b: Expr<T>) -> Expr<T>
+where
+    Multiplication<(), T>: ExprOf<T>,
+{
+    Box::new(Multiplication { a, b })
+}
+
+pub(crate) fn div<T: 'static>(dividend: Expr<T>, divisor: Expr<T>) -> Expr<T>
+where
+    Division<(), T>: ExprOf<T>,
+{
+    Box::new(Division { dividend, divisor })
+}
+
+pub(crate) fn neg<T: 'static>(inner: Expr<T>) -> Expr<T>
+where
+    Negate<(), T>: ExprOf<T>,
+{
+    Box::new(Negate { inner })
+}
+
+pub(crate) fn abs<T: 'static>(inner: Expr<T>) -> Expr<T>
+where
+    Abs<(), T>: ExprOf<T>,
+{
+    Box::new(Abs { inner })
+}
+
+pub(crate) fn round<T: 'static>(inner: Expr<T>) -> Expr<T>
+where
+    Round<(), T>: ExprOf<T>,
+{
+    Box::new(Round { inner })
+}
+
+pub(crate) fn min<T: 'static>(a: Expr<T>, b: Expr<T>) -> Expr<T>
+where
+    Min<(), T>: ExprOf<T>,
+{
+    Box::new(Min { a, b })
+}
+
+pub(crate) fn max<T: 'static>(a: Expr<T>, b: Expr<T>) -> Expr<T>
+where
+    Max<(), T>: ExprOf<T>,
+{
+    Box::new(Max { a, b })
+}
+
+pub(crate) fn floor<T: 'static>(inner: Expr<T>) -> Expr<T>
+where
+    Floor<(), T>: ExprOf<T>,
+{
+    Box::new(Floor { inner })
+}
+
+pub(crate) fn clamp(inner: Expr<f32>, min: Expr<f32>, max: Expr<f32>) -> Expr<f32> {
+    Box::new(Clamp { inner, min, max })
+}
+
+///`clamp(inner, 0, 1)`
+pub(crate) fn saturate(inner: Expr<f32>) -> Expr<f32> {
+    clamp(inner, constant(0.0), constant(1.0))
+}
+
+pub(crate) fn mix(a: Expr<f32>, b: Expr<f32>, t: Expr<f32>) -> Expr<f32> {
+    Box::new(Mix { a, b, t })
+}
+
+pub(crate) fn sign(inner: Expr<f32>) -> Expr<f32> {
+    Box::new(Sign { inner })
+}
+
+pub(crate) fn sqrt(inner: Expr<f32>) -> Expr<f32> {
+    Box::new(Sqrt { inner })
+}
+
+pub(crate) fn sin(inner: Expr<f32>) -> Expr<f32> {
+    Box::new(Sine { inner })
+}
+
+pub(crate) fn cos(inner: Expr<f32>) -> Expr<f32> {
+    Box::new(Cosine { inner })
+}
+
+pub(crate) fn atan2(y: Expr<f32>, x: Expr<f32>) -> Expr<f32> {
+    Box::new(ArcTangent2 { y, x })
+}
+
+///GLSL's `mod`, whose result has the sign of `divisor`.
+pub(crate) fn modulo(dividend: Expr<f32>, divisor: Expr<f32>) -> Expr<f32> {
+    Box::new(Modulo { dividend, divisor })
+}
+
+pub(crate) fn greater(a: Expr<f32>, b: Expr<f32>) -> Expr<bool> {
+    Box::new(Greater { a, b })
+}
+
+pub(crate) fn greater_equal(a: Expr<f32>, b: Expr<f32>) -> Expr<bool> {
+    Box::new(GreaterEqual { a, b })
+}
+
+pub(crate) fn less(a: Expr<f32>, b: Expr<f32>) -> Expr<bool> {
+    Box::new(Less { a, b })
+}
+
+pub(crate) fn and(a: Expr<bool>, b: Expr<bool>) -> Expr<bool> {
+    Box::new(And { a, b })
+}
+
+pub(crate) fn or(a: Expr<bool>, b: Expr<bool>) -> Expr<bool> {
+    Box::new(Or { a, b })
+}
+
+pub(crate) fn not(inner: Expr<bool>) -> Expr<bool> {
+    Box::new(Not { inner })
+}
+
+pub(crate) fn select(
+    condition: Expr<bool>,
+    true_branch: Expr<f32>,
+    false_branch: Expr<f32>,
+) -> Expr<f32> {
+    Box::new(Select {
+        condition,
+        true_branch,
+        false_branch,
+    })
+}
+
+///`vector * scalar`
+pub(crate) fn scale<V: 'static>(vector: Expr<V>, scalar: Expr<f32>) -> Expr<V>
+where
+    VectorTimesScalar<(), V>: ExprOf<V>,
+{
+    Box::new(VectorTimesScalar { vector, scalar })
+}
+
+pub(crate) fn splat<V: 'static>(inner: Expr<f32>) -> Expr<V>
+where
+    Splat<V, ()>: ExprOf<V>,
+{
+    Box::new(Splat::new(inner))
+}
+
+pub(crate) fn length<V: 'static>(inner: Expr<V>) -> Expr<f32>
+where
+    Length<V, ()>: ExprOf<f32>,
+{
+    Box::new(Length { inner })
+}
+
+pub(crate) fn dot<V: 'static>(a: Expr<V>, b: Expr<V>) -> Expr<f32>
+where
+    Dot<V, ()>: ExprOf<f32>,
+{
+    Box::new(Dot { a, b })
+}
+
+pub(crate) fn element<V: 'static>(inner: Expr<V>, element: u32) -> Expr<f32>
+where
+    VecSelectElement<V, ()>: ExprOf<f32>,
+{
+    Box::new(VecSelectElement { element, inner })
+}
+
+pub(crate) fn swizzle<V: 'static, S: 'static>(pattern: &str, inner: Expr<V>) -> Expr<S>
+where
+    Swizzle<V, S, ()>: ExprOf<S>,
+{
+    Box::new(Swizzle::new(pattern, inner))
+}
+
+pub(crate) fn vec2(x: Expr<f32>, y: Expr<f32>) -> Expr<Vec2> {
+    Box::new(VecConstruct::<Vec2, ()>::new(vec![x, y]))
+}
+
+///`vector * matrix`, which is the same as `transpose(matrix) * vector`.
+pub(crate) fn vector_times_matrix<M: 'static, V: 'static>(
+    vector: Expr<V>,
+    matrix: Expr<M>,
+) -> Expr<V>
+where
+    VectorTimesMatrix<M, V, ()>: ExprOf<V>,
+{
+    Box::new(VectorTimesMatrix { vector, matrix })
+}
+
+pub(crate) fn bitwise_and<T: 'static>(a: Expr<T>, b: Expr<T>) -> Expr<T>
+where
+    BitwiseAnd<(), T>: ExprOf<T>,
+{
+    Box::new(BitwiseAnd { a, b })
+}
+
+pub(crate) fn bitwise_xor<T: 'static>(a: Expr<T>, b: Expr<T>) -> Expr<T>
+where
+    BitwiseXor<(), T>: ExprOf<T>,
+{
+    Box::new(BitwiseXor { a, b })
+}
+
+pub(crate) fn shift_right<T: 'static>(base: Expr<T>, shift: Expr<T>) -> Expr<T>
+where
+    ShiftRight<(), T>: ExprOf<T>,
+{
+    Box::new(ShiftRight { base, shift })
+}
+
+pub(crate) fn convert<S: 'static, T: 'static>(inner: Expr<S>) -> Expr<T>
+where
+    Convert<S, T, ()>: ExprOf<T>,
+{
+    Box::new(Convert::new(inner))
+}
@@ -0,0 +1,316 @@
+//! Constant folding and algebraic simplification of single instructions.
+//!
+//! Each value that is emitted via [Serializer::value](crate::Serializer::value) is checked against the definitions of its operands.
+//! If all operands are float constants, the result is calculated at JIT time. Otherwise a few identities are applied, like
+//! `x * 1 = x` or `sqrt(x * x) = abs(x)`.
+
+use fxhash::FxHashMap;
+use rspirv::{
+    dr::{Instruction, Operand},
+    spirv::{Op, Word},
+};
+
+///Result of a successful simplification.
+pub(crate) enum Simplified {
+    ///The instruction is equal to an existing value.
+    Existing(Word),
+    ///The instruction evaluates to a float constant with the given components. A single component is a scalar.
+    Constant(Vec<f32>),
+    ///The instruction is equal to the absolute value of a float value.
+    FAbs(Word),
+}
+
+///Returns the components of a float scalar or vector constant.
+fn float_constant(definitions: &FxHashMap<Word, Instruction>, id: Word) -> Option<Vec<f32>> {
+    let definition = definitions.get(&id)?;
+    match definition.class.opcode {
+        Op::Constant => match definition.operands.first()? {
+            Operand::LiteralFloat32(f) => Some(vec![*f]),
+            _ => None,
+        },
+        Op::ConstantComposite => {
+            let mut components = Vec::with_capacity(definition.operands.len());
+            for operand in &definition.operands {
+                //Only vectors of scalars, no matrices
+                match float_constant(definitions, operand.unwrap_id_ref())?.as_slice() {
+                    [f] => components.push(*f),
+                    _ => return None,
+                }
+            }
+            Some(components)
+        }
+        _ => None,
+    }
+}
+
+///Returns true if `id` is an int or float constant, where each component equals `value`.
+fn is_constant(definitions: &FxHashMap<Word, Instruction>, id: Word, value: u32) -> bool {
+    if let Some(components) = float_constant(definitions, id) {
+        return components.iter().all(|c| *c == value as f32);
+    }
+
+    match definitions.get(&id) {
+        Some(definition) if definition.class.opcode == Op::Constant => {
+            matches!(
+                definition.operands.first(),
+                Some(Operand::LiteralInt32(i)) if *i == value
+            ) || matches!(
+                definition.operands.first(),
+                Some(Operand::LiteralInt64(i)) if *i == value as u64
+            )
+        }
+        Some(definition) if definition.class.opcode == Op::ConstantComposite => definition
+            .operands
+            .iter()
+            .all(|o| is_constant(definitions, o.unwrap_id_ref(), value)),
+        _ => false,
+    }
+}
+
+///True if `id` is a float constant where each component is a zero with the given sign. Note that only `x + -0` and
+/// `x - 0` are `x` for every float `x`, since `-0 + 0` is `+0`.
+fn is_signed_zero(definitions: &FxHashMap<Word, Instruction>, id: Word, negative: bool) -> bool {
+    match float_constant(definitions, id) {
+        Some(components) => components
+            .iter()
+            .all(|c| *c == 0.0 && c.is_sign_negative() == negative),
+        None => false,
+    }
+}
+
+///Returns the GLSL.std.450 instruction number, if `instruction` is an extended instruction of the set `glsl_std_450`.
+fn ext_inst(instruction: &Instruction, glsl_std_450: Option<Word>) -> Option<u32> {
+    if instruction.class.opcode != Op::ExtInst {
+        return None;
+    }
+    match (&instruction.operands[0], &instruction.operands[1]) {
+        (Operand::IdRef(set), Operand::LiteralExtInstInteger(inst))
+            if Some(*set) == glsl_std_450 =>
+        {
+            Some(*inst)
+        }
+        _ => None,
+    }
+}
+
+///Tries to simplify `instruction` based on the `definitions` of its operands.
+pub(crate) fn simplify(
+    instruction: &Instruction,
+    definitions: &FxHashMap<Word, Instruction>,
+    glsl_std_450: Option<Word>,
+) -> Option<Simplified> {
+    let ext = ext_inst(instruction, glsl_std_450);
+    //Value operands, for extended instructions without the set and instruction number.
+    let args: Vec<Word> = match (instruction.class.opcode, ext) {
+        (Op::ExtInst, None) => return None,
+        (Op::ExtInst, Some(_)) => instruction.operands[2..]
+            .iter()
+            .map(|o| o.unwrap_id_ref())
+            .collect(),
+        _ => instruction
+            .operands
+            .iter()
+            .filter_map(|o| match o {
+                Operand::IdRef(id) => Some(*id),
+                _ => None,
+            })
+            .collect(),
+    };
+
+    if let Some(simplified) = identity(
+        instruction.class.opcode,
+        ext,
+        &args,
+        definitions,
+        glsl_std_450,
+    ) {
+        return Some(simplified);
+    }
+
+    //Fold if all operands are float constants
+    let mut constants = Vec::with_capacity(args.len());
+    for arg in &args {
+        constants.push(float_constant(definitions, *arg)?);
+    }
+    fold(instruction, ext, &constants).map(Simplified::Constant)
+}
+
+///Applies algebraic identities.
+fn identity(
+    opcode: Op,
+    ext: Option<u32>,
+    args: &[Word],
+    definitions: &FxHashMap<Word, Instruction>,
+    glsl_std_450: Option<Word>,
+) -> Option<Simplified> {
+    match (opcode, ext, args) {
+        //x * 1, 1 * x
+        (Op::FMul | Op::IMul, _, [a, b]) if is_constant(definitions, *b, 1) => {
+            Some(Simplified::Existing(*a))
+        }
+        (Op::FMul | Op::IMul, _, [a, b]) if is_constant(definitions, *a, 1) => {
+            Some(Simplified::Existing(*b))
+        }
+        (Op::VectorTimesScalar, _, [v, s]) if is_constant(definitions, *s, 1) => {
+            Some(Simplified::Existing(*v))
+        }
+        //x + 0, 0 + x, x - 0
+        (Op::IAdd, _, [a, b]) if is_constant(definitions, *b, 0) => Some(Simplified::Existing(*a)),
+        (Op::IAdd, _, [a, b]) if is_constant(definitions, *a, 0) => Some(Simplified::Existing(*b)),
+        (Op::ISub, _, [a, b]) if is_constant(definitions, *b, 0) => Some(Simplified::Existing(*a)),
+        //x + -0, -0 + x, x - +0
+        (Op::FAdd, _, [a, b]) if is_signed_zero(definitions, *b, true) => {
+            Some(Simplified::Existing(*a))
+        }
+        (Op::FAdd, _, [a, b]) if is_signed_zero(definitions, *a, true) => {
+            Some(Simplified::Existing(*b))
+        }
+        (Op::FSub, _, [a, b]) if is_signed_zero(definitions, *b, false) => {
+            Some(Simplified::Existing(*a))
+        }
+        //x / 1
+        (Op::FDiv | Op::SDiv | Op::UDiv, _, [a, b]) if is_constant(definitions, *b, 1) => {
+            Some(Simplified::Existing(*a))
+        }
+        //abs(abs(x))
+        (Op::ExtInst, Some(inst @ (4 | 5)), [a]) => {
+            let inner = definitions.get(a)?;
+            if ext_inst(inner, glsl_std_450) == Some(inst) {
+                Some(Simplified::Existing(*a))
+            } else {
+                None
+            }
+        }
+        //sqrt(x * x)
+        (Op::ExtInst, Some(31), [a]) => {
+            let inner = definitions.get(a)?;
+            match inner.operands.as_slice() {
+                [Operand::IdRef(x), Operand::IdRef(y)]
+                    if inner.class.opcode == Op::FMul && x == y =>
+                {
+                    Some(Simplified::FAbs(*x))
+                }
+                _ => None,
+            }
+        }
+        //min(x, x), max(x, x)
+        (Op::ExtInst, Some(37..=42), [a, b]) if a == b => Some(Simplified::Existing(*a)),
+        _ => None,
+    }
+}
+
+///Applies `f` component wise. Scalar arguments are broadcast to the length of the vector arguments.
+fn component_wise(args: &[Vec<f32>], f: impl Fn(&[f32]) -> f32) -> Option<Vec<f32>> {
+    let len = args.iter().map(|a| a.len()).max()?;
+    if args.iter().any(|a| a.len() != len && a.len() != 1) {
+        return None;
+    }
+
+    let mut components = Vec::with_capacity(len);
+    let mut component_args = vec![0.0; args.len()];
+    for idx in 0..len {
+        for (component_arg, arg) in component_args.iter_mut().zip(args) {
+            *component_arg = if arg.len() == 1 { arg[0] } else { arg[idx] };
+        }
+        components.push(f(&component_args));
+    }
+    Some(components)
+}
+
+///Calculates the float result of `instruction` where `args` are the constant values of its operands.
+fn fold(instruction: &Instruction, ext: Option<u32>, args: &[Vec<f32>]) -> Option<Vec<f32>> {
+    let dot = |a: &[f32], b: &[f32]| a.iter().zip(b).map(|(a, b)| a * b).sum::<f32>();
+    match (instruction.class.opcode, ext, args) {
+        (Op::FNegate, _, _) => component_wise(args, |a| -a[0]),
+        (Op::FAdd, _, _) => component_wise(args, |a| a[0] + a[1]),
+        (Op::FSub, _, _) => component_wise(args, |a| a[0] - a[1]),
+        (Op::FMul | Op::VectorTimesScalar, _, _) => component_wise(args, |a| a[0] * a[1]),
+        (Op::FDiv, _, _) => component_wise(args, |a| a[0] / a[1]),
+        (Op::FRem, _, _) => component_wise(args, |a| a[0] % a[1]),
+        (Op::FMod, _, _) => component_wise(args, |a| a[0] - a[1] * (a[0] / a[1]).floor()),
+        (Op::Dot, _, [a, b]) => Some(vec![dot(a, b)]),
+        //Only vectors of scalars, no matrices
+        (Op::CompositeConstruct, _, _) if args.iter().all(|a| a.len() == 1) => {
+            Some(args.iter().map(|a| a[0]).collect())
+        }
+        (Op::CompositeExtract, _, [v]) => match instruction.operands.as_slice() {
+            [_, Operand::LiteralInt32(idx)] => Some(vec![*v.get(*idx as usize)?]),
+            _ => None,
+        },
+        (Op::VectorShuffle, _, [a, b]) => {
+            let mut components = Vec::new();
+            for operand in &instruction.operands[2..] {
+                match operand {
+                    Operand::LiteralInt32(idx) => {
+                        let idx = *idx as usize;
+                        components.push(if idx < a.len() {
+                            a[idx]
+                        } else {
+                            *b.get(idx - a.len())?
+                        });
+                    }
+                    _ => return None,
+                }
+            }
+            Some(components)
+        }
+        (Op::ExtInst, Some(inst), _) => match inst {
+            1 => component_wise(args, |a| a[0].round()),
+            3 => component_wise(args, |a| a[0].trunc()),
+            4 => component_wise(args, |a| a[0].abs()),
+            6 => component_wise(args, |a| {
+                if a[0] > 0.0 {
+                    1.0
+                } else if a[0] < 0.0 {
+                    -1.0
+                } else {
+                    0.0
+                }
+            }),
+            8 => component_wise(args, |a| a[0].floor()),
+            9 => component_wise(args, |a| a[0].ceil()),
+            10 => component_wise(args, |a| a[0] - a[0].floor()),
+            13 => component_wise(args, |a| a[0].sin()),
+            14 => component_wise(args, |a| a[0].cos()),
+            15 => component_wise(args, |a| a[0].tan()),
+            16 => component_wise(args, |a| a[0].asin()),
+            17 => component_wise(args, |a| a[0].acos()),
+            18 => component_wise(args, |a| a[0].atan()),
+            19 => component_wise(args, |a| a[0].sinh()),
+            20 => component_wise(args, |a| a[0].cosh()),
+            21 => component_wise(args, |a| a[0].tanh()),
+            22 => component_wise(args, |a| a[0].asinh()),
+            23 => component_wise(args, |a| a[0].acosh()),
+            24 => component_wise(args, |a| a[0].atanh()),
+            25 => component_wise(args, |a| a[0].atan2(a[1])),
+            26 => component_wise(args, |a| a[0].powf(a[1])),
+            27 => component_wise(args, |a| a[0].exp()),
+            28 => component_wise(args, |a| a[0].ln()),
+            29 => component_wise(args, |a| a[0].exp2()),
+            30 => component_wise(args, |a| a[0].log2()),
+            31 => component_wise(args, |a| a[0].sqrt()),
+            32 => component_wise(args, |a| 1.0 / a[0].sqrt()),
+            37 => component_wise(args, |a| a[0].min(a[1])),
+            40 => component_wise(args, |a| a[0].max(a[1])),
+            43 => component_wise(args, |a| a[0].max(a[1]).min(a[2])),
+            46 => component_wise(args, |a| a[0] * (1.0 - a[2]) + a[1] * a[2]),
+            48 => component_wise(args, |a| if a[1] < a[0] { 0.0 } else { 1.0 }),
+            49 => component_wise(args, |a| {
+                let t = ((a[2] - a[0]) / (a[1] - a[0])).clamp(0.0, 1.0);
+                t * t * (3.0 - 2.0 * t)
+            }),
+            50 => component_wise(args, |a| a[0].mul_add(a[1], a[2])),
+            66 => Some(vec![dot(&args[0], &args[0]).sqrt()]),
+            67 => {
+                let diff = component_wise(args, |a| a[0] - a[1])?;
+                Some(vec![dot(&diff, &diff).sqrt()])
+            }
+            69 => {
+                let length = dot(&args[0], &args[0]).sqrt();
+                component_wise(args, |a| a[0] / length)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -6,31 +6,105 @@
 pub use glam;
 use spv_fi::SpvFi;
 
-use std::marker::PhantomData;
+use std::{any::type_name, error::Error, marker::PhantomData};
 
 pub use rspirv;
-use rspirv::{dr::Builder, spirv::Word};
+use fxhash::FxHashMap;
+use rspirv::{
+    binary::Assemble,
+    dr::{Builder, Instruction, Operand},
+    spirv::{Op, Word},
+};
 
 pub mod operations;
 
+pub mod dynamic;
+
+pub mod sdf;
+
+pub mod noise;
+
+///Short hand constructors for operation trees.
+mod expr;
+
+///Constant folding and algebraic simplification.
+mod fold;
+
+///CPU side evaluation of operations.
+mod evaluator;
+pub use evaluator::Evaluator;
+
 use operations::Constant;
 
 ///SpirV analyzer related functions.
 pub mod spv_fi;
-use spv_fi::IntoSpvType;
+use spv_fi::{IntoSpvType, SpvType};
 
 use algae_gpu::simple_hash;
 
 ///Runtime serializer of a algae function. `'a` is the SpirV-Builders's lifetime, `'b` is the injection functions lifetime.
+///
+/// The serializer keeps track of the values and constants that where already emitted. If an operation emits an instruction
+/// via [value](Serializer::value) or [constant](Serializer::constant) that is identical to a former one, the former result is reused.
+///
+/// Values are simplified while being emitted. Values that only depend on float constants are calculated at JIT time and emitted
+/// as a constant, and identities like `x * 1`, `x + 0`, `abs(abs(x))`, `sqrt(x * x)` or `min(x, x)` are replaced by their simpler form.
+/// Use [set_folding](Serializer::set_folding) to disable this.
 pub struct Serializer<'a, 'b> {
     //The spirv builder
     pub(crate) builder: &'a mut Builder,
     pub(crate) interface: &'b SpvFi,
+    ///Value numbering table. Each scope contains the values of one structured control flow construct.
+    values: Vec<FxHashMap<InstructionKey, Word>>,
+    ///Hash-consed constants of the module.
+    constants: FxHashMap<InstructionKey, Word>,
+    ///Id of the imported GLSL.std.450 instruction set, if it was loaded already.
+    glsl_std_450: Option<Word>,
+    ///Defining instruction of each value and constant emitted by this serializer. Used when folding.
+    definitions: FxHashMap<Word, Instruction>,
+    ///True if values are folded and simplified.
+    fold: bool,
+}
+
+///Identifies an instruction by its opcode, type and operands, but not its result id.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct InstructionKey {
+    opcode: Op,
+    result_type: Option<Word>,
+    operands: Vec<Word>,
+}
+
+impl InstructionKey {
+    fn from_instruction(instruction: &Instruction) -> Self {
+        let mut operands = Vec::with_capacity(instruction.operands.len());
+        for operand in &instruction.operands {
+            operand.assemble_into(&mut operands);
+        }
+
+        InstructionKey {
+            opcode: instruction.class.opcode,
+            result_type: instruction.result_type,
+            operands,
+        }
+    }
 }
 
 impl<'a, 'b> Serializer<'a, 'b> {
     pub fn new(builder: &'a mut Builder, interface: &'b SpvFi) -> Self {
-        Serializer { builder, interface }
+        Serializer {
+            builder,
+            interface,
+            values: vec![FxHashMap::default()],
+            constants: FxHashMap::default(),
+            glsl_std_450: None,
+            definitions: FxHashMap::default(),
+            fold: true,
+        }
+    }
+
+    ///Enables or disables constant folding and algebraic simplification of values. Enabled by default.
+    pub fn set_folding(&mut self, fold: bool) {
+        self.fold = fold;
     }
 
     pub fn builder(&self) -> &Builder {
@@ -41,9 +115,153 @@ impl<'a, 'b> Serializer<'a, 'b> {
         &mut self.builder
     }
 
+    ///Emits a value into the current block via `build`. If an identical instruction was emitted before in the current or
+    /// an enclosing scope, the new instruction is removed again and the former result id is returned.
+    ///
+    /// If folding is enabled, the instruction might be replaced by a constant or a simpler value.
+    ///
+    /// `build` must append exactly one instruction that has no side effects, for instance an arithmetic instruction.
+    pub fn value(
+        &mut self,
+        build: impl FnOnce(&mut Builder) -> Result<Word, rspirv::dr::Error>,
+    ) -> Result<Word, rspirv::dr::Error> {
+        let id = build(self.builder)?;
+
+        let (function, block) = match (
+            self.builder.selected_function(),
+            self.builder.selected_block(),
+        ) {
+            (Some(function), Some(block)) => (function, block),
+            _ => return Err(rspirv::dr::Error::DetachedInstruction(None)),
+        };
+        let instructions =
+            &mut self.builder.module_mut().functions[function].blocks[block].instructions;
+
+        if self.fold {
+            let simplified =
+                fold::simplify(instructions.last().unwrap(), &self.definitions, self.glsl_std_450);
+            if let Some(simplified) = simplified {
+                let instruction = instructions.pop().unwrap();
+                return self.emit_simplified(instruction, simplified);
+            }
+        }
+
+        let key = InstructionKey::from_instruction(instructions.last().unwrap());
+        if let Some(existing) = self.values.iter().rev().find_map(|scope| scope.get(&key)) {
+            instructions.pop();
+            Ok(*existing)
+        } else {
+            self.definitions.insert(id, instructions.last().unwrap().clone());
+            self.values.last_mut().unwrap().insert(key, id);
+            Ok(id)
+        }
+    }
+
+    ///Emits the replacement of `instruction`, which was already removed from the current block.
+    fn emit_simplified(
+        &mut self,
+        instruction: Instruction,
+        simplified: fold::Simplified,
+    ) -> Result<Word, rspirv::dr::Error> {
+        let result_type = instruction.result_type.unwrap();
+        match simplified {
+            fold::Simplified::Existing(id) => Ok(id),
+            fold::Simplified::Constant(components) => {
+                if let [scalar] = components.as_slice() {
+                    Ok(self.constant(|builder| builder.constant_f32(result_type, *scalar)))
+                } else {
+                    let t_f32 = self.builder.type_float(32);
+                    let ids: Vec<Word> = components
+                        .into_iter()
+                        .map(|c| self.constant(|builder| builder.constant_f32(t_f32, c)))
+                        .collect();
+                    Ok(self.constant(|builder| builder.constant_composite(result_type, ids)))
+                }
+            }
+            fold::Simplified::FAbs(id) => {
+                let ext_instset_id = self.glsl_std_450();
+                self.value(|builder| {
+                    builder.ext_inst(result_type, None, ext_instset_id, 4, [Operand::IdRef(id)])
+                })
+            }
+        }
+    }
+
+    ///Emits a constant via `build`. Constants are hash-consed per type and value, so if the same constant was emitted before
+    /// the new one is removed again and the former id is returned.
+    pub fn constant(&mut self, build: impl FnOnce(&mut Builder) -> Word) -> Word {
+        let id = build(self.builder);
+
+        let globals = &mut self.builder.module_mut().types_global_values;
+        let key = InstructionKey::from_instruction(globals.last().unwrap());
+        if let Some(existing) = self.constants.get(&key) {
+            globals.pop();
+            *existing
+        } else {
+            self.definitions.insert(id, globals.last().unwrap().clone());
+            self.constants.insert(key, id);
+            id
+        }
+    }
+
+    ///Returns the id of the GLSL.std.450 extended instruction set. Imports it if the module does not import it yet.
+    pub fn glsl_std_450(&mut self) -> Word {
+        if let Some(id) = self.glsl_std_450 {
+            return id;
+        }
+
+        let existing = self
+            .builder
+            .module_ref()
+            .ext_inst_imports
+            .iter()
+            .find(|import| {
+                matches!(import.operands.first(), Some(Operand::LiteralString(name)) if name == "GLSL.std.450")
+            })
+            .and_then(|import| import.result_id);
+        let id = existing.unwrap_or_else(|| self.builder.ext_inst_import("GLSL.std.450"));
+        self.glsl_std_450 = Some(id);
+        id
+    }
+
+    ///Starts a new value scope. Values emitted within the scope are not reused after [pop_scope](Serializer::pop_scope). Must be used
+    /// by operations that emit blocks which do not dominate the code that follows, for instance the branches of an if-else.
+    pub fn push_scope(&mut self) {
+        self.values.push(FxHashMap::default());
+    }
+
+    ///Ends the current value scope, see [push_scope](Serializer::push_scope).
+    pub fn pop_scope(&mut self) {
+        assert!(self.values.len() > 1, "Tried to pop the function's root scope");
+        self.values.pop();
+    }
+
+    ///Returns the label id of the block that is currently serialized into, if there is any. Needed for instance to
+    /// specify the parent blocks of an `OpPhi` instruction.
+    pub fn current_block_label(&self) -> Option<Word> {
+        let function = self.builder.selected_function()?;
+        let block = self.builder.selected_block()?;
+        self.builder.module_ref().functions[function].blocks[block]
+            .label
+            .as_ref()?
+            .result_id
+    }
+
+    ///Returns the id of `T`'s SpirV type. `O` is the operation that needs the type, it is reported if `T` has no SpirV type.
+    pub fn type_id<T: IntoSpvType, O: ?Sized>(&mut self) -> Result<Word, SerializeError> {
+        T::spirv_type_id(self).ok_or_else(|| SerializeError::UnsupportedType {
+            operation: type_name::<O>().to_string(),
+            ty: T::into_spv_type(),
+        })
+    }
+
     ///Tries to find a variable of type `T` in the runtime signature of the function. Returns the data id  at which the data is loaded if one is found. Otherwise the variables defined default value is loaded there.
     /// or nothing.
-    pub fn get_variable<T: IntoSpvType>(&mut self, name: &str, default_value: T) -> DataId<T>
+    pub fn get_variable<T: IntoSpvType>(
+        &mut self,
+        name: &str,
+        default_value: T,
+    ) -> Result<DataId<T>, SerializeError>
     where
         Constant<(), T>: Operation<Input = (), Output = DataId<T>>,
     {
@@ -53,14 +271,18 @@ impl<'a, 'b> Serializer<'a, 'b> {
         if let Some(param) = self.interface.get_parameter::<T>(shash, &spvtype) {
             //inline load procedure
             let did = self
-                .builder
-                .composite_extract(param.spirv_type_id, None, param.composite_id, [1])
-                .unwrap();
+                .value(|builder| {
+                    builder.composite_extract(param.spirv_type_id, None, param.composite_id, [1])
+                })
+                .map_err(|error| SerializeError::Builder {
+                    operation: format!("variable {}", name),
+                    error,
+                })?;
 
-            DataId {
+            Ok(DataId {
                 id: did,
                 ty: PhantomData,
-            }
+            })
         } else {
             #[cfg(feature = "logging")]
             log::warn!(
@@ -77,6 +299,105 @@ impl<'a, 'b> Serializer<'a, 'b> {
     }
 }
 
+///Errors that can occur while an operation is serialized. `operation` names the operation that failed, usually by its
+/// type name.
+#[derive(Debug)]
+pub enum SerializeError {
+    ///Occurs if the SpirV builder fails to emit an instruction, for instance because no function or block is selected.
+    Builder {
+        operation: String,
+        error: rspirv::dr::Error,
+    },
+    ///Occurs if a type has no representation in the SpirV module.
+    UnsupportedType { operation: String, ty: SpvType },
+    ///Occurs if a result is accessed that is not defined in the current context.
+    UnknownResult { operation: String, name: String },
+    ///Occurs if a result is accessed as another type than it was defined with.
+    TypeMismatch {
+        operation: String,
+        name: String,
+        expected: String,
+        found: String,
+    },
+    ///Occurs if an element of a vector is selected that the vector does not have.
+    ElementOutOfBounds {
+        operation: String,
+        element: u32,
+        elements: u32,
+    },
+    ///Occurs if a vector is built from a number of elements that does not match its length.
+    ElementCountMismatch {
+        operation: String,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl SerializeError {
+    ///Wraps the `error` of the SpirV builder that occurred while serializing the operation `O`.
+    pub fn builder<O: ?Sized>(error: rspirv::dr::Error) -> Self {
+        SerializeError::Builder {
+            operation: type_name::<O>().to_string(),
+            error,
+        }
+    }
+}
+
+impl std::fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SerializeError::Builder { operation, error } => {
+                write!(f, "Failed to emit {}: {}", operation, error)
+            }
+            SerializeError::UnsupportedType { operation, ty } => write!(
+                f,
+                "Type {:?} of {} has no SpirV representation",
+                ty, operation
+            ),
+            SerializeError::UnknownResult { operation, name } => {
+                write!(f, "{} expected a result with name {}", operation, name)
+            }
+            SerializeError::TypeMismatch {
+                operation,
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} expected result {} of type {}, but found {}",
+                operation, name, expected, found
+            ),
+            SerializeError::ElementOutOfBounds {
+                operation,
+                element,
+                elements,
+            } => write!(
+                f,
+                "{} selects element {} of a vector with {} elements",
+                operation, element, elements
+            ),
+            SerializeError::ElementCountMismatch {
+                operation,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} expected {} elements, but found {}",
+                operation, expected, found
+            ),
+        }
+    }
+}
+
+impl Error for SerializeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SerializeError::Builder { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
 ///Type data id with type tag
 #[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Hash)]
 pub struct DataId<T> {
@@ -105,5 +426,16 @@ pub trait Operation {
     type Input;
     type Output;
 
-    fn serialize(&mut self, serializer: &mut Serializer, input: Self::Input) -> Self::Output;
+    ///Emits the operation into `serializer`'s module. Fails with a [SerializeError] instead of panicking, for instance if
+    /// a named result does not exist. The module might contain parts of the operation afterwards, therefore it should be
+    /// discarded.
+    fn serialize(
+        &mut self,
+        serializer: &mut Serializer,
+        input: Self::Input,
+    ) -> Result<Self::Output, SerializeError>;
+
+    ///Evaluates the operation on the CPU. Works like [serialize](Operation::serialize), but instead of emitting code the
+    /// result is calculated immediately and saved within `evaluator`.
+    fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output;
 }
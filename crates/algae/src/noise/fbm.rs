@@ -0,0 +1,89 @@
+use glam::{Vec2, Vec3};
+
+use super::Coordinates;
+use crate::{
+    expr::*, sdf::Parameter, BoxOperation, DataId, Evaluator, Operation, SerializeError, Serializer,
+};
+
+///Fractal Brownian motion, the sum of `octaves` samples of `noise`. Each octave is sampled at `lacunarity` times the
+/// frequency of the previous one, and weighted with `gain` times its weight. The sum is divided by the sum of all
+/// weights, therefore the result stays in the range of `noise`.
+pub struct Fbm<V> {
+    pub noise: BoxOperation<DataId<V>, f32>,
+    ///Number of samples, at least 1.
+    pub octaves: u32,
+    pub lacunarity: f32,
+    pub gain: f32,
+}
+
+impl<V> Fbm<V> {
+    ///Creates the fBm of `noise` with a lacunarity of `2` and a gain of `0.5`. Panics if there are no `octaves`.
+    pub fn new(noise: impl Parameter<DataId<V>, f32>, octaves: u32) -> Self {
+        assert!(octaves >= 1, "Fbm needs at least 1 octave, got {}", octaves);
+        Fbm {
+            noise: noise.into_operation(),
+            octaves,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+
+    #[must_use]
+    pub fn with_lacunarity(mut self, lacunarity: f32) -> Self {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    #[must_use]
+    pub fn with_gain(mut self, gain: f32) -> Self {
+        self.gain = gain;
+        self
+    }
+
+    ///Frequency and weight of each octave.
+    fn octaves(&self) -> impl Iterator<Item = (f32, f32)> {
+        let (lacunarity, gain) = (self.lacunarity, self.gain);
+        (0..self.octaves as i32).map(move |octave| (lacunarity.powi(octave), gain.powi(octave)))
+    }
+
+    fn normalization(&self) -> f32 {
+        1.0 / self.octaves().map(|(_, weight)| weight).sum::<f32>()
+    }
+}
+
+macro_rules! impl_fbm {
+    ($($pos:ty),+) => {
+        $(
+            impl Operation for Fbm<$pos> {
+                type Input = DataId<$pos>;
+                type Output = DataId<f32>;
+
+                fn serialize(
+                    &mut self,
+                    serializer: &mut Serializer,
+                    input: Self::Input,
+                ) -> Result<Self::Output, SerializeError> {
+                    let mut sum = constant(0.0);
+                    for (frequency, weight) in self.octaves().collect::<Vec<_>>() {
+                        let position = serializer.bind(<$pos>::scaled(input, frequency))?;
+                        let sample = self.noise.serialize(serializer, position)?;
+                        sum = add(sum, mul(value(sample), constant(weight)));
+                    }
+                    serializer.bind(mul(sum, constant(self.normalization())))
+                }
+
+                fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                    let mut sum = constant(0.0);
+                    for (frequency, weight) in self.octaves().collect::<Vec<_>>() {
+                        let position = evaluated(evaluator.bind(<$pos>::scaled(input, frequency)));
+                        let sample = self.noise.evaluate(evaluator, position);
+                        sum = add(sum, mul(value(sample), constant(weight)));
+                    }
+                    evaluated(evaluator.bind(mul(sum, constant(self.normalization()))))
+                }
+            }
+        )+
+    };
+}
+
+impl_fbm!(f32, Vec2, Vec3);
@@ -0,0 +1,92 @@
+//! Noises that interpolate between the corners of the lattice cell the position is in. Both use the quintic fade curve
+//! of Ken Perlin's [Improving Noise](https://mrl.cs.nyu.edu/~perlin/paper445.pdf), therefore they are smooth across
+//! cell borders.
+use super::{corners, gradient, hash, noise, offset, random, signed, split};
+use crate::{expr::*, DataId};
+
+noise!(
+    ///Value noise, in `[-1, 1]`. Interpolates between random values at the lattice points.
+    ValueNoise,
+    value_noise
+);
+
+noise!(
+    ///Perlin's gradient noise, roughly in `[-1, 1]`. Interpolates between random gradients at the lattice points, which
+    /// results in less blocky noise than [ValueNoise]. The noise is `0` at all lattice points.
+    PerlinNoise,
+    perlin_noise
+);
+
+///Scales Perlin noise to roughly `[-1, 1]`, depending on the number of dimensions.
+const PERLIN_SCALE: [f32; 3] = [2.6, 1.5, 1.15];
+
+///`6t^5 - 15t^4 + 10t^3`
+fn fade<C: Context>(context: &mut C, t: DataId<f32>) -> Result<DataId<f32>, C::Error> {
+    let polynomial = add(
+        mul(value(t), sub(mul(value(t), constant(6.0)), constant(15.0))),
+        constant(10.0),
+    );
+    context.bind(mul(mul(mul(value(t), value(t)), value(t)), polynomial))
+}
+
+///Interpolates between the `values` at the corners of a cell, ordered like [corners], with the `weights` per axis.
+fn interpolate(mut values: Vec<Expr<f32>>, weights: &[DataId<f32>]) -> Expr<f32> {
+    for weight in weights.iter().rev() {
+        let upper = values.split_off(values.len() / 2);
+        values = values
+            .into_iter()
+            .zip(upper)
+            .map(|(lower, upper)| mix(lower, upper, value(*weight)))
+            .collect();
+    }
+    values.pop().unwrap()
+}
+
+fn value_noise<C: Context>(
+    context: &mut C,
+    position: &[DataId<f32>],
+    seed: u32,
+) -> Result<DataId<f32>, C::Error> {
+    let (cell, fraction) = split(context, position)?;
+    let weights = fraction
+        .iter()
+        .map(|f| fade(context, *f))
+        .collect::<Result<Vec<_>, _>>()?;
+    let values = corners(position.len())
+        .map(|corner| {
+            let hash = hash(context, offset(&cell, &corner), seed)?;
+            Ok(signed(random(hash, 8, 24)))
+        })
+        .collect::<Result<_, _>>()?;
+    context.bind(interpolate(values, &weights))
+}
+
+fn perlin_noise<C: Context>(
+    context: &mut C,
+    position: &[DataId<f32>],
+    seed: u32,
+) -> Result<DataId<f32>, C::Error> {
+    let (cell, fraction) = split(context, position)?;
+    let weights = fraction
+        .iter()
+        .map(|f| fade(context, *f))
+        .collect::<Result<Vec<_>, _>>()?;
+    let values = corners(position.len())
+        .map(|corner| {
+            let hash = hash(context, offset(&cell, &corner), seed)?;
+            //dot(gradient, fraction - corner)
+            let dot = gradient(hash, position.len())
+                .into_iter()
+                .zip(fraction.iter().zip(&corner))
+                .map(|(g, (f, c))| mul(g, sub(value(*f), constant(*c))))
+                .reduce(add)
+                .unwrap();
+            Ok(dot)
+        })
+        .collect::<Result<_, _>>()?;
+    let noise = mul(
+        interpolate(values, &weights),
+        constant(PERLIN_SCALE[position.len() - 1]),
+    );
+    context.bind(noise)
+}
@@ -0,0 +1,237 @@
+//! Procedural noise, built from the existing operations. Each noise is an [Operation](crate::Operation) that takes the
+//! position it is sampled at as input and returns the noise value at that position. Noise can be sampled at `f32`, `Vec2`
+//! and `Vec3` positions.
+//!
+//! All noises are based on an integer lattice. Each lattice point gets a pseudo random value from a PCG hash of its
+//! coordinates, as described by Jarzynski and Olano in
+//! [Hash Functions for GPU Rendering](https://jcgt.org/published/0009/03/02/). The hash only uses 32bit integer
+//! arithmetic, therefore the CPU evaluation matches the GPU, up to the usual floating point differences.
+//!
+//! - [ValueNoise] interpolates random values at the lattice points.
+//! - [PerlinNoise] interpolates random gradients at the lattice points.
+//! - [SimplexNoise] sums random gradients of the corners of a simplex lattice.
+//! - [WorleyNoise] returns the distance to the nearest random feature point.
+//!
+//! [Fbm] sums several octaves of any noise, which is the usual base of terrain and materials.
+//!
+//! # Example
+//! ```rust
+//! use algae::{
+//!     glam::Vec2,
+//!     noise::{Fbm, PerlinNoise},
+//!     operations::{Constant, WithInput},
+//!     Evaluator,
+//! };
+//!
+//! let mut terrain = WithInput {
+//!     input: Box::new(Constant::new(Vec2::new(0.25, 3.5))),
+//!     inner: Box::new(Fbm::new(PerlinNoise::<Vec2>::new(7), 4).with_gain(0.4)),
+//! };
+//! let height = Evaluator::new().evaluate(&mut terrain, ());
+//! assert!((-1.0..=1.0).contains(&height));
+//! ```
+use glam::{Vec2, Vec3};
+
+use crate::{expr::*, DataId};
+
+mod fbm;
+mod lattice;
+mod simplex;
+mod worley;
+
+pub use fbm::Fbm;
+pub use lattice::{PerlinNoise, ValueNoise};
+pub use simplex::SimplexNoise;
+pub use worley::WorleyNoise;
+
+///Positions noise can be sampled at.
+pub(crate) trait Coordinates: Sized {
+    ///Splits `position` into its components.
+    fn components<C: Context>(
+        context: &mut C,
+        position: DataId<Self>,
+    ) -> Result<Vec<DataId<f32>>, C::Error>;
+    ///`position * factor`
+    fn scaled(position: DataId<Self>, factor: f32) -> Expr<Self>;
+}
+
+impl Coordinates for f32 {
+    fn components<C: Context>(
+        _context: &mut C,
+        position: DataId<Self>,
+    ) -> Result<Vec<DataId<f32>>, C::Error> {
+        Ok(vec![position])
+    }
+
+    fn scaled(position: DataId<Self>, factor: f32) -> Expr<Self> {
+        mul(value(position), constant(factor))
+    }
+}
+
+macro_rules! impl_vec_coordinates {
+    ($($vec:ty, $dimensions:expr);+) => {
+        $(
+            impl Coordinates for $vec {
+                fn components<C: Context>(
+                    context: &mut C,
+                    position: DataId<Self>,
+                ) -> Result<Vec<DataId<f32>>, C::Error> {
+                    (0..$dimensions)
+                        .map(|i| context.bind(element(value(position), i)))
+                        .collect()
+                }
+
+                fn scaled(position: DataId<Self>, factor: f32) -> Expr<Self> {
+                    scale(value(position), constant(factor))
+                }
+            }
+        )+
+    };
+}
+
+impl_vec_coordinates!(Vec2, 2; Vec3, 3);
+
+///Declares the noise `$name` with a seed, and implements it for all positions via `$function`, which calculates the
+/// noise from the components of the position.
+macro_rules! noise {
+    ($(#[$doc:meta])* $name:ident, $function:ident) => {
+        $(#[$doc])*
+        pub struct $name<V> {
+            ///Different seeds result in unrelated noise.
+            pub seed: u32,
+            ty: std::marker::PhantomData<V>,
+        }
+
+        impl<V> $name<V> {
+            pub fn new(seed: u32) -> Self {
+                $name {
+                    seed,
+                    ty: std::marker::PhantomData,
+                }
+            }
+        }
+
+        noise!(@impl $name, $function, f32, glam::Vec2, glam::Vec3);
+    };
+    (@impl $name:ident, $function:ident, $($pos:ty),+) => {
+        $(
+            impl crate::Operation for $name<$pos> {
+                type Input = crate::DataId<$pos>;
+                type Output = crate::DataId<f32>;
+
+                fn serialize(
+                    &mut self,
+                    serializer: &mut crate::Serializer,
+                    input: Self::Input,
+                ) -> Result<Self::Output, crate::SerializeError> {
+                    let position =
+                        <$pos as crate::noise::Coordinates>::components(serializer, input)?;
+                    $function(serializer, &position, self.seed)
+                }
+
+                fn evaluate(
+                    &mut self,
+                    evaluator: &mut crate::Evaluator,
+                    input: Self::Input,
+                ) -> Self::Output {
+                    let position = crate::expr::evaluated(
+                        <$pos as crate::noise::Coordinates>::components(evaluator, input),
+                    );
+                    crate::expr::evaluated($function(evaluator, &position, self.seed))
+                }
+            }
+        )+
+    };
+}
+
+pub(crate) use noise;
+
+///PCG hash of `v`.
+fn pcg<C: Context>(context: &mut C, v: Expr<u32>) -> Result<DataId<u32>, C::Error> {
+    let state = context.bind(add(mul(v, constant(747796405u32)), constant(2891336453u32)))?;
+    let shift = add(shift_right(value(state), constant(28u32)), constant(4u32));
+    let word = context.bind(mul(
+        bitwise_xor(shift_right(value(state), shift), value(state)),
+        constant(277803737u32),
+    ))?;
+    context.bind(bitwise_xor(
+        shift_right(value(word), constant(22u32)),
+        value(word),
+    ))
+}
+
+///Hash of the lattice point with the integer valued coordinates `point`.
+fn hash<C: Context>(
+    context: &mut C,
+    point: Vec<Expr<f32>>,
+    seed: u32,
+) -> Result<DataId<u32>, C::Error> {
+    let mut hash = context.bind(constant(seed))?;
+    for coordinate in point {
+        let bits = convert::<i32, u32>(convert::<f32, i32>(coordinate));
+        hash = pcg(context, add(value(hash), bits))?;
+    }
+    Ok(hash)
+}
+
+///Uniformly distributed value in `[0, 1)` made of `bits` bits of `hash`, starting at bit `shift`.
+fn random(hash: DataId<u32>, shift: u32, bits: u32) -> Expr<f32> {
+    let bits_value = bitwise_and(
+        shift_right(value(hash), constant(shift)),
+        constant((1u32 << bits) - 1),
+    );
+    mul(
+        convert::<u32, f32>(bits_value),
+        constant(1.0 / (1u32 << bits) as f32),
+    )
+}
+
+///Maps `x` from `[0, 1)` to `[-1, 1)`.
+fn signed(x: Expr<f32>) -> Expr<f32> {
+    sub(mul(x, constant(2.0)), constant(1.0))
+}
+
+///Random vector in `[-1, 1)^dimensions`, each component is made of 10 bits of `hash`.
+fn gradient(hash: DataId<u32>, dimensions: usize) -> Vec<Expr<f32>> {
+    (0..dimensions as u32)
+        .map(|i| signed(random(hash, i * 10, 10)))
+        .collect()
+}
+
+///Components of the lattice cell and of the position within that cell.
+type Split = (Vec<DataId<f32>>, Vec<DataId<f32>>);
+
+///Splits `position` into the lattice cell it is in, which is the lattice point with the smallest coordinates of the
+/// cell, and the position within that cell.
+fn split<C: Context>(context: &mut C, position: &[DataId<f32>]) -> Result<Split, C::Error> {
+    let mut cells = Vec::with_capacity(position.len());
+    let mut fractions = Vec::with_capacity(position.len());
+    for p in position {
+        let cell = context.bind(floor(value(*p)))?;
+        fractions.push(context.bind(sub(value(*p), value(cell)))?);
+        cells.push(cell);
+    }
+    Ok((cells, fractions))
+}
+
+///`cell + offset`, as coordinates that can be hashed.
+fn offset(cell: &[DataId<f32>], offset: &[f32]) -> Vec<Expr<f32>> {
+    cell.iter()
+        .zip(offset)
+        .map(|(c, o)| add(value(*c), constant(*o)))
+        .collect()
+}
+
+///Offsets from a lattice cell to its `2^dimensions` corners. The offset on axis `i` of corner `c` is bit `i` of `c`.
+fn corners(dimensions: usize) -> impl Iterator<Item = Vec<f32>> {
+    (0..1u32 << dimensions).map(move |c| (0..dimensions).map(|i| ((c >> i) & 1) as f32).collect())
+}
+
+///Offsets from a lattice cell to the `3^dimensions` cells around it, including itself.
+fn neighbours(dimensions: usize) -> impl Iterator<Item = Vec<f32>> {
+    (0..3u32.pow(dimensions as u32)).map(move |n| {
+        (0..dimensions as u32)
+            .map(|i| (n / 3u32.pow(i) % 3) as f32 - 1.0)
+            .collect()
+    })
+}
@@ -0,0 +1,149 @@
+//! Simplex noise as described by Stefan Gustavson in
+//! [Simplex noise demystified](https://weber.itn.liu.se/~stegu/simplexnoise/simplexnoise.pdf), for any number of
+//! dimensions.
+use super::{gradient, hash, noise};
+use crate::{expr::*, DataId};
+
+noise!(
+    ///Ken Perlin's simplex noise, roughly in `[-1, 1]`. Sums random gradients at the corners of the simplex the position
+    /// is in, which is cheaper than [PerlinNoise](super::PerlinNoise) for higher dimensions and has no axis aligned
+    /// artifacts. The noise is `0` at all lattice points.
+    SimplexNoise,
+    simplex_noise
+);
+
+///Constants of the simplex lattice per number of dimensions.
+struct Lattice {
+    ///Skews the position into the simplex lattice, `(sqrt(n + 1) - 1) / n`.
+    skew: f32,
+    ///Skews the lattice back, `(1 - 1 / sqrt(n + 1)) / n`.
+    unskew: f32,
+    ///Squared radius of the contribution of each corner.
+    radius: f32,
+    ///Scales the result to roughly `[-1, 1]`.
+    scale: f32,
+}
+
+const LATTICES: [Lattice; 3] = [
+    Lattice {
+        skew: 0.0,
+        unskew: 0.0,
+        radius: 1.0,
+        scale: 4.0,
+    },
+    Lattice {
+        skew: 0.3660254,
+        unskew: 0.21132487,
+        radius: 0.5,
+        scale: 72.0,
+    },
+    Lattice {
+        skew: 1.0 / 3.0,
+        unskew: 1.0 / 6.0,
+        radius: 0.5,
+        scale: 64.0,
+    },
+];
+
+fn simplex_noise<C: Context>(
+    context: &mut C,
+    position: &[DataId<f32>],
+    seed: u32,
+) -> Result<DataId<f32>, C::Error> {
+    let dimensions = position.len();
+    let lattice = &LATTICES[dimensions - 1];
+
+    let sum = |ids: &[DataId<f32>]| ids.iter().map(|id| value(*id)).reduce(add).unwrap();
+    let skew = context.bind(mul(sum(position), constant(lattice.skew)))?;
+    let cell = position
+        .iter()
+        .map(|p| context.bind(floor(add(value(*p), value(skew)))))
+        .collect::<Result<Vec<_>, _>>()?;
+    let unskew = context.bind(mul(sum(&cell), constant(lattice.unskew)))?;
+    //Position relative to the first corner of the simplex.
+    let first = position
+        .iter()
+        .zip(&cell)
+        .map(|(p, c)| context.bind(add(sub(value(*p), value(*c)), value(unskew))))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    //The simplex is traversed along the axes in the order of decreasing components of `first`. The rank of an axis is
+    // the number of axes that are traversed before it.
+    let ranks = (0..dimensions)
+        .map(|i| {
+            let rank = (0..dimensions)
+                .filter(|j| *j != i)
+                .map(|j| {
+                    let before = if j < i {
+                        greater_equal(value(first[j]), value(first[i]))
+                    } else {
+                        greater(value(first[j]), value(first[i]))
+                    };
+                    select(before, constant(1.0), constant(0.0))
+                })
+                .reduce(add)
+                .unwrap_or_else(|| constant(0.0));
+            context.bind(rank)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    //Offsets of the inner corners, the first corner is at `cell`, the last one at `cell + 1`.
+    let inner_offsets = (1..dimensions)
+        .map(|corner| {
+            ranks
+                .iter()
+                .map(|rank| {
+                    let traversed = less(value(*rank), constant(corner as f32));
+                    context.bind(select(traversed, constant(1.0), constant(0.0)))
+                })
+                .collect()
+        })
+        .collect::<Result<Vec<Vec<_>>, _>>()?;
+    let corner_offset = |corner: usize, axis: usize| match corner {
+        0 => constant(0.0),
+        c if c == dimensions => constant(1.0),
+        c => value(inner_offsets[c - 1][axis]),
+    };
+
+    let mut noise = None;
+    for corner in 0..=dimensions {
+        let point = (0..dimensions)
+            .map(|i| add(value(cell[i]), corner_offset(corner, i)))
+            .collect();
+        let hash = hash(context, point, seed)?;
+        let relative = (0..dimensions)
+            .map(|i| {
+                context.bind(add(
+                    sub(value(first[i]), corner_offset(corner, i)),
+                    constant(corner as f32 * lattice.unskew),
+                ))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        //max(radius - |relative|^2, 0)^4 * dot(gradient, relative)
+        let falloff = context.bind(max(
+            sub(
+                constant(lattice.radius),
+                relative
+                    .iter()
+                    .map(|r| mul(value(*r), value(*r)))
+                    .reduce(add)
+                    .unwrap(),
+            ),
+            constant(0.0),
+        ))?;
+        let falloff = context.bind(mul(value(falloff), value(falloff)))?;
+        let contribution = mul(
+            mul(value(falloff), value(falloff)),
+            gradient(hash, dimensions)
+                .into_iter()
+                .zip(&relative)
+                .map(|(g, r)| mul(g, value(*r)))
+                .reduce(add)
+                .unwrap(),
+        );
+        noise = Some(match noise {
+            Some(noise) => add(noise, contribution),
+            None => contribution,
+        });
+    }
+    context.bind(mul(noise.unwrap(), constant(lattice.scale)))
+}
@@ -0,0 +1,36 @@
+//! Cellular noise as described by Steven Worley in "A Cellular Texture Basis Function".
+use super::{hash, neighbours, noise, offset, random, split};
+use crate::{expr::*, DataId};
+
+noise!(
+    ///Worley noise, the distance to the nearest feature point. Each lattice cell contains one feature point at a random
+    /// position. The distance is `0` at the feature points and at most `sqrt(n)` for `n` dimensions.
+    WorleyNoise,
+    worley_noise
+);
+
+fn worley_noise<C: Context>(
+    context: &mut C,
+    position: &[DataId<f32>],
+    seed: u32,
+) -> Result<DataId<f32>, C::Error> {
+    let (cell, fraction) = split(context, position)?;
+    let mut nearest = None;
+    //The nearest feature point is always within the cells around the position.
+    for neighbour in neighbours(position.len()) {
+        let hash = hash(context, offset(&cell, &neighbour), seed)?;
+        let mut squared_distance = constant(0.0);
+        for (i, (n, f)) in neighbour.iter().zip(&fraction).enumerate() {
+            let delta = context.bind(sub(
+                add(constant(*n), random(hash, i as u32 * 10, 10)),
+                value(*f),
+            ))?;
+            squared_distance = add(squared_distance, mul(value(delta), value(delta)));
+        }
+        nearest = Some(match nearest {
+            Some(nearest) => min(nearest, squared_distance),
+            None => squared_distance,
+        });
+    }
+    context.bind(sqrt(nearest.unwrap()))
+}
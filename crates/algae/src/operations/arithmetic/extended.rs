@@ -0,0 +1,331 @@
+//! Implements the float operations that map directly to an instruction of the GLSL.std.450 extended instruction set.
+//! Each operation is implemented for `f32` and `Vec2`-`Vec4`, where vectors are calculated component wise.
+
+use glam::{Vec2, Vec3, Vec4};
+use rspirv::dr::Operand;
+
+use super::trigonomy::{
+    ArcCosine, ArcSine, ArcTangent, ArcTangent2, HyperbolicArcCosine, HyperbolicArcSine,
+    HyperbolicArcTangent, HyperbolicCosine, HyperbolicSine, HyperbolicTangent,
+};
+use super::{
+    Ceil, Clamp, Exp, Exp2, Floor, Fma, Fract, InverseSqrt, Log, Log2, Mix, Modulo, Pow, Remainder,
+    Round, Sign, SmoothStep, Step, Trunc,
+};
+use crate::{DataId, Evaluator, Operation};
+
+///Component wise access to floats and float vectors. Used to evaluate the extended instructions on the CPU.
+trait FloatComponents: Copy {
+    ///Calculates `f` for each component, where `f` gets the component of each of the `args`.
+    fn zip_n<const N: usize>(args: [Self; N], f: impl Fn([f32; N]) -> f32) -> Self;
+}
+
+impl FloatComponents for f32 {
+    fn zip_n<const N: usize>(args: [Self; N], f: impl Fn([f32; N]) -> f32) -> Self {
+        f(args)
+    }
+}
+
+macro_rules! impl_float_components_vec {
+    ($vecty:ty, $($element_name:ident),+) => {
+        impl FloatComponents for $vecty {
+            fn zip_n<const N: usize>(args: [Self; N], f: impl Fn([f32; N]) -> f32) -> Self {
+                <$vecty>::new($(f(args.map(|a| a.$element_name))),+)
+            }
+        }
+    };
+}
+
+impl_float_components_vec!(Vec2, x, y);
+impl_float_components_vec!(Vec3, x, y, z);
+impl_float_components_vec!(Vec4, x, y, z, w);
+
+///Implements the operation `$op` with the operand fields `$field` via the extended instruction `$inst` for each of the
+/// supplied float types. `$eval` is called per component with an array of the operands when evaluating.
+macro_rules! impl_float_ext {
+    ($op:ident, $fields:tt, $inst:expr, $eval:expr, $($fty:ty),+) => {
+        $(
+            impl_float_ext!(@impl $op, $fields, $inst, $eval, $fty);
+        )+
+    };
+    (@impl $op:ident, [$($field:ident),+], $inst:expr, $eval:expr, $fty:ty) => {
+        impl<I: Clone> Operation for $op<I, $fty> {
+            type Input = I;
+            type Output = DataId<$fty>;
+
+            fn serialize(
+                &mut self,
+                serializer: &mut crate::Serializer,
+                input: Self::Input,
+            ) -> Result<Self::Output, crate::SerializeError> {
+                let operands = [
+                    $(Operand::IdRef(self.$field.serialize(serializer, input.clone())?.id)),+
+                ];
+                let t_res = serializer.type_id::<$fty, Self>()?;
+
+                //Load instructionset
+                let ext_instset_id = serializer.glsl_std_450();
+                Ok(DataId::from(
+                    serializer
+                        .value(|builder| {
+                            builder.ext_inst(t_res, None, ext_instset_id, $inst, operands)
+                        })
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let ids = [$(self.$field.evaluate(evaluator, input.clone())),+];
+                let res = <$fty>::zip_n(ids.map(|id| evaluator.load(id)), $eval);
+                evaluator.store(res)
+            }
+        }
+    };
+}
+
+impl_float_ext!(Round, [inner], 1, |[x]| x.round(), f32, Vec2, Vec3, Vec4);
+impl_float_ext!(Trunc, [inner], 3, |[x]| x.trunc(), f32, Vec2, Vec3, Vec4);
+impl_float_ext!(
+    Sign,
+    [inner],
+    6,
+    |[x]| if x > 0.0 {
+        1.0
+    } else if x < 0.0 {
+        -1.0
+    } else {
+        0.0
+    },
+    f32,
+    Vec2,
+    Vec3,
+    Vec4
+);
+impl_float_ext!(Floor, [inner], 8, |[x]| x.floor(), f32, Vec2, Vec3, Vec4);
+impl_float_ext!(Ceil, [inner], 9, |[x]| x.ceil(), f32, Vec2, Vec3, Vec4);
+impl_float_ext!(
+    Fract,
+    [inner],
+    10,
+    |[x]| x - x.floor(),
+    f32,
+    Vec2,
+    Vec3,
+    Vec4
+);
+
+impl_float_ext!(ArcSine, [inner], 16, |[x]| x.asin(), f32, Vec2, Vec3, Vec4);
+impl_float_ext!(
+    ArcCosine,
+    [inner],
+    17,
+    |[x]| x.acos(),
+    f32,
+    Vec2,
+    Vec3,
+    Vec4
+);
+impl_float_ext!(
+    ArcTangent,
+    [inner],
+    18,
+    |[x]| x.atan(),
+    f32,
+    Vec2,
+    Vec3,
+    Vec4
+);
+impl_float_ext!(
+    HyperbolicSine,
+    [inner],
+    19,
+    |[x]| x.sinh(),
+    f32,
+    Vec2,
+    Vec3,
+    Vec4
+);
+impl_float_ext!(
+    HyperbolicCosine,
+    [inner],
+    20,
+    |[x]| x.cosh(),
+    f32,
+    Vec2,
+    Vec3,
+    Vec4
+);
+impl_float_ext!(
+    HyperbolicTangent,
+    [inner],
+    21,
+    |[x]| x.tanh(),
+    f32,
+    Vec2,
+    Vec3,
+    Vec4
+);
+impl_float_ext!(
+    HyperbolicArcSine,
+    [inner],
+    22,
+    |[x]| x.asinh(),
+    f32,
+    Vec2,
+    Vec3,
+    Vec4
+);
+impl_float_ext!(
+    HyperbolicArcCosine,
+    [inner],
+    23,
+    |[x]| x.acosh(),
+    f32,
+    Vec2,
+    Vec3,
+    Vec4
+);
+impl_float_ext!(
+    HyperbolicArcTangent,
+    [inner],
+    24,
+    |[x]| x.atanh(),
+    f32,
+    Vec2,
+    Vec3,
+    Vec4
+);
+impl_float_ext!(
+    ArcTangent2,
+    [y, x],
+    25,
+    |[y, x]| y.atan2(x),
+    f32,
+    Vec2,
+    Vec3,
+    Vec4
+);
+
+impl_float_ext!(
+    Pow,
+    [base, exponent],
+    26,
+    |[b, e]| b.powf(e),
+    f32,
+    Vec2,
+    Vec3,
+    Vec4
+);
+impl_float_ext!(Exp, [inner], 27, |[x]| x.exp(), f32, Vec2, Vec3, Vec4);
+impl_float_ext!(Log, [inner], 28, |[x]| x.ln(), f32, Vec2, Vec3, Vec4);
+impl_float_ext!(Exp2, [inner], 29, |[x]| x.exp2(), f32, Vec2, Vec3, Vec4);
+impl_float_ext!(Log2, [inner], 30, |[x]| x.log2(), f32, Vec2, Vec3, Vec4);
+impl_float_ext!(
+    InverseSqrt,
+    [inner],
+    32,
+    |[x]| 1.0 / x.sqrt(),
+    f32,
+    Vec2,
+    Vec3,
+    Vec4
+);
+
+impl_float_ext!(
+    Clamp,
+    [inner, min, max],
+    43,
+    |[x, min, max]| x.max(min).min(max),
+    f32,
+    Vec2,
+    Vec3,
+    Vec4
+);
+impl_float_ext!(
+    Mix,
+    [a, b, t],
+    46,
+    |[a, b, t]| a * (1.0 - t) + b * t,
+    f32,
+    Vec2,
+    Vec3,
+    Vec4
+);
+impl_float_ext!(
+    Step,
+    [edge, x],
+    48,
+    |[edge, x]| if x < edge { 0.0 } else { 1.0 },
+    f32,
+    Vec2,
+    Vec3,
+    Vec4
+);
+impl_float_ext!(
+    SmoothStep,
+    [edge0, edge1, x],
+    49,
+    |[edge0, edge1, x]| {
+        let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    },
+    f32,
+    Vec2,
+    Vec3,
+    Vec4
+);
+impl_float_ext!(
+    Fma,
+    [a, b, c],
+    50,
+    |[a, b, c]| a.mul_add(b, c),
+    f32,
+    Vec2,
+    Vec3,
+    Vec4
+);
+
+///Implements the float division remainders `Remainder` and `Modulo`, which are core instructions instead of extended ones.
+macro_rules! impl_float_rem {
+    ($op:ident, $builder_fn:ident, $eval:expr, $($fty:ty),+) => {
+        $(
+            impl<I: Clone> Operation for $op<I, $fty> {
+                type Input = I;
+                type Output = DataId<$fty>;
+
+                fn serialize(
+                    &mut self,
+                    serializer: &mut crate::Serializer,
+                    input: Self::Input,
+                ) -> Result<Self::Output, crate::SerializeError> {
+                    let ra = self.dividend.serialize(serializer, input.clone())?;
+                    let rb = self.divisor.serialize(serializer, input)?;
+                    let t_res = serializer.type_id::<$fty, Self>()?;
+                    Ok(DataId::from(
+                        serializer
+                            .value(|builder| builder.$builder_fn(t_res, None, ra.id, rb.id))
+                            .map_err(crate::SerializeError::builder::<Self>)?,
+                    ))
+                }
+
+                fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                    let ra = self.dividend.evaluate(evaluator, input.clone());
+                    let rb = self.divisor.evaluate(evaluator, input);
+                    let res = <$fty>::zip_n([evaluator.load(ra), evaluator.load(rb)], $eval);
+                    evaluator.store(res)
+                }
+            }
+        )+
+    };
+}
+
+impl_float_rem!(Remainder, f_rem, |[a, b]| a % b, f32, Vec2, Vec3, Vec4);
+impl_float_rem!(
+    Modulo,
+    f_mod,
+    |[a, b]| a - b * (a / b).floor(),
+    f32,
+    Vec2,
+    Vec3,
+    Vec4
+);
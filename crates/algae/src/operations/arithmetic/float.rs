@@ -1,201 +1,89 @@
 use glam::{Vec2, Vec3, Vec4};
 use rspirv::dr::Operand;
-use std::marker::PhantomData;
 
-use super::{Abs, Max, Min};
-use crate::operations::{
-    Addition, Division, Multiplication, Sqrt, Square, Subtraction, VecSelectElement,
+use super::{
+    Abs, Max, Min, VectorDividedByScalar, VectorMinusScalar, VectorPlusScalar, VectorTimesScalar,
 };
-use crate::spv_fi::IntoSpvType;
+use crate::operations::{Addition, Division, Multiplication, Negate, Sqrt, Square, Subtraction};
 use crate::DataId;
-use crate::Operation;
-
-impl<I: Clone> Operation for Addition<I, f32> {
-    type Input = I;
-    type Output = DataId<f32>;
-
-    fn serialize(
-        &mut self,
-        serializer: &mut crate::Serializer,
-        input: Self::Input,
-    ) -> Self::Output {
-        let ra = self.a.serialize(serializer, input.clone());
-        let rb = self.b.serialize(serializer, input);
-        let t_f32 = f32::spirv_type_id(serializer).unwrap();
-        DataId::from(
-            serializer
-                .builder_mut()
-                .f_add(t_f32, None, ra.id, rb.id)
-                .unwrap(),
-        )
-    }
-}
-macro_rules! vec_op_add {
-    ($vecty:ty, $nel:expr) => {
-        //In general this operations work by extracing each component of each type, doing the `op`
-        //on each pair, and then assembling the a new variable of the type.
-
-        impl<I: Clone> Operation for Addition<I, $vecty> {
-            type Input = I;
-            type Output = DataId<$vecty>;
-
-            fn serialize(
-                &mut self,
-                serializer: &mut crate::Serializer,
-                input: Self::Input,
-            ) -> Self::Output {
-                let t_f32 = f32::spirv_type_id(serializer).unwrap();
-                let t_vec = <$vecty>::spirv_type_id(serializer).unwrap();
-
-                //get inner results
-                let ra = self.a.serialize(serializer, input.clone());
-                let rb = self.b.serialize(serializer, input);
-
-                //load each element-pair, add them and collet the element ids
-                let mut result_ids = [0u32; $nel];
-                for idx in 0..$nel {
-                    let extracted_a = VecSelectElement {
-                        element: idx,
-                        inner: Box::new(ra),
-                    }
-                    .serialize(serializer, ());
-                    let extracted_b = VecSelectElement {
-                        element: idx,
-                        inner: Box::new(rb),
-                    }
-                    .serialize(serializer, ());
-
-                    //issue add and safe result at correct place
-                    result_ids[idx as usize] = serializer
-                        .builder_mut()
-                        .f_add(t_f32, None, extracted_a.id, extracted_b.id)
-                        .unwrap();
+use crate::{Evaluator, Operation};
+
+///Implements the two operand operation `$op` with the fields `$a` and `$b` via the builders `$builder_fn` for floats
+/// and float vectors. SpirV's float arithmetic works component wise on vectors, therefore each operation maps to a single instruction.
+macro_rules! impl_float_binop {
+    ($op:ident, $a:ident, $b:ident, $builder_fn:ident, $eval:tt, $($fty:ty),+) => {
+        $(
+            impl<I: Clone> Operation for $op<I, $fty> {
+                type Input = I;
+                type Output = DataId<$fty>;
+
+                fn serialize(
+                    &mut self,
+                    serializer: &mut crate::Serializer,
+                    input: Self::Input,
+                ) -> Result<Self::Output, crate::SerializeError> {
+                    let ra = self.$a.serialize(serializer, input.clone())?;
+                    let rb = self.$b.serialize(serializer, input)?;
+                    let t_res = serializer.type_id::<$fty, Self>()?;
+                    Ok(DataId::from(
+                        serializer
+                            .value(|builder| builder.$builder_fn(t_res, None, ra.id, rb.id))
+                            .map_err(crate::SerializeError::builder::<Self>)?,
+                    ))
                 }
 
-                //now build new vector and return
-                let vector_id = serializer
-                    .builder_mut()
-                    .composite_construct(t_vec, None, result_ids)
-                    .unwrap();
-
-                DataId {
-                    id: vector_id,
-                    ty: PhantomData,
+                fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                    let ra = self.$a.evaluate(evaluator, input.clone());
+                    let rb = self.$b.evaluate(evaluator, input);
+                    let res = evaluator.load(ra) $eval evaluator.load(rb);
+                    evaluator.store(res)
                 }
             }
-        }
+        )+
     };
 }
-vec_op_add!(Vec2, 2);
-vec_op_add!(Vec3, 3);
-vec_op_add!(Vec4, 4);
 
-impl<I: Clone> Operation for Subtraction<I, f32> {
-    type Input = I;
-    type Output = DataId<f32>;
-
-    fn serialize(
-        &mut self,
-        serializer: &mut crate::Serializer,
-        input: Self::Input,
-    ) -> Self::Output {
-        let ra = self.minuent.serialize(serializer, input.clone());
-        let rb = self.subtrahend.serialize(serializer, input);
-        let t_f32 = f32::spirv_type_id(serializer).unwrap();
-        DataId::from(
-            serializer
-                .builder_mut()
-                .f_sub(t_f32, None, ra.id, rb.id)
-                .unwrap(),
-        )
-    }
-}
-macro_rules! vec_op_sub {
-    ($vecty:ty, $nel:expr) => {
-        //In general this operations work by extracing each component of each type, doing the `op`
-        //on each pair, and then assembling the a new variable of the type.
-
-        impl<I: Clone> Operation for Subtraction<I, $vecty> {
-            type Input = I;
-            type Output = DataId<$vecty>;
-
-            fn serialize(
-                &mut self,
-                serializer: &mut crate::Serializer,
-                input: Self::Input,
-            ) -> Self::Output {
-                let t_f32 = f32::spirv_type_id(serializer).unwrap();
-                let t_vec = <$vecty>::spirv_type_id(serializer).unwrap();
-
-                //get inner results
-                let ra = self.minuent.serialize(serializer, input.clone());
-                let rb = self.subtrahend.serialize(serializer, input);
-
-                //load each element-pair, add them and collet the element ids
-                let mut result_ids = [0u32; $nel];
-                for idx in 0..$nel {
-                    let extracted_a = VecSelectElement {
-                        element: idx,
-                        inner: Box::new(ra),
-                    }
-                    .serialize(serializer, ());
-                    let extracted_b = VecSelectElement {
-                        element: idx,
-                        inner: Box::new(rb),
-                    }
-                    .serialize(serializer, ());
-
-                    //issue add and safe result at correct place
-                    result_ids[idx as usize] = serializer
-                        .builder_mut()
-                        .f_sub(t_f32, None, extracted_a.id, extracted_b.id)
-                        .unwrap();
+impl_float_binop!(Addition, a, b, f_add, +, f32, Vec2, Vec3, Vec4);
+impl_float_binop!(Subtraction, minuent, subtrahend, f_sub, -, f32, Vec2, Vec3, Vec4);
+impl_float_binop!(Multiplication, a, b, f_mul, *, f32, Vec2, Vec3, Vec4);
+impl_float_binop!(Division, dividend, divisor, f_div, /, f32, Vec2, Vec3, Vec4);
+
+macro_rules! impl_float_negate {
+    ($($fty:ty),+) => {
+        $(
+            impl<I> Operation for Negate<I, $fty> {
+                type Input = I;
+                type Output = DataId<$fty>;
+
+                fn serialize(
+                    &mut self,
+                    serializer: &mut crate::Serializer,
+                    input: Self::Input,
+                ) -> Result<Self::Output, crate::SerializeError> {
+                    let res = self.inner.serialize(serializer, input)?;
+                    let t_res = serializer.type_id::<$fty, Self>()?;
+                    Ok(DataId::from(
+                        serializer
+                            .value(|builder| builder.f_negate(t_res, None, res.id))
+                            .map_err(crate::SerializeError::builder::<Self>)?,
+                    ))
                 }
 
-                //now build new vector and return
-                let vector_id = serializer
-                    .builder_mut()
-                    .composite_construct(t_vec, None, result_ids)
-                    .unwrap();
-
-                DataId {
-                    id: vector_id,
-                    ty: PhantomData,
+                fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                    let ra = self.inner.evaluate(evaluator, input);
+                    let res = -evaluator.load(ra);
+                    evaluator.store(res)
                 }
             }
-        }
+        )+
     };
 }
-vec_op_sub!(Vec2, 2);
-vec_op_sub!(Vec3, 3);
-vec_op_sub!(Vec4, 4);
 
-impl<I: Clone> Operation for Multiplication<I, f32> {
-    type Input = I;
-    type Output = DataId<f32>;
-
-    fn serialize(
-        &mut self,
-        serializer: &mut crate::Serializer,
-        input: Self::Input,
-    ) -> Self::Output {
-        let ra = self.a.serialize(serializer, input.clone());
-        let rb = self.b.serialize(serializer, input);
-        let t_f32 = f32::spirv_type_id(serializer).unwrap();
-        DataId::from(
-            serializer
-                .builder_mut()
-                .f_mul(t_f32, None, ra.id, rb.id)
-                .unwrap(),
-        )
-    }
-}
-macro_rules! vec_op_mul {
-    ($vecty:ty, $nel:expr) => {
-        //In general this operations work by extracing each component of each type, doing the `op`
-        //on each pair, and then assembling the a new variable of the type.
+impl_float_negate!(f32, Vec2, Vec3, Vec4);
 
-        impl<I: Clone> Operation for Multiplication<I, $vecty> {
+macro_rules! impl_vec_times_scalar {
+    ($vecty:ty) => {
+        impl<I: Clone> Operation for VectorTimesScalar<I, $vecty> {
             type Input = I;
             type Output = DataId<$vecty>;
 
@@ -203,132 +91,72 @@ macro_rules! vec_op_mul {
                 &mut self,
                 serializer: &mut crate::Serializer,
                 input: Self::Input,
-            ) -> Self::Output {
-                let t_f32 = f32::spirv_type_id(serializer).unwrap();
-                let t_vec = <$vecty>::spirv_type_id(serializer).unwrap();
-
-                //get inner results
-                let ra = self.a.serialize(serializer, input.clone());
-                let rb = self.b.serialize(serializer, input);
-
-                //load each element-pair, add them and collet the element ids
-                let mut result_ids = [0u32; $nel];
-                for idx in 0..$nel {
-                    let extracted_a = VecSelectElement {
-                        element: idx,
-                        inner: Box::new(ra),
-                    }
-                    .serialize(serializer, ());
-                    let extracted_b = VecSelectElement {
-                        element: idx,
-                        inner: Box::new(rb),
-                    }
-                    .serialize(serializer, ());
-
-                    //issue add and safe result at correct place
-                    result_ids[idx as usize] = serializer
-                        .builder_mut()
-                        .f_mul(t_f32, None, extracted_a.id, extracted_b.id)
-                        .unwrap();
-                }
-
-                //now build new vector and return
-                let vector_id = serializer
-                    .builder_mut()
-                    .composite_construct(t_vec, None, result_ids)
-                    .unwrap();
+            ) -> Result<Self::Output, crate::SerializeError> {
+                let rv = self.vector.serialize(serializer, input.clone())?;
+                let rs = self.scalar.serialize(serializer, input)?;
+                let t_vec = serializer.type_id::<$vecty, Self>()?;
+                Ok(DataId::from(
+                    serializer
+                        .value(|builder| builder.vector_times_scalar(t_vec, None, rv.id, rs.id))
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
 
-                DataId {
-                    id: vector_id,
-                    ty: PhantomData,
-                }
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let rv = self.vector.evaluate(evaluator, input.clone());
+                let rs = self.scalar.evaluate(evaluator, input);
+                let res = evaluator.load(rv) * evaluator.load(rs);
+                evaluator.store(res)
             }
         }
     };
 }
-vec_op_mul!(Vec2, 2);
-vec_op_mul!(Vec3, 3);
-vec_op_mul!(Vec4, 4);
-
-impl<I: Clone> Operation for Division<I, f32> {
-    type Input = I;
-    type Output = DataId<f32>;
-
-    fn serialize(
-        &mut self,
-        serializer: &mut crate::Serializer,
-        input: Self::Input,
-    ) -> Self::Output {
-        let ra = self.dividend.serialize(serializer, input.clone());
-        let rb = self.divisor.serialize(serializer, input);
-        let t_f32 = f32::spirv_type_id(serializer).unwrap();
-        DataId::from(
-            serializer
-                .builder_mut()
-                .f_div(t_f32, None, ra.id, rb.id)
-                .unwrap(),
-        )
-    }
-}
-macro_rules! vec_op_div {
-    ($vecty:ty, $nel:expr) => {
-        //In general this operations work by extracing each component of each type, doing the `op`
-        //on each pair, and then assembling the a new variable of the type.
 
-        impl<I: Clone> Operation for Division<I, $vecty> {
-            type Input = I;
-            type Output = DataId<$vecty>;
-
-            fn serialize(
-                &mut self,
-                serializer: &mut crate::Serializer,
-                input: Self::Input,
-            ) -> Self::Output {
-                let t_f32 = f32::spirv_type_id(serializer).unwrap();
-                let t_vec = <$vecty>::spirv_type_id(serializer).unwrap();
-
-                //get inner results
-                let ra = self.dividend.serialize(serializer, input.clone());
-                let rb = self.divisor.serialize(serializer, input);
-
-                //load each element-pair, add them and collet the element ids
-                let mut result_ids = [0u32; $nel];
-                for idx in 0..$nel {
-                    let extracted_a = VecSelectElement {
-                        element: idx,
-                        inner: Box::new(ra),
-                    }
-                    .serialize(serializer, ());
-                    let extracted_b = VecSelectElement {
-                        element: idx,
-                        inner: Box::new(rb),
-                    }
-                    .serialize(serializer, ());
-
-                    //issue add and safe result at correct place
-                    result_ids[idx as usize] = serializer
-                        .builder_mut()
-                        .f_div(t_f32, None, extracted_a.id, extracted_b.id)
-                        .unwrap();
+impl_vec_times_scalar!(Vec2);
+impl_vec_times_scalar!(Vec3);
+impl_vec_times_scalar!(Vec4);
+
+///Implements the scalar broadcasting operation `$op`. There is no native instruction for those. Therefore the scalar
+/// is splat into a vector first, which is then used by the component wise instruction `$builder_fn`.
+macro_rules! impl_vec_scalar_broadcast {
+    ($op:ident, $builder_fn:ident, $eval:tt, $($vecty:ty, $nel:expr);+) => {
+        $(
+            impl<I: Clone> Operation for $op<I, $vecty> {
+                type Input = I;
+                type Output = DataId<$vecty>;
+
+                fn serialize(
+                    &mut self,
+                    serializer: &mut crate::Serializer,
+                    input: Self::Input,
+                ) -> Result<Self::Output, crate::SerializeError> {
+                    let rv = self.vector.serialize(serializer, input.clone())?;
+                    let rs = self.scalar.serialize(serializer, input)?;
+                    let t_vec = serializer.type_id::<$vecty, Self>()?;
+                    let splat = serializer
+                        .value(|builder| builder.composite_construct(t_vec, None, [rs.id; $nel]))
+                        .map_err(crate::SerializeError::builder::<Self>)?;
+                    Ok(DataId::from(
+                        serializer
+                            .value(|builder| builder.$builder_fn(t_vec, None, rv.id, splat))
+                            .map_err(crate::SerializeError::builder::<Self>)?,
+                    ))
                 }
 
-                //now build new vector and return
-                let vector_id = serializer
-                    .builder_mut()
-                    .composite_construct(t_vec, None, result_ids)
-                    .unwrap();
-
-                DataId {
-                    id: vector_id,
-                    ty: PhantomData,
+                fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                    let rv = self.vector.evaluate(evaluator, input.clone());
+                    let rs = self.scalar.evaluate(evaluator, input);
+                    let res = evaluator.load(rv) $eval evaluator.load(rs);
+                    evaluator.store(res)
                 }
             }
-        }
+        )+
     };
 }
-vec_op_div!(Vec2, 2);
-vec_op_div!(Vec3, 3);
-vec_op_div!(Vec4, 4);
+
+impl_vec_scalar_broadcast!(VectorPlusScalar, f_add, +, Vec2, 2; Vec3, 3; Vec4, 4);
+impl_vec_scalar_broadcast!(VectorMinusScalar, f_sub, -, Vec2, 2; Vec3, 3; Vec4, 4);
+impl_vec_scalar_broadcast!(VectorDividedByScalar, f_div, /, Vec2, 2; Vec3, 3; Vec4, 4);
 
 impl<I> Operation for Square<I, f32> {
     type Input = I;
@@ -338,15 +166,20 @@ impl<I> Operation for Square<I, f32> {
         &mut self,
         serializer: &mut crate::Serializer,
         input: Self::Input,
-    ) -> Self::Output {
-        let ra = self.inner.serialize(serializer, input);
-        let t_f32 = f32::spirv_type_id(serializer).unwrap();
-        DataId::from(
+    ) -> Result<Self::Output, crate::SerializeError> {
+        let ra = self.inner.serialize(serializer, input)?;
+        let t_f32 = serializer.type_id::<f32, Self>()?;
+        Ok(DataId::from(
             serializer
-                .builder_mut()
-                .f_mul(t_f32, None, ra.id, ra.id)
-                .unwrap(),
-        )
+                .value(|builder| builder.f_mul(t_f32, None, ra.id, ra.id))
+                .map_err(crate::SerializeError::builder::<Self>)?,
+        ))
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        let ra = self.inner.evaluate(evaluator, input);
+        let res = evaluator.load(ra) * evaluator.load(ra);
+        evaluator.store(res)
     }
 }
 
@@ -360,15 +193,20 @@ macro_rules! impl_sq_fvec {
                 &mut self,
                 serializer: &mut crate::Serializer,
                 input: Self::Input,
-            ) -> Self::Output {
-                let ra = self.inner.serialize(serializer, input);
-                let tvec = <$vecty>::spirv_type_id(serializer).unwrap();
-                DataId::from(
+            ) -> Result<Self::Output, crate::SerializeError> {
+                let ra = self.inner.serialize(serializer, input)?;
+                let tvec = serializer.type_id::<$vecty, Self>()?;
+                Ok(DataId::from(
                     serializer
-                        .builder_mut()
-                        .f_mul(tvec, None, ra.id, ra.id)
-                        .unwrap(),
-                )
+                        .value(|builder| builder.f_mul(tvec, None, ra.id, ra.id))
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let ra = self.inner.evaluate(evaluator, input);
+                let res = evaluator.load(ra) * evaluator.load(ra);
+                evaluator.store(res)
             }
         }
     };
@@ -387,19 +225,26 @@ impl<I> Operation for Sqrt<I> {
         &mut self,
         serializer: &mut crate::Serializer,
         input: Self::Input,
-    ) -> Self::Output {
-        let result = self.inner.serialize(serializer, input);
+    ) -> Result<Self::Output, crate::SerializeError> {
+        let result = self.inner.serialize(serializer, input)?;
 
         //make sure the instructionset is loaded
-        let ext_instset_id = serializer.builder_mut().ext_inst_import("GLSL.std.450");
-        let tf32 = f32::spirv_type_id(serializer).unwrap();
+        let ext_instset_id = serializer.glsl_std_450();
+        let tf32 = serializer.type_id::<f32, Self>()?;
         //now call its sqrt function with out result
-        DataId::from(
+        Ok(DataId::from(
             serializer
-                .builder_mut()
-                .ext_inst(tf32, None, ext_instset_id, 31, [Operand::IdRef(result.id)])
-                .unwrap(),
-        )
+                .value(|builder| {
+                    builder.ext_inst(tf32, None, ext_instset_id, 31, [Operand::IdRef(result.id)])
+                })
+                .map_err(crate::SerializeError::builder::<Self>)?,
+        ))
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        let ra = self.inner.evaluate(evaluator, input);
+        let res = evaluator.load(ra).sqrt();
+        evaluator.store(res)
     }
 }
 
@@ -411,20 +256,27 @@ impl<I> Operation for Abs<I, f32> {
         &mut self,
         serializer: &mut crate::Serializer,
         input: Self::Input,
-    ) -> Self::Output {
+    ) -> Result<Self::Output, crate::SerializeError> {
         //Uses the extended instructionset to get the length of an vector
-        let res = self.inner.serialize(serializer, input);
-        let tf32 = f32::spirv_type_id(serializer).unwrap();
+        let res = self.inner.serialize(serializer, input)?;
+        let tf32 = serializer.type_id::<f32, Self>()?;
         //Load extended instruction set
-        let ext_instset_id = serializer.builder_mut().ext_inst_import("GLSL.std.450");
+        let ext_instset_id = serializer.glsl_std_450();
 
         //Now execute the sinus function
-        DataId::from(
+        Ok(DataId::from(
             serializer
-                .builder_mut()
-                .ext_inst(tf32, None, ext_instset_id, 4, [Operand::IdRef(res.id)])
-                .unwrap(),
-        )
+                .value(|builder| {
+                    builder.ext_inst(tf32, None, ext_instset_id, 4, [Operand::IdRef(res.id)])
+                })
+                .map_err(crate::SerializeError::builder::<Self>)?,
+        ))
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        let ra = self.inner.evaluate(evaluator, input);
+        let res = evaluator.load(ra).abs();
+        evaluator.store(res)
     }
 }
 
@@ -435,27 +287,35 @@ impl<I: Clone> Operation for Max<I, f32> {
         &mut self,
         serializer: &mut crate::Serializer,
         input: Self::Input,
-    ) -> Self::Output {
+    ) -> Result<Self::Output, crate::SerializeError> {
         //Uses the extended instructionset to get the length of an vector
-        let ra = self.a.serialize(serializer, input.clone());
-        let rb = self.b.serialize(serializer, input);
-        let tf32 = f32::spirv_type_id(serializer).unwrap();
+        let ra = self.a.serialize(serializer, input.clone())?;
+        let rb = self.b.serialize(serializer, input)?;
+        let tf32 = serializer.type_id::<f32, Self>()?;
         //Load extended instruction set
-        let ext_instset_id = serializer.builder_mut().ext_inst_import("GLSL.std.450");
+        let ext_instset_id = serializer.glsl_std_450();
 
         //Now execute the sinus function
-        DataId::from(
+        Ok(DataId::from(
             serializer
-                .builder_mut()
-                .ext_inst(
-                    tf32,
-                    None,
-                    ext_instset_id,
-                    40,
-                    [Operand::IdRef(ra.id), Operand::IdRef(rb.id)],
-                )
-                .unwrap(),
-        )
+                .value(|builder| {
+                    builder.ext_inst(
+                        tf32,
+                        None,
+                        ext_instset_id,
+                        40,
+                        [Operand::IdRef(ra.id), Operand::IdRef(rb.id)],
+                    )
+                })
+                .map_err(crate::SerializeError::builder::<Self>)?,
+        ))
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        let ra = self.a.evaluate(evaluator, input.clone());
+        let rb = self.b.evaluate(evaluator, input);
+        let res = evaluator.load(ra).max(evaluator.load(rb));
+        evaluator.store(res)
     }
 }
 
@@ -466,26 +326,34 @@ impl<I: Clone> Operation for Min<I, f32> {
         &mut self,
         serializer: &mut crate::Serializer,
         input: Self::Input,
-    ) -> Self::Output {
+    ) -> Result<Self::Output, crate::SerializeError> {
         //Uses the extended instructionset to get the length of an vector
-        let ra = self.a.serialize(serializer, input.clone());
-        let rb = self.b.serialize(serializer, input);
-        let tf32 = f32::spirv_type_id(serializer).unwrap();
+        let ra = self.a.serialize(serializer, input.clone())?;
+        let rb = self.b.serialize(serializer, input)?;
+        let tf32 = serializer.type_id::<f32, Self>()?;
         //Load extended instruction set
-        let ext_instset_id = serializer.builder_mut().ext_inst_import("GLSL.std.450");
+        let ext_instset_id = serializer.glsl_std_450();
 
         //Now execute the sinus function
-        DataId::from(
+        Ok(DataId::from(
             serializer
-                .builder_mut()
-                .ext_inst(
-                    tf32,
-                    None,
-                    ext_instset_id,
-                    37,
-                    [Operand::IdRef(ra.id), Operand::IdRef(rb.id)],
-                )
-                .unwrap(),
-        )
+                .value(|builder| {
+                    builder.ext_inst(
+                        tf32,
+                        None,
+                        ext_instset_id,
+                        37,
+                        [Operand::IdRef(ra.id), Operand::IdRef(rb.id)],
+                    )
+                })
+                .map_err(crate::SerializeError::builder::<Self>)?,
+        ))
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        let ra = self.a.evaluate(evaluator, input.clone());
+        let rb = self.b.evaluate(evaluator, input);
+        let res = evaluator.load(ra).min(evaluator.load(rb));
+        evaluator.store(res)
     }
 }
@@ -1 +1,500 @@
+//! Integer arithmetic and bit operations for signed and unsigned scalars and vectors.
+//!
+//! SpirV integer arithmetic wraps on overflow. The CPU evaluation follows this behaviour. Division or remainder by zero
+//! is undefined in SpirV, when evaluated on the CPU the result is `0`. The same is true for shifts by at least the bit
+//! width of the integer.
+//!
+//! Note that the 64bit versions need the `Int64` capability to be enabled in the module they are injected into.
 
+use glam::{IVec2, IVec3, IVec4, UVec2, UVec3, UVec4};
+use rspirv::dr::Operand;
+
+use super::{
+    Abs, BitwiseAnd, BitwiseOr, BitwiseXor, Max, Min, Modulo, Remainder, ShiftLeft, ShiftRight,
+};
+use crate::operations::{Addition, Division, Multiplication, Negate, Subtraction};
+use crate::{DataId, Evaluator, Operation};
+
+///Component wise access to integer scalars and vectors. Used to evaluate integer operations with SpirV's semantics
+/// on the CPU.
+trait IntComponents: Copy {
+    type Element: Copy;
+    fn map(self, f: impl Fn(Self::Element) -> Self::Element) -> Self;
+    fn zip_map(
+        self,
+        other: Self,
+        f: impl Fn(Self::Element, Self::Element) -> Self::Element,
+    ) -> Self;
+}
+
+macro_rules! impl_int_components_scalar {
+    ($($intty:ty),+) => {
+        $(
+            impl IntComponents for $intty {
+                type Element = $intty;
+                fn map(self, f: impl Fn(Self::Element) -> Self::Element) -> Self {
+                    f(self)
+                }
+                fn zip_map(
+                    self,
+                    other: Self,
+                    f: impl Fn(Self::Element, Self::Element) -> Self::Element,
+                ) -> Self {
+                    f(self, other)
+                }
+            }
+        )+
+    };
+}
+
+impl_int_components_scalar!(i32, u32, i64, u64);
+
+macro_rules! impl_int_components_vec {
+    ($vecty:ty, $basety:ty, $($element_name:ident),+) => {
+        impl IntComponents for $vecty {
+            type Element = $basety;
+            fn map(self, f: impl Fn(Self::Element) -> Self::Element) -> Self {
+                <$vecty>::new($(f(self.$element_name)),+)
+            }
+            fn zip_map(
+                self,
+                other: Self,
+                f: impl Fn(Self::Element, Self::Element) -> Self::Element,
+            ) -> Self {
+                <$vecty>::new($(f(self.$element_name, other.$element_name)),+)
+            }
+        }
+    };
+}
+
+impl_int_components_vec!(IVec2, i32, x, y);
+impl_int_components_vec!(IVec3, i32, x, y, z);
+impl_int_components_vec!(IVec4, i32, x, y, z, w);
+impl_int_components_vec!(UVec2, u32, x, y);
+impl_int_components_vec!(UVec3, u32, x, y, z);
+impl_int_components_vec!(UVec4, u32, x, y, z, w);
+
+///Converts a shift amount for `checked_shl` and `checked_shr`. Negative or too large amounts stay out of range, therefore
+/// the shift results in `0`.
+fn shift_amount<T: TryInto<u32>>(shift: T) -> u32 {
+    shift.try_into().unwrap_or(u32::MAX)
+}
+
+///Returns `a` modulo `b` where the result takes the sign of `b`, as defined by `OpSMod`.
+macro_rules! signed_mod {
+    ($a:expr, $b:expr) => {{
+        let (a, b) = ($a, $b);
+        match a.checked_rem(b) {
+            Some(r) if r != 0 && (r < 0) != (b < 0) => r.wrapping_add(b),
+            Some(r) => r,
+            None => 0,
+        }
+    }};
+}
+
+///Implements a two operand operation `$op` with the fields `$a` and `$b` via the builders `$builder_fn` for each of the
+/// supplied integer types. `$eval` is used per component when evaluating.
+macro_rules! impl_int_binop {
+    ($op:ident, $a:ident, $b:ident, $builder_fn:ident, $eval:expr, $($intty:ty),+) => {
+        $(
+            impl<I: Clone> Operation for $op<I, $intty> {
+                type Input = I;
+                type Output = DataId<$intty>;
+
+                fn serialize(
+                    &mut self,
+                    serializer: &mut crate::Serializer,
+                    input: Self::Input,
+                ) -> Result<Self::Output, crate::SerializeError> {
+                    let ra = self.$a.serialize(serializer, input.clone())?;
+                    let rb = self.$b.serialize(serializer, input)?;
+                    let t_int = serializer.type_id::<$intty, Self>()?;
+                    Ok(DataId::from(
+                        serializer
+                            .value(|builder| builder.$builder_fn(t_int, None, ra.id, rb.id))
+                            .map_err(crate::SerializeError::builder::<Self>)?,
+                    ))
+                }
+
+                fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                    let ra = self.$a.evaluate(evaluator, input.clone());
+                    let rb = self.$b.evaluate(evaluator, input);
+                    let res = evaluator.load(ra).zip_map(evaluator.load(rb), $eval);
+                    evaluator.store(res)
+                }
+            }
+        )+
+    };
+}
+
+///Implements a two operand operation `$op` based on the GLSL.std.450 instruction `$inst`.
+macro_rules! impl_int_ext_binop {
+    ($op:ident, $inst:expr, $eval:expr, $($intty:ty),+) => {
+        $(
+            impl<I: Clone> Operation for $op<I, $intty> {
+                type Input = I;
+                type Output = DataId<$intty>;
+
+                fn serialize(
+                    &mut self,
+                    serializer: &mut crate::Serializer,
+                    input: Self::Input,
+                ) -> Result<Self::Output, crate::SerializeError> {
+                    let ra = self.a.serialize(serializer, input.clone())?;
+                    let rb = self.b.serialize(serializer, input)?;
+                    let t_int = serializer.type_id::<$intty, Self>()?;
+
+                    //Load instructionset
+                    let ext_instset_id = serializer.glsl_std_450();
+                    Ok(DataId::from(
+                        serializer
+                            .value(|builder| builder.ext_inst(
+                                t_int,
+                                None,
+                                ext_instset_id,
+                                $inst,
+                                [Operand::IdRef(ra.id), Operand::IdRef(rb.id)],
+                            ))
+                            .map_err(crate::SerializeError::builder::<Self>)?,
+                    ))
+                }
+
+                fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                    let ra = self.a.evaluate(evaluator, input.clone());
+                    let rb = self.b.evaluate(evaluator, input);
+                    let res = evaluator.load(ra).zip_map(evaluator.load(rb), $eval);
+                    evaluator.store(res)
+                }
+            }
+        )+
+    };
+}
+
+impl_int_binop!(
+    Addition,
+    a,
+    b,
+    i_add,
+    |a, b| a.wrapping_add(b),
+    i32,
+    u32,
+    i64,
+    u64,
+    IVec2,
+    IVec3,
+    IVec4,
+    UVec2,
+    UVec3,
+    UVec4
+);
+impl_int_binop!(
+    Subtraction,
+    minuent,
+    subtrahend,
+    i_sub,
+    |a, b| a.wrapping_sub(b),
+    i32,
+    u32,
+    i64,
+    u64,
+    IVec2,
+    IVec3,
+    IVec4,
+    UVec2,
+    UVec3,
+    UVec4
+);
+impl_int_binop!(
+    Multiplication,
+    a,
+    b,
+    i_mul,
+    |a, b| a.wrapping_mul(b),
+    i32,
+    u32,
+    i64,
+    u64,
+    IVec2,
+    IVec3,
+    IVec4,
+    UVec2,
+    UVec3,
+    UVec4
+);
+
+impl_int_binop!(
+    Division,
+    dividend,
+    divisor,
+    s_div,
+    |a, b| a.checked_div(b).unwrap_or(0),
+    i32,
+    i64,
+    IVec2,
+    IVec3,
+    IVec4
+);
+impl_int_binop!(
+    Division,
+    dividend,
+    divisor,
+    u_div,
+    |a, b| a.checked_div(b).unwrap_or(0),
+    u32,
+    u64,
+    UVec2,
+    UVec3,
+    UVec4
+);
+
+impl_int_binop!(
+    Remainder,
+    dividend,
+    divisor,
+    s_rem,
+    |a, b| a.checked_rem(b).unwrap_or(0),
+    i32,
+    i64,
+    IVec2,
+    IVec3,
+    IVec4
+);
+impl_int_binop!(
+    Modulo,
+    dividend,
+    divisor,
+    s_mod,
+    |a, b| signed_mod!(a, b),
+    i32,
+    i64,
+    IVec2,
+    IVec3,
+    IVec4
+);
+//For unsigned integers remainder and modulo are the same.
+impl_int_binop!(
+    Remainder,
+    dividend,
+    divisor,
+    u_mod,
+    |a, b| a.checked_rem(b).unwrap_or(0),
+    u32,
+    u64,
+    UVec2,
+    UVec3,
+    UVec4
+);
+impl_int_binop!(
+    Modulo,
+    dividend,
+    divisor,
+    u_mod,
+    |a, b| a.checked_rem(b).unwrap_or(0),
+    u32,
+    u64,
+    UVec2,
+    UVec3,
+    UVec4
+);
+
+impl_int_binop!(
+    BitwiseAnd,
+    a,
+    b,
+    bitwise_and,
+    |a, b| a & b,
+    i32,
+    u32,
+    i64,
+    u64,
+    IVec2,
+    IVec3,
+    IVec4,
+    UVec2,
+    UVec3,
+    UVec4
+);
+impl_int_binop!(
+    BitwiseOr,
+    a,
+    b,
+    bitwise_or,
+    |a, b| a | b,
+    i32,
+    u32,
+    i64,
+    u64,
+    IVec2,
+    IVec3,
+    IVec4,
+    UVec2,
+    UVec3,
+    UVec4
+);
+impl_int_binop!(
+    BitwiseXor,
+    a,
+    b,
+    bitwise_xor,
+    |a, b| a ^ b,
+    i32,
+    u32,
+    i64,
+    u64,
+    IVec2,
+    IVec3,
+    IVec4,
+    UVec2,
+    UVec3,
+    UVec4
+);
+impl_int_binop!(
+    ShiftLeft,
+    base,
+    shift,
+    shift_left_logical,
+    |a, b| a.checked_shl(shift_amount(b)).unwrap_or(0),
+    i32,
+    u32,
+    i64,
+    u64,
+    IVec2,
+    IVec3,
+    IVec4,
+    UVec2,
+    UVec3,
+    UVec4
+);
+impl_int_binop!(
+    ShiftRight,
+    base,
+    shift,
+    shift_right_arithmetic,
+    |a, b| a.checked_shr(shift_amount(b)).unwrap_or(0),
+    i32,
+    i64,
+    IVec2,
+    IVec3,
+    IVec4
+);
+impl_int_binop!(
+    ShiftRight,
+    base,
+    shift,
+    shift_right_logical,
+    |a, b| a.checked_shr(shift_amount(b)).unwrap_or(0),
+    u32,
+    u64,
+    UVec2,
+    UVec3,
+    UVec4
+);
+
+impl_int_ext_binop!(Min, 39, |a, b| a.min(b), i32, i64, IVec2, IVec3, IVec4);
+impl_int_ext_binop!(Min, 38, |a, b| a.min(b), u32, u64, UVec2, UVec3, UVec4);
+impl_int_ext_binop!(Max, 42, |a, b| a.max(b), i32, i64, IVec2, IVec3, IVec4);
+impl_int_ext_binop!(Max, 41, |a, b| a.max(b), u32, u64, UVec2, UVec3, UVec4);
+
+macro_rules! impl_sabs {
+    ($($intty:ty),+) => {
+        $(
+            ///Uses the extended instruction set to implement abs via the `SAbs` instruction.
+            impl<I> Operation for Abs<I, $intty> {
+                type Input = I;
+                type Output = DataId<$intty>;
+
+                fn serialize(
+                    &mut self,
+                    serializer: &mut crate::Serializer,
+                    input: Self::Input,
+                ) -> Result<Self::Output, crate::SerializeError> {
+                    let res = self.inner.serialize(serializer, input)?;
+                    let t_int = serializer.type_id::<$intty, Self>()?;
+                    //Load extended instruction set
+                    let ext_instset_id = serializer.glsl_std_450();
+
+                    Ok(DataId::from(
+                        serializer
+                            .value(|builder| {
+                                builder.ext_inst(
+                                    t_int,
+                                    None,
+                                    ext_instset_id,
+                                    5,
+                                    [Operand::IdRef(res.id)],
+                                )
+                            })
+                            .map_err(crate::SerializeError::builder::<Self>)?,
+                    ))
+                }
+
+                fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                    let ra = self.inner.evaluate(evaluator, input);
+                    let res = evaluator.load(ra).map(|a| a.wrapping_abs());
+                    evaluator.store(res)
+                }
+            }
+        )+
+    };
+}
+
+impl_sabs!(i32, i64, IVec2, IVec3, IVec4);
+
+macro_rules! impl_int_negate {
+    ($($intty:ty),+) => {
+        $(
+            impl<I> Operation for Negate<I, $intty> {
+                type Input = I;
+                type Output = DataId<$intty>;
+
+                fn serialize(
+                    &mut self,
+                    serializer: &mut crate::Serializer,
+                    input: Self::Input,
+                ) -> Result<Self::Output, crate::SerializeError> {
+                    let res = self.inner.serialize(serializer, input)?;
+                    let t_int = serializer.type_id::<$intty, Self>()?;
+                    Ok(DataId::from(
+                        serializer
+                            .value(|builder| builder.s_negate(t_int, None, res.id))
+                            .map_err(crate::SerializeError::builder::<Self>)?,
+                    ))
+                }
+
+                fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                    let ra = self.inner.evaluate(evaluator, input);
+                    let res = evaluator.load(ra).map(|a| a.wrapping_neg());
+                    evaluator.store(res)
+                }
+            }
+        )+
+    };
+}
+
+impl_int_negate!(i32, i64, IVec2, IVec3, IVec4);
+
+macro_rules! impl_uabs {
+    ($($intty:ty),+) => {
+        $(
+            ///Unsigned values are always positive, therefore the inner result is returned as is.
+            impl<I> Operation for Abs<I, $intty> {
+                type Input = I;
+                type Output = DataId<$intty>;
+
+                fn serialize(
+                    &mut self,
+                    serializer: &mut crate::Serializer,
+                    input: Self::Input,
+                ) -> Result<Self::Output, crate::SerializeError> {
+                    self.inner.serialize(serializer, input)
+                }
+
+                fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                    self.inner.evaluate(evaluator, input)
+                }
+            }
+        )+
+    };
+}
+
+impl_uabs!(u32, u64, UVec2, UVec3, UVec4);
@@ -8,6 +8,9 @@ pub use float::*;
 mod integer;
 pub use integer::*;
 
+///Float operations based on GLSL's extended instruction set.
+mod extended;
+
 ///Special trigonometric functions usually only implemented on floats.
 pub(crate) mod trigonomy;
 
@@ -29,12 +32,58 @@ pub struct Multiplication<I, O> {
     pub b: BoxOperation<I, O>,
 }
 
-///Multiplication of two values: `result = dividend / divisor`
+///Division of two values: `result = dividend / divisor`
 pub struct Division<I, O> {
     pub dividend: BoxOperation<I, O>,
     pub divisor: BoxOperation<I, O>,
 }
 
+///Negation of a float or signed integer value: `result = -inner`
+pub struct Negate<I, O> {
+    pub inner: BoxOperation<I, O>,
+}
+
+///Remainder of a division: `result = dividend % divisor`. The result takes the sign of the `dividend`.
+pub struct Remainder<I, O> {
+    pub dividend: BoxOperation<I, O>,
+    pub divisor: BoxOperation<I, O>,
+}
+
+///Modulo of a division. In contrast to [Remainder] the result takes the sign of the `divisor`. For floats this is the same as
+/// GLSL's `mod`: `result = dividend - divisor * floor(dividend / divisor)`.
+pub struct Modulo<I, O> {
+    pub dividend: BoxOperation<I, O>,
+    pub divisor: BoxOperation<I, O>,
+}
+
+///Scales each component of `vector` by `scalar`: `result = vector * scalar`.
+pub struct VectorTimesScalar<I, V> {
+    pub vector: BoxOperation<I, V>,
+    pub scalar: BoxOperation<I, f32>,
+}
+
+///Adds `scalar` to each component of `vector`: `result = vector + scalar`.
+pub struct VectorPlusScalar<I, V> {
+    pub vector: BoxOperation<I, V>,
+    pub scalar: BoxOperation<I, f32>,
+}
+
+///Subtracts `scalar` from each component of `vector`: `result = vector - scalar`.
+///
+/// Use [Splat](crate::operations::Splat) and [Subtraction] if the scalar is the minuent.
+pub struct VectorMinusScalar<I, V> {
+    pub vector: BoxOperation<I, V>,
+    pub scalar: BoxOperation<I, f32>,
+}
+
+///Divides each component of `vector` by `scalar`: `result = vector / scalar`.
+///
+/// Use [Splat](crate::operations::Splat) and [Division] if the scalar is the dividend.
+pub struct VectorDividedByScalar<I, V> {
+    pub vector: BoxOperation<I, V>,
+    pub scalar: BoxOperation<I, f32>,
+}
+
 ///Squares the inner result: `result = a*a`
 pub struct Square<I, O> {
     pub inner: BoxOperation<I, O>,
@@ -61,3 +110,129 @@ pub struct Min<I, O> {
     pub a: BoxOperation<I, O>,
     pub b: BoxOperation<I, O>,
 }
+
+///Rounds the inner result to the nearest integer that is less than or equal to it.
+pub struct Floor<I, O> {
+    pub inner: BoxOperation<I, O>,
+}
+
+///Rounds the inner result to the nearest integer that is greater than or equal to it.
+pub struct Ceil<I, O> {
+    pub inner: BoxOperation<I, O>,
+}
+
+///Rounds the inner result to the nearest integer. The direction of `0.5` is implementation defined on the GPU, on the CPU it rounds away from zero.
+pub struct Round<I, O> {
+    pub inner: BoxOperation<I, O>,
+}
+
+///Rounds the inner result towards zero.
+pub struct Trunc<I, O> {
+    pub inner: BoxOperation<I, O>,
+}
+
+///Returns the fractional part of the inner result: `result = x - floor(x)`.
+pub struct Fract<I, O> {
+    pub inner: BoxOperation<I, O>,
+}
+
+///Raises `base` to the power of `exponent`. The result is undefined if `base < 0`, or if `base == 0` and `exponent <= 0`.
+pub struct Pow<I, O> {
+    pub base: BoxOperation<I, O>,
+    pub exponent: BoxOperation<I, O>,
+}
+
+///Natural exponentiation of the inner result: `result = e^x`.
+pub struct Exp<I, O> {
+    pub inner: BoxOperation<I, O>,
+}
+
+///Base 2 exponentiation of the inner result: `result = 2^x`.
+pub struct Exp2<I, O> {
+    pub inner: BoxOperation<I, O>,
+}
+
+///Natural logarithm of the inner result. The result is undefined if `x <= 0`.
+pub struct Log<I, O> {
+    pub inner: BoxOperation<I, O>,
+}
+
+///Base 2 logarithm of the inner result. The result is undefined if `x <= 0`.
+pub struct Log2<I, O> {
+    pub inner: BoxOperation<I, O>,
+}
+
+///Returns the reciprocal of the square root of the inner result: `result = 1 / sqrt(x)`.
+pub struct InverseSqrt<I, O> {
+    pub inner: BoxOperation<I, O>,
+}
+
+///Returns `1.0` if the inner result is positive, `-1.0` if it is negative and `0.0` otherwise.
+pub struct Sign<I, O> {
+    pub inner: BoxOperation<I, O>,
+}
+
+///Constrains `inner` to the range `min..=max`: `result = min(max(x, min), max)`.
+pub struct Clamp<I, O> {
+    pub inner: BoxOperation<I, O>,
+    pub min: BoxOperation<I, O>,
+    pub max: BoxOperation<I, O>,
+}
+
+///Linear interpolation between `a` and `b`: `result = a * (1 - t) + b * t`.
+pub struct Mix<I, O> {
+    pub a: BoxOperation<I, O>,
+    pub b: BoxOperation<I, O>,
+    pub t: BoxOperation<I, O>,
+}
+
+///Returns `0.0` if `x < edge`, otherwise `1.0`.
+pub struct Step<I, O> {
+    pub edge: BoxOperation<I, O>,
+    pub x: BoxOperation<I, O>,
+}
+
+///Smooth Hermite interpolation between `0.0` and `1.0` when `edge0 < x < edge1`. The result is undefined if `edge0 >= edge1`.
+pub struct SmoothStep<I, O> {
+    pub edge0: BoxOperation<I, O>,
+    pub edge1: BoxOperation<I, O>,
+    pub x: BoxOperation<I, O>,
+}
+
+///Fused multiply add: `result = a * b + c`.
+pub struct Fma<I, O> {
+    pub a: BoxOperation<I, O>,
+    pub b: BoxOperation<I, O>,
+    pub c: BoxOperation<I, O>,
+}
+
+///Bitwise and of two integers: `result = a & b`
+pub struct BitwiseAnd<I, O> {
+    pub a: BoxOperation<I, O>,
+    pub b: BoxOperation<I, O>,
+}
+
+///Bitwise or of two integers: `result = a | b`
+pub struct BitwiseOr<I, O> {
+    pub a: BoxOperation<I, O>,
+    pub b: BoxOperation<I, O>,
+}
+
+///Bitwise exclusive or of two integers: `result = a ^ b`
+pub struct BitwiseXor<I, O> {
+    pub a: BoxOperation<I, O>,
+    pub b: BoxOperation<I, O>,
+}
+
+///Shifts the bits of an integer to the left: `result = base << shift`
+pub struct ShiftLeft<I, O> {
+    pub base: BoxOperation<I, O>,
+    pub shift: BoxOperation<I, O>,
+}
+
+///Shifts the bits of an integer to the right: `result = base >> shift`. Signed integers are shifted arithmetically, which
+/// keeps their sign, unsigned integers are filled with zeros.
+pub struct ShiftRight<I, O> {
+    pub base: BoxOperation<I, O>,
+    pub shift: BoxOperation<I, O>,
+}
@@ -1,6 +1,6 @@
 use rspirv::dr::Operand;
 
-use crate::{spv_fi::IntoSpvType, BoxOperation, DataId, Operation, Serializer};
+use crate::{BoxOperation, DataId, Evaluator, Operation, SerializeError, Serializer};
 
 ///Calculates the sine of some value.
 pub struct Sine<I> {
@@ -10,20 +10,31 @@ pub struct Sine<I> {
 impl<I> Operation for Sine<I> {
     type Input = I;
     type Output = DataId<f32>;
-    fn serialize(&mut self, serializer: &mut Serializer, input: Self::Input) -> Self::Output {
+    fn serialize(
+        &mut self,
+        serializer: &mut Serializer,
+        input: Self::Input,
+    ) -> Result<Self::Output, SerializeError> {
         //Uses the extended instructionset to get the length of an vector
-        let res = self.inner.serialize(serializer, input);
-        let tf32 = f32::spirv_type_id(serializer).unwrap();
+        let res = self.inner.serialize(serializer, input)?;
+        let tf32 = serializer.type_id::<f32, Self>()?;
         //Load extended instruction set
-        let ext_instset_id = serializer.builder_mut().ext_inst_import("GLSL.std.450");
+        let ext_instset_id = serializer.glsl_std_450();
 
         //Now execute the sinus function
-        DataId::from(
+        Ok(DataId::from(
             serializer
-                .builder_mut()
-                .ext_inst(tf32, None, ext_instset_id, 13, [Operand::IdRef(res.id)])
-                .unwrap(),
-        )
+                .value(|builder| {
+                    builder.ext_inst(tf32, None, ext_instset_id, 13, [Operand::IdRef(res.id)])
+                })
+                .map_err(SerializeError::builder::<Self>)?,
+        ))
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        let ra = self.inner.evaluate(evaluator, input);
+        let res = evaluator.load(ra).sin();
+        evaluator.store(res)
     }
 }
 
@@ -35,20 +46,31 @@ pub struct Cosine<I> {
 impl<I> Operation for Cosine<I> {
     type Input = I;
     type Output = DataId<f32>;
-    fn serialize(&mut self, serializer: &mut Serializer, input: Self::Input) -> Self::Output {
+    fn serialize(
+        &mut self,
+        serializer: &mut Serializer,
+        input: Self::Input,
+    ) -> Result<Self::Output, SerializeError> {
         //Uses the extended instructionset to get the length of an vector
-        let res = self.inner.serialize(serializer, input);
-        let tf32 = f32::spirv_type_id(serializer).unwrap();
+        let res = self.inner.serialize(serializer, input)?;
+        let tf32 = serializer.type_id::<f32, Self>()?;
         //Load extended instruction set
-        let ext_instset_id = serializer.builder_mut().ext_inst_import("GLSL.std.450");
+        let ext_instset_id = serializer.glsl_std_450();
 
         //Now execute the sinus function
-        DataId::from(
+        Ok(DataId::from(
             serializer
-                .builder_mut()
-                .ext_inst(tf32, None, ext_instset_id, 14, [Operand::IdRef(res.id)])
-                .unwrap(),
-        )
+                .value(|builder| {
+                    builder.ext_inst(tf32, None, ext_instset_id, 14, [Operand::IdRef(res.id)])
+                })
+                .map_err(SerializeError::builder::<Self>)?,
+        ))
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        let ra = self.inner.evaluate(evaluator, input);
+        let res = evaluator.load(ra).cos();
+        evaluator.store(res)
     }
 }
 
@@ -60,19 +82,81 @@ pub struct Tangent<I> {
 impl<I> Operation for Tangent<I> {
     type Input = I;
     type Output = DataId<f32>;
-    fn serialize(&mut self, serializer: &mut Serializer, input: Self::Input) -> Self::Output {
+    fn serialize(
+        &mut self,
+        serializer: &mut Serializer,
+        input: Self::Input,
+    ) -> Result<Self::Output, SerializeError> {
         //Uses the extended instructionset to get the length of an vector
-        let res = self.inner.serialize(serializer, input);
-        let tf32 = f32::spirv_type_id(serializer).unwrap();
+        let res = self.inner.serialize(serializer, input)?;
+        let tf32 = serializer.type_id::<f32, Self>()?;
         //Load extended instruction set
-        let ext_instset_id = serializer.builder_mut().ext_inst_import("GLSL.std.450");
+        let ext_instset_id = serializer.glsl_std_450();
 
         //Now execute the sinus function
-        DataId::from(
+        Ok(DataId::from(
             serializer
-                .builder_mut()
-                .ext_inst(tf32, None, ext_instset_id, 15, [Operand::IdRef(res.id)])
-                .unwrap(),
-        )
+                .value(|builder| {
+                    builder.ext_inst(tf32, None, ext_instset_id, 15, [Operand::IdRef(res.id)])
+                })
+                .map_err(SerializeError::builder::<Self>)?,
+        ))
     }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        let ra = self.inner.evaluate(evaluator, input);
+        let res = evaluator.load(ra).tan();
+        evaluator.store(res)
+    }
+}
+
+///Calculates the arc sine of some value. The result is undefined if `|x| > 1`.
+pub struct ArcSine<I, O> {
+    pub inner: BoxOperation<I, O>,
+}
+
+///Calculates the arc cosine of some value. The result is undefined if `|x| > 1`.
+pub struct ArcCosine<I, O> {
+    pub inner: BoxOperation<I, O>,
+}
+
+///Calculates the arc tangent of some value.
+pub struct ArcTangent<I, O> {
+    pub inner: BoxOperation<I, O>,
+}
+
+///Calculates the arc tangent of `y / x`, where the signs of `y` and `x` are used to determine the quadrant.
+pub struct ArcTangent2<I, O> {
+    pub y: BoxOperation<I, O>,
+    pub x: BoxOperation<I, O>,
+}
+
+///Calculates the hyperbolic sine of some value.
+pub struct HyperbolicSine<I, O> {
+    pub inner: BoxOperation<I, O>,
+}
+
+///Calculates the hyperbolic cosine of some value.
+pub struct HyperbolicCosine<I, O> {
+    pub inner: BoxOperation<I, O>,
+}
+
+///Calculates the hyperbolic tangent of some value.
+pub struct HyperbolicTangent<I, O> {
+    pub inner: BoxOperation<I, O>,
+}
+
+///Calculates the inverse hyperbolic sine of some value.
+pub struct HyperbolicArcSine<I, O> {
+    pub inner: BoxOperation<I, O>,
+}
+
+///Calculates the inverse hyperbolic cosine of some value. The result is undefined if `x < 1`.
+pub struct HyperbolicArcCosine<I, O> {
+    pub inner: BoxOperation<I, O>,
+}
+
+///Calculates the inverse hyperbolic tangent of some value. The result is undefined if `|x| >= 1`.
+pub struct HyperbolicArcTangent<I, O> {
+    pub inner: BoxOperation<I, O>,
 }
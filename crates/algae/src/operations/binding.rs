@@ -0,0 +1,182 @@
+//! Typed let-bindings. In contrast to the named results of [OrderedOperations](super::OrderedOperations), a result is
+//! accessed via the [Binding] handle it was bound to. Therefore a misspelled or mistyped access is a compile error.
+
+use std::{
+    any::type_name,
+    marker::PhantomData,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::{BoxOperation, DataId, Evaluator, Operation, SerializeError, Serializer};
+
+use super::ResultContext;
+
+///Source of the unique ids of all bindings.
+static NEXT_BINDING: AtomicU64 = AtomicU64::new(0);
+
+///Handle to the result of type `T` that is bound by a [Let]. Can be used any number of times within the body of the [Let],
+/// each use returns the same result without calculating it again.
+pub struct Binding<T> {
+    id: u64,
+    ty: PhantomData<T>,
+}
+
+impl<T> Clone for Binding<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Binding<T> {}
+
+impl<T: 'static> Operation for Binding<T> {
+    type Input = ResultContext;
+    type Output = DataId<T>;
+
+    fn serialize(
+        &mut self,
+        _serializer: &mut Serializer,
+        input: Self::Input,
+    ) -> Result<Self::Output, SerializeError> {
+        input.get_binding(type_name::<Self>(), self.id)
+    }
+
+    fn evaluate(&mut self, _evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        input
+            .get_binding(type_name::<Self>(), self.id)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+///Calculates `value` once and binds it for `body`, like `let name = value; body` in Rust. Returns the result of `body`.
+///
+/// The body is created by a closure that gets the [Binding] of the value. `name` is only used for debugging and error
+/// messages, but the value is also accessible as a named result via [AccessResult](super::AccessResult). If the input is a
+/// [ResultContext], `value` and `body` have access to the results of that context as well.
+///
+/// # Example
+///
+/// Inigo Quilez's 2D box sdf, where the component wise distance to the box is used twice:
+/// ```rust
+/// use algae::glam::Vec2;
+/// use algae::operations::{
+///     Abs, Addition, Constant, Length, Let, Max, Min, Subtraction, Variable, VecSelectElement,
+/// };
+///
+/// let sdf = Let::<(), Vec2, f32>::new(
+///     "d",
+///     Box::new(Subtraction {
+///         minuent: Box::new(Abs {
+///             inner: Box::new(Variable::new("coord", Vec2::ZERO)),
+///         }),
+///         subtrahend: Box::new(Constant::new(Vec2::new(1.0, 2.0))),
+///     }),
+///     |d| {
+///         Box::new(Addition {
+///             a: Box::new(Length {
+///                 inner: Box::new(Max {
+///                     a: Box::new(d),
+///                     b: Box::new(Constant::new(Vec2::ZERO)),
+///                 }),
+///             }),
+///             b: Box::new(Min {
+///                 a: Box::new(Max {
+///                     a: Box::new(VecSelectElement { element: 0, inner: Box::new(d) }),
+///                     b: Box::new(VecSelectElement { element: 1, inner: Box::new(d) }),
+///                 }),
+///                 b: Box::new(Constant::new(0.0)),
+///             }),
+///         })
+///     },
+/// );
+/// ```
+pub struct Let<I, T, O> {
+    ///Debug name of the binding.
+    pub name: String,
+    pub value: BoxOperation<I, T>,
+    pub body: BoxOperation<ResultContext, O>,
+    binding: Binding<T>,
+}
+
+impl<I, T, O> Let<I, T, O> {
+    pub fn new(
+        name: impl Into<String>,
+        value: BoxOperation<I, T>,
+        body: impl FnOnce(Binding<T>) -> BoxOperation<ResultContext, O>,
+    ) -> Self {
+        let binding = Binding {
+            id: NEXT_BINDING.fetch_add(1, Ordering::Relaxed),
+            ty: PhantomData,
+        };
+
+        Let {
+            name: name.into(),
+            value,
+            body: body(binding),
+            binding,
+        }
+    }
+}
+
+impl<I, T: 'static, O> Let<I, T, O> {
+    ///Serializes the binding where `context` is the context that is extended for the body.
+    fn serialize_let(
+        &mut self,
+        serializer: &mut Serializer,
+        input: I,
+        mut context: ResultContext,
+    ) -> Result<DataId<O>, SerializeError> {
+        let value = self.value.serialize(serializer, input)?;
+        context.insert_binding(self.binding.id, self.name.clone(), value);
+        self.body.serialize(serializer, context)
+    }
+
+    fn evaluate_let(
+        &mut self,
+        evaluator: &mut Evaluator,
+        input: I,
+        mut context: ResultContext,
+    ) -> DataId<O> {
+        let value = self.value.evaluate(evaluator, input);
+        context.insert_binding(self.binding.id, self.name.clone(), value);
+        self.body.evaluate(evaluator, context)
+    }
+}
+
+///Implementation for a binding that does not inherit any results.
+impl<T: 'static, O> Operation for Let<(), T, O> {
+    type Input = ();
+    type Output = DataId<O>;
+
+    fn serialize(
+        &mut self,
+        serializer: &mut Serializer,
+        input: Self::Input,
+    ) -> Result<Self::Output, SerializeError> {
+        self.serialize_let(serializer, input, ResultContext::new())
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        self.evaluate_let(evaluator, input, ResultContext::new())
+    }
+}
+
+///Implementation for a binding whose value and body inherit the results of some super context.
+impl<T: 'static, O> Operation for Let<ResultContext, T, O> {
+    type Input = ResultContext;
+    type Output = DataId<O>;
+
+    fn serialize(
+        &mut self,
+        serializer: &mut Serializer,
+        input: Self::Input,
+    ) -> Result<Self::Output, SerializeError> {
+        let context = input.clone();
+        self.serialize_let(serializer, input, context)
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        let context = input.clone();
+        self.evaluate_let(evaluator, input, context)
+    }
+}
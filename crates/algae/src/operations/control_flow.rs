@@ -0,0 +1,413 @@
+//! Operations that decide at runtime which value is used.
+
+use rspirv::spirv::{LoopControl, SelectionControl, Word};
+
+use crate::{
+    spv_fi::IntoSpvType, BoxOperation, DataId, Evaluator, Operation, SerializeError, Serializer,
+};
+
+use super::ResultContext;
+
+///Label of the block `serializer` currently emits into, fails if there is none.
+fn block_label<O: ?Sized>(serializer: &Serializer) -> Result<Word, SerializeError> {
+    serializer
+        .current_block_label()
+        .ok_or_else(|| SerializeError::builder::<O>(rspirv::dr::Error::DetachedInstruction(None)))
+}
+
+///Returns `true_branch` if `condition` is true, otherwise `false_branch`.
+///
+/// Both branches are calculated before one result is selected via `OpSelect`. This is usually the best choice if both branches are
+/// cheap to calculate. Use [IfElse] if only the selected branch should be executed.
+pub struct Select<I, T> {
+    pub condition: BoxOperation<I, bool>,
+    pub true_branch: BoxOperation<I, T>,
+    pub false_branch: BoxOperation<I, T>,
+}
+
+impl<I: Clone, T> Operation for Select<I, T>
+where
+    T: IntoSpvType + Clone + 'static,
+{
+    type Input = I;
+    type Output = DataId<T>;
+
+    fn serialize(
+        &mut self,
+        serializer: &mut Serializer,
+        input: Self::Input,
+    ) -> Result<Self::Output, SerializeError> {
+        let cond = self.condition.serialize(serializer, input.clone())?;
+        let rtrue = self.true_branch.serialize(serializer, input.clone())?;
+        let rfalse = self.false_branch.serialize(serializer, input)?;
+        let t_res = serializer.type_id::<T, Self>()?;
+
+        Ok(DataId::from(
+            serializer
+                .value(|builder| builder.select(t_res, None, cond.id, rtrue.id, rfalse.id))
+                .map_err(SerializeError::builder::<Self>)?,
+        ))
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        let cond = self.condition.evaluate(evaluator, input.clone());
+        //Since operations have no side effects it is enough to evaluate the selected branch.
+        if evaluator.load(cond) {
+            self.true_branch.evaluate(evaluator, input)
+        } else {
+            self.false_branch.evaluate(evaluator, input)
+        }
+    }
+}
+
+///Executes `true_branch` if `condition` is true, otherwise `false_branch`.
+///
+/// In contrast to [Select] only the taken branch is executed. This is done via structured control flow, meaning the operation
+/// is serialized into a `OpSelectionMerge`/`OpBranchConditional` diamond whose result is merged via `OpPhi`. Prefer this over [Select]
+/// if at least one branch is expensive.
+///
+/// Each branch can itself contain control flow.
+pub struct IfElse<I, T> {
+    pub condition: BoxOperation<I, bool>,
+    pub true_branch: BoxOperation<I, T>,
+    pub false_branch: BoxOperation<I, T>,
+}
+
+impl<I: Clone, T> Operation for IfElse<I, T>
+where
+    T: IntoSpvType + Clone + 'static,
+{
+    type Input = I;
+    type Output = DataId<T>;
+
+    fn serialize(
+        &mut self,
+        serializer: &mut Serializer,
+        input: Self::Input,
+    ) -> Result<Self::Output, SerializeError> {
+        let cond = self.condition.serialize(serializer, input.clone())?;
+        let t_res = serializer.type_id::<T, Self>()?;
+
+        let true_label = serializer.builder_mut().id();
+        let false_label = serializer.builder_mut().id();
+        let merge_label = serializer.builder_mut().id();
+
+        //End the current block with the branch. Note that rspirv's builder treats the merge instruction as
+        //the block's terminator. Therefore the header block is reselected for the actual branch.
+        let header_block = serializer.builder().selected_block();
+        serializer
+            .builder_mut()
+            .selection_merge(merge_label, SelectionControl::NONE)
+            .map_err(SerializeError::builder::<Self>)?;
+        serializer
+            .builder_mut()
+            .select_block(header_block)
+            .map_err(SerializeError::builder::<Self>)?;
+        serializer
+            .builder_mut()
+            .branch_conditional(cond.id, true_label, false_label, [])
+            .map_err(SerializeError::builder::<Self>)?;
+
+        //Serialize both branches. Note that a branch might have introduced new blocks. Therefore the block that
+        //ends a branch is not necessarily the one it started in.
+        serializer
+            .builder_mut()
+            .begin_block(Some(true_label))
+            .map_err(SerializeError::builder::<Self>)?;
+        //Values of a branch do not dominate the merge block, therefore each branch gets its own scope.
+        serializer.push_scope();
+        let rtrue = self.true_branch.serialize(serializer, input.clone())?;
+        serializer.pop_scope();
+        let true_parent = block_label::<Self>(serializer)?;
+        serializer
+            .builder_mut()
+            .branch(merge_label)
+            .map_err(SerializeError::builder::<Self>)?;
+
+        serializer
+            .builder_mut()
+            .begin_block(Some(false_label))
+            .map_err(SerializeError::builder::<Self>)?;
+        serializer.push_scope();
+        let rfalse = self.false_branch.serialize(serializer, input)?;
+        serializer.pop_scope();
+        let false_parent = block_label::<Self>(serializer)?;
+        serializer
+            .builder_mut()
+            .branch(merge_label)
+            .map_err(SerializeError::builder::<Self>)?;
+
+        //Merge both results
+        serializer
+            .builder_mut()
+            .begin_block(Some(merge_label))
+            .map_err(SerializeError::builder::<Self>)?;
+        Ok(DataId::from(
+            serializer
+                .builder_mut()
+                .phi(
+                    t_res,
+                    None,
+                    [(rtrue.id, true_parent), (rfalse.id, false_parent)],
+                )
+                .map_err(SerializeError::builder::<Self>)?,
+        ))
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        let cond = self.condition.evaluate(evaluator, input.clone());
+        if evaluator.load(cond) {
+            self.true_branch.evaluate(evaluator, input)
+        } else {
+            self.false_branch.evaluate(evaluator, input)
+        }
+    }
+}
+
+///Executes `body` `iterations` times, where each iteration calculates the next value based on the former one. Starts with
+/// the value calculated by `initial`. Returns the value of the last iteration, or the initial value if no iteration was executed.
+///
+/// The body has access to the current iteration index (as `u32`, starting at 0) and the carried value via [AccessResult](super::AccessResult).
+/// The names of those results can be configured via `index_name` and `value_name`. If the loop is used within an [OrderedOperations](super::OrderedOperations)
+/// chain, the body also has access to the results of the chain.
+///
+/// The loop is serialized as a structured `OpLoopMerge` loop, where the carried value and the index are merged via `OpPhi` in the loop's header.
+///
+/// # Example
+///
+/// Sums up the indices `0..10`:
+/// ```rust
+/// use algae::operations::{AccessResult, Addition, Constant, Loop};
+///
+/// let sum = Loop::<(), u32>::new(
+///     Box::new(Constant::new(10u32)),
+///     Box::new(Constant::new(0u32)),
+///     Box::new(Addition{
+///         a: Box::new(AccessResult::<u32>::new(Loop::<(), u32>::DEFAULT_VALUE_NAME)),
+///         b: Box::new(AccessResult::<u32>::new(Loop::<(), u32>::DEFAULT_INDEX_NAME)),
+///     })
+/// );
+/// ```
+pub struct Loop<I, T> {
+    ///Number of iterations.
+    pub iterations: BoxOperation<I, u32>,
+    ///Value before the first iteration.
+    pub initial: BoxOperation<I, T>,
+    ///Calculates the value of the next iteration.
+    pub body: BoxOperation<ResultContext, T>,
+    ///Name under which the iteration index is accessible within `body`.
+    pub index_name: String,
+    ///Name under which the value of the former iteration is accessible within `body`.
+    pub value_name: String,
+}
+
+impl<I, T> Loop<I, T> {
+    pub const DEFAULT_INDEX_NAME: &'static str = "index";
+    pub const DEFAULT_VALUE_NAME: &'static str = "value";
+
+    ///Creates a loop where the index and value are accessible via [DEFAULT_INDEX_NAME](Self::DEFAULT_INDEX_NAME) and [DEFAULT_VALUE_NAME](Self::DEFAULT_VALUE_NAME).
+    pub fn new(
+        iterations: BoxOperation<I, u32>,
+        initial: BoxOperation<I, T>,
+        body: BoxOperation<ResultContext, T>,
+    ) -> Self {
+        Loop {
+            iterations,
+            initial,
+            body,
+            index_name: Self::DEFAULT_INDEX_NAME.to_string(),
+            value_name: Self::DEFAULT_VALUE_NAME.to_string(),
+        }
+    }
+
+    ///Changes the names under which the body can access the index and value. Needed if loops are nested and the outer
+    /// loop's values should be accessible.
+    #[must_use]
+    pub fn with_names(
+        mut self,
+        index_name: impl Into<String>,
+        value_name: impl Into<String>,
+    ) -> Self {
+        self.index_name = index_name.into();
+        self.value_name = value_name.into();
+        self
+    }
+}
+
+impl<I: Clone, T> Loop<I, T>
+where
+    T: IntoSpvType + Clone + 'static,
+{
+    ///Serializes the loop where `context` is the context that is extended for the body.
+    fn serialize_loop(
+        &mut self,
+        serializer: &mut Serializer,
+        input: I,
+        context: ResultContext,
+    ) -> Result<DataId<T>, SerializeError> {
+        let iterations = self.iterations.serialize(serializer, input.clone())?;
+        let initial = self.initial.serialize(serializer, input)?;
+        let zero = 0u32.constant_serialize(serializer)?;
+        let one = 1u32.constant_serialize(serializer)?;
+        let t_u32 = serializer.type_id::<u32, Self>()?;
+        let t_bool = serializer.type_id::<bool, Self>()?;
+        let t_res = serializer.type_id::<T, Self>()?;
+
+        let header_label = serializer.builder_mut().id();
+        let body_label = serializer.builder_mut().id();
+        let continue_label = serializer.builder_mut().id();
+        let merge_label = serializer.builder_mut().id();
+        //Ids of the next index and value. Are defined in the continue block, but already needed by the header's phis.
+        let next_index = serializer.builder_mut().id();
+        let next_value = serializer.builder_mut().id();
+
+        let pre_label = block_label::<Self>(serializer)?;
+        serializer
+            .builder_mut()
+            .branch(header_label)
+            .map_err(SerializeError::builder::<Self>)?;
+
+        //Header, merges index and value, then decides if the body is executed again.
+        serializer
+            .builder_mut()
+            .begin_block(Some(header_label))
+            .map_err(SerializeError::builder::<Self>)?;
+        let index = serializer
+            .builder_mut()
+            .phi(
+                t_u32,
+                None,
+                [(zero.id, pre_label), (next_index, continue_label)],
+            )
+            .map_err(SerializeError::builder::<Self>)?;
+        let value = serializer
+            .builder_mut()
+            .phi(
+                t_res,
+                None,
+                [(initial.id, pre_label), (next_value, continue_label)],
+            )
+            .map_err(SerializeError::builder::<Self>)?;
+        let cond = serializer
+            .builder_mut()
+            .u_less_than(t_bool, None, index, iterations.id)
+            .map_err(SerializeError::builder::<Self>)?;
+        //Same as for the selection merge, the merge instruction is treated as terminator by rspirv.
+        let header_block = serializer.builder().selected_block();
+        serializer
+            .builder_mut()
+            .loop_merge(merge_label, continue_label, LoopControl::NONE, [])
+            .map_err(SerializeError::builder::<Self>)?;
+        serializer
+            .builder_mut()
+            .select_block(header_block)
+            .map_err(SerializeError::builder::<Self>)?;
+        serializer
+            .builder_mut()
+            .branch_conditional(cond, body_label, merge_label, [])
+            .map_err(SerializeError::builder::<Self>)?;
+
+        //Body, might introduce new blocks itself.
+        serializer
+            .builder_mut()
+            .begin_block(Some(body_label))
+            .map_err(SerializeError::builder::<Self>)?;
+        let mut body_context = context;
+        body_context.insert_result(self.index_name.clone(), DataId::<u32>::from(index));
+        body_context.insert_result(self.value_name.clone(), DataId::<T>::from(value));
+        serializer.push_scope();
+        let body_res = self.body.serialize(serializer, body_context)?;
+        serializer.pop_scope();
+        serializer
+            .builder_mut()
+            .branch(continue_label)
+            .map_err(SerializeError::builder::<Self>)?;
+
+        //Continue block, advances index and value.
+        serializer
+            .builder_mut()
+            .begin_block(Some(continue_label))
+            .map_err(SerializeError::builder::<Self>)?;
+        serializer
+            .builder_mut()
+            .i_add(t_u32, Some(next_index), index, one.id)
+            .map_err(SerializeError::builder::<Self>)?;
+        serializer
+            .builder_mut()
+            .copy_object(t_res, Some(next_value), body_res.id)
+            .map_err(SerializeError::builder::<Self>)?;
+        serializer
+            .builder_mut()
+            .branch(header_label)
+            .map_err(SerializeError::builder::<Self>)?;
+
+        //The value merged in the header is the last calculated one, since the header dominates the merge block.
+        serializer
+            .builder_mut()
+            .begin_block(Some(merge_label))
+            .map_err(SerializeError::builder::<Self>)?;
+        Ok(DataId::from(value))
+    }
+
+    fn evaluate_loop(
+        &mut self,
+        evaluator: &mut Evaluator,
+        input: I,
+        context: ResultContext,
+    ) -> DataId<T> {
+        let iterations = self.iterations.evaluate(evaluator, input.clone());
+        let mut value = self.initial.evaluate(evaluator, input);
+        for index in 0..evaluator.load(iterations) {
+            let mut body_context = context.clone();
+            body_context.insert_result(self.index_name.clone(), evaluator.store(index));
+            body_context.insert_result(self.value_name.clone(), value);
+            value = self.body.evaluate(evaluator, body_context);
+        }
+
+        value
+    }
+}
+
+///Implementation for a loop that does not inherit any results.
+impl<T> Operation for Loop<(), T>
+where
+    T: IntoSpvType + Clone + 'static,
+{
+    type Input = ();
+    type Output = DataId<T>;
+
+    fn serialize(
+        &mut self,
+        serializer: &mut Serializer,
+        input: Self::Input,
+    ) -> Result<Self::Output, SerializeError> {
+        self.serialize_loop(serializer, input, ResultContext::new())
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        self.evaluate_loop(evaluator, input, ResultContext::new())
+    }
+}
+
+///Implementation for a loop whose body inherits the results of some super context.
+impl<T> Operation for Loop<ResultContext, T>
+where
+    T: IntoSpvType + Clone + 'static,
+{
+    type Input = ResultContext;
+    type Output = DataId<T>;
+
+    fn serialize(
+        &mut self,
+        serializer: &mut Serializer,
+        input: Self::Input,
+    ) -> Result<Self::Output, SerializeError> {
+        let context = input.clone();
+        self.serialize_loop(serializer, input, context)
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        let context = input.clone();
+        self.evaluate_loop(evaluator, input, context)
+    }
+}
@@ -0,0 +1,98 @@
+//! Conversions between float, signed and unsigned integer scalars and vectors of the same width.
+
+use std::marker::PhantomData;
+
+use glam::{IVec2, IVec3, IVec4, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4};
+
+use crate::{BoxOperation, DataId, Evaluator, Operation};
+
+///Converts the result of `inner` from `S` to `T`.
+///
+/// Floats are rounded towards zero when converted to integers, the result is undefined if the rounded value does not fit
+/// into `T`. Conversions between signed and unsigned integers keep the bits, for instance `-1i32` becomes `u32::MAX`.
+pub struct Convert<S, T, I> {
+    pub inner: BoxOperation<I, S>,
+    pub ty: PhantomData<T>,
+}
+
+impl<S, T, I> Convert<S, T, I> {
+    pub fn new(inner: BoxOperation<I, S>) -> Self {
+        Convert {
+            inner,
+            ty: PhantomData,
+        }
+    }
+}
+
+///Implements the conversion from `$from` to `$to` via the builder `$builder_fn`. `$eval` converts the value when evaluating.
+macro_rules! impl_convert {
+    ($builder_fn:ident, $($from:ty => $to:ty, $eval:expr);+) => {
+        $(
+            impl<I> Operation for Convert<$from, $to, I> {
+                type Input = I;
+                type Output = DataId<$to>;
+
+                fn serialize(
+                    &mut self,
+                    serializer: &mut crate::Serializer,
+                    input: Self::Input,
+                ) -> Result<Self::Output, crate::SerializeError> {
+                    let res = self.inner.serialize(serializer, input)?;
+                    let t_res = serializer.type_id::<$to, Self>()?;
+                    Ok(DataId::from(
+                        serializer
+                            .value(|builder| builder.$builder_fn(t_res, None, res.id))
+                            .map_err(crate::SerializeError::builder::<Self>)?,
+                    ))
+                }
+
+                fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                    let res = self.inner.evaluate(evaluator, input);
+                    let eval: fn($from) -> $to = $eval;
+                    let converted = eval(evaluator.load(res));
+                    evaluator.store(converted)
+                }
+            }
+        )+
+    };
+}
+
+impl_convert!(
+    convert_f_to_s,
+    f32 => i32, |x| x as i32;
+    Vec2 => IVec2, |x| x.as_ivec2();
+    Vec3 => IVec3, |x| x.as_ivec3();
+    Vec4 => IVec4, |x| x.as_ivec4()
+);
+impl_convert!(
+    convert_f_to_u,
+    f32 => u32, |x| x as u32;
+    Vec2 => UVec2, |x| x.as_uvec2();
+    Vec3 => UVec3, |x| x.as_uvec3();
+    Vec4 => UVec4, |x| x.as_uvec4()
+);
+impl_convert!(
+    convert_s_to_f,
+    i32 => f32, |x| x as f32;
+    IVec2 => Vec2, |x| x.as_vec2();
+    IVec3 => Vec3, |x| x.as_vec3();
+    IVec4 => Vec4, |x| x.as_vec4()
+);
+impl_convert!(
+    convert_u_to_f,
+    u32 => f32, |x| x as f32;
+    UVec2 => Vec2, |x| x.as_vec2();
+    UVec3 => Vec3, |x| x.as_vec3();
+    UVec4 => Vec4, |x| x.as_vec4()
+);
+impl_convert!(
+    bitcast,
+    i32 => u32, |x| x as u32;
+    IVec2 => UVec2, |x| x.as_uvec2();
+    IVec3 => UVec3, |x| x.as_uvec3();
+    IVec4 => UVec4, |x| x.as_uvec4();
+    u32 => i32, |x| x as i32;
+    UVec2 => IVec2, |x| x.as_ivec2();
+    UVec3 => IVec3, |x| x.as_ivec3();
+    UVec4 => IVec4, |x| x.as_ivec4()
+);
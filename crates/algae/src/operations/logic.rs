@@ -0,0 +1,435 @@
+//! Comparison and boolean logic operations.
+//!
+//! Comparing scalars results in a `bool`, comparing vectors is done component wise and results in a boolean vector of the same
+//! length (for instance `BVec3` when comparing two `Vec3`). Those can be reduced to a single `bool` via [Any] or [All].
+//!
+//! Float comparisons are *ordered*, meaning that they are false if any operand is NaN. The only exception is [NotEqual], which is
+//! true in that case, similar to Rust's `!=`.
+
+use glam::{BVec2, BVec3, BVec4, IVec2, IVec3, IVec4, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4};
+
+use crate::{BoxOperation, DataId, Evaluator, Operation};
+
+///Returns true if `a < b`.
+pub struct Less<I, T> {
+    pub a: BoxOperation<I, T>,
+    pub b: BoxOperation<I, T>,
+}
+
+///Returns true if `a <= b`.
+pub struct LessEqual<I, T> {
+    pub a: BoxOperation<I, T>,
+    pub b: BoxOperation<I, T>,
+}
+
+///Returns true if `a > b`.
+pub struct Greater<I, T> {
+    pub a: BoxOperation<I, T>,
+    pub b: BoxOperation<I, T>,
+}
+
+///Returns true if `a >= b`.
+pub struct GreaterEqual<I, T> {
+    pub a: BoxOperation<I, T>,
+    pub b: BoxOperation<I, T>,
+}
+
+///Returns true if `a == b`.
+pub struct Equal<I, T> {
+    pub a: BoxOperation<I, T>,
+    pub b: BoxOperation<I, T>,
+}
+
+///Returns true if `a != b`.
+pub struct NotEqual<I, T> {
+    pub a: BoxOperation<I, T>,
+    pub b: BoxOperation<I, T>,
+}
+
+///Logical and of two booleans, or boolean vectors.
+pub struct And<I, B> {
+    pub a: BoxOperation<I, B>,
+    pub b: BoxOperation<I, B>,
+}
+
+///Logical or of two booleans, or boolean vectors.
+pub struct Or<I, B> {
+    pub a: BoxOperation<I, B>,
+    pub b: BoxOperation<I, B>,
+}
+
+///Logical negation of a boolean, or boolean vector.
+pub struct Not<I, B> {
+    pub inner: BoxOperation<I, B>,
+}
+
+///Returns true if any component of the boolean vector `V` is true.
+pub struct Any<I, V> {
+    pub inner: BoxOperation<I, V>,
+}
+
+///Returns true if all components of the boolean vector `V` are true.
+pub struct All<I, V> {
+    pub inner: BoxOperation<I, V>,
+}
+
+///Component wise comparison of scalars and vectors. `Mask` is the boolean type that holds one result per component.
+trait CompareComponents: Copy {
+    type Element: Copy;
+    type Mask;
+    fn compare(self, other: Self, f: impl Fn(Self::Element, Self::Element) -> bool) -> Self::Mask;
+}
+
+macro_rules! impl_compare_scalar {
+    ($($ty:ty),+) => {
+        $(
+            impl CompareComponents for $ty {
+                type Element = $ty;
+                type Mask = bool;
+                fn compare(
+                    self,
+                    other: Self,
+                    f: impl Fn(Self::Element, Self::Element) -> bool,
+                ) -> Self::Mask {
+                    f(self, other)
+                }
+            }
+        )+
+    };
+}
+
+impl_compare_scalar!(f32, i32, u32, i64, u64);
+
+macro_rules! impl_compare_vec {
+    ($vecty:ty, $basety:ty, $maskty:ty, $($element_name:ident),+) => {
+        impl CompareComponents for $vecty {
+            type Element = $basety;
+            type Mask = $maskty;
+            fn compare(
+                self,
+                other: Self,
+                f: impl Fn(Self::Element, Self::Element) -> bool,
+            ) -> Self::Mask {
+                <$maskty>::new($(f(self.$element_name, other.$element_name)),+)
+            }
+        }
+    };
+}
+
+impl_compare_vec!(Vec2, f32, BVec2, x, y);
+impl_compare_vec!(Vec3, f32, BVec3, x, y, z);
+impl_compare_vec!(Vec4, f32, BVec4, x, y, z, w);
+impl_compare_vec!(IVec2, i32, BVec2, x, y);
+impl_compare_vec!(IVec3, i32, BVec3, x, y, z);
+impl_compare_vec!(IVec4, i32, BVec4, x, y, z, w);
+impl_compare_vec!(UVec2, u32, BVec2, x, y);
+impl_compare_vec!(UVec3, u32, BVec3, x, y, z);
+impl_compare_vec!(UVec4, u32, BVec4, x, y, z, w);
+
+///Implements the comparison `$op` via the builder function `$builder_fn` for all supplied types. `$cmp` is used per component
+/// when evaluating.
+macro_rules! impl_compare {
+    ($op:ident, $builder_fn:ident, $cmp:expr, $($ty:ty => $maskty:ty),+) => {
+        $(
+            impl<I: Clone> Operation for $op<I, $ty> {
+                type Input = I;
+                type Output = DataId<$maskty>;
+
+                fn serialize(
+                    &mut self,
+                    serializer: &mut crate::Serializer,
+                    input: Self::Input,
+                ) -> Result<Self::Output, crate::SerializeError> {
+                    let ra = self.a.serialize(serializer, input.clone())?;
+                    let rb = self.b.serialize(serializer, input)?;
+                    let t_mask = serializer.type_id::<$maskty, Self>()?;
+                    Ok(DataId::from(
+                        serializer
+                            .value(|builder| builder.$builder_fn(t_mask, None, ra.id, rb.id))
+                            .map_err(crate::SerializeError::builder::<Self>)?,
+                    ))
+                }
+
+                fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                    let ra = self.a.evaluate(evaluator, input.clone());
+                    let rb = self.b.evaluate(evaluator, input);
+                    let res = evaluator.load(ra).compare(evaluator.load(rb), $cmp);
+                    evaluator.store(res)
+                }
+            }
+        )+
+    };
+}
+
+impl_compare!(
+    Less,
+    f_ord_less_than,
+    |a, b| a < b,
+    f32 => bool,
+    Vec2 => BVec2,
+    Vec3 => BVec3,
+    Vec4 => BVec4
+);
+impl_compare!(
+    LessEqual,
+    f_ord_less_than_equal,
+    |a, b| a <= b,
+    f32 => bool,
+    Vec2 => BVec2,
+    Vec3 => BVec3,
+    Vec4 => BVec4
+);
+impl_compare!(
+    Greater,
+    f_ord_greater_than,
+    |a, b| a > b,
+    f32 => bool,
+    Vec2 => BVec2,
+    Vec3 => BVec3,
+    Vec4 => BVec4
+);
+impl_compare!(
+    GreaterEqual,
+    f_ord_greater_than_equal,
+    |a, b| a >= b,
+    f32 => bool,
+    Vec2 => BVec2,
+    Vec3 => BVec3,
+    Vec4 => BVec4
+);
+impl_compare!(
+    Equal,
+    f_ord_equal,
+    |a, b| a == b,
+    f32 => bool,
+    Vec2 => BVec2,
+    Vec3 => BVec3,
+    Vec4 => BVec4
+);
+impl_compare!(
+    NotEqual,
+    f_unord_not_equal,
+    |a, b| a != b,
+    f32 => bool,
+    Vec2 => BVec2,
+    Vec3 => BVec3,
+    Vec4 => BVec4
+);
+
+impl_compare!(
+    Less,
+    s_less_than,
+    |a, b| a < b,
+    i32 => bool,
+    i64 => bool,
+    IVec2 => BVec2,
+    IVec3 => BVec3,
+    IVec4 => BVec4
+);
+impl_compare!(
+    LessEqual,
+    s_less_than_equal,
+    |a, b| a <= b,
+    i32 => bool,
+    i64 => bool,
+    IVec2 => BVec2,
+    IVec3 => BVec3,
+    IVec4 => BVec4
+);
+impl_compare!(
+    Greater,
+    s_greater_than,
+    |a, b| a > b,
+    i32 => bool,
+    i64 => bool,
+    IVec2 => BVec2,
+    IVec3 => BVec3,
+    IVec4 => BVec4
+);
+impl_compare!(
+    GreaterEqual,
+    s_greater_than_equal,
+    |a, b| a >= b,
+    i32 => bool,
+    i64 => bool,
+    IVec2 => BVec2,
+    IVec3 => BVec3,
+    IVec4 => BVec4
+);
+
+impl_compare!(
+    Less,
+    u_less_than,
+    |a, b| a < b,
+    u32 => bool,
+    u64 => bool,
+    UVec2 => BVec2,
+    UVec3 => BVec3,
+    UVec4 => BVec4
+);
+impl_compare!(
+    LessEqual,
+    u_less_than_equal,
+    |a, b| a <= b,
+    u32 => bool,
+    u64 => bool,
+    UVec2 => BVec2,
+    UVec3 => BVec3,
+    UVec4 => BVec4
+);
+impl_compare!(
+    Greater,
+    u_greater_than,
+    |a, b| a > b,
+    u32 => bool,
+    u64 => bool,
+    UVec2 => BVec2,
+    UVec3 => BVec3,
+    UVec4 => BVec4
+);
+impl_compare!(
+    GreaterEqual,
+    u_greater_than_equal,
+    |a, b| a >= b,
+    u32 => bool,
+    u64 => bool,
+    UVec2 => BVec2,
+    UVec3 => BVec3,
+    UVec4 => BVec4
+);
+
+impl_compare!(
+    Equal,
+    i_equal,
+    |a, b| a == b,
+    i32 => bool,
+    i64 => bool,
+    u32 => bool,
+    u64 => bool,
+    IVec2 => BVec2,
+    IVec3 => BVec3,
+    IVec4 => BVec4,
+    UVec2 => BVec2,
+    UVec3 => BVec3,
+    UVec4 => BVec4
+);
+impl_compare!(
+    NotEqual,
+    i_not_equal,
+    |a, b| a != b,
+    i32 => bool,
+    i64 => bool,
+    u32 => bool,
+    u64 => bool,
+    IVec2 => BVec2,
+    IVec3 => BVec3,
+    IVec4 => BVec4,
+    UVec2 => BVec2,
+    UVec3 => BVec3,
+    UVec4 => BVec4
+);
+
+///Implements a two operand boolean operation `$op` via `$builder_fn`. `$rustop` is the according rust operator.
+macro_rules! impl_logic_binop {
+    ($op:ident, $builder_fn:ident, $rustop:tt, $($ty:ty),+) => {
+        $(
+            impl<I: Clone> Operation for $op<I, $ty> {
+                type Input = I;
+                type Output = DataId<$ty>;
+
+                fn serialize(
+                    &mut self,
+                    serializer: &mut crate::Serializer,
+                    input: Self::Input,
+                ) -> Result<Self::Output, crate::SerializeError> {
+                    let ra = self.a.serialize(serializer, input.clone())?;
+                    let rb = self.b.serialize(serializer, input)?;
+                    let t_bool = serializer.type_id::<$ty, Self>()?;
+                    Ok(DataId::from(
+                        serializer
+                            .value(|builder| builder.$builder_fn(t_bool, None, ra.id, rb.id))
+                            .map_err(crate::SerializeError::builder::<Self>)?,
+                    ))
+                }
+
+                fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                    let ra = self.a.evaluate(evaluator, input.clone());
+                    let rb = self.b.evaluate(evaluator, input);
+                    let res = evaluator.load(ra) $rustop evaluator.load(rb);
+                    evaluator.store(res)
+                }
+            }
+        )+
+    };
+}
+
+impl_logic_binop!(And, logical_and, &, bool, BVec2, BVec3, BVec4);
+impl_logic_binop!(Or, logical_or, |, bool, BVec2, BVec3, BVec4);
+
+macro_rules! impl_not {
+    ($($ty:ty),+) => {
+        $(
+            impl<I> Operation for Not<I, $ty> {
+                type Input = I;
+                type Output = DataId<$ty>;
+
+                fn serialize(
+                    &mut self,
+                    serializer: &mut crate::Serializer,
+                    input: Self::Input,
+                ) -> Result<Self::Output, crate::SerializeError> {
+                    let ra = self.inner.serialize(serializer, input)?;
+                    let t_bool = serializer.type_id::<$ty, Self>()?;
+                    Ok(DataId::from(
+                        serializer
+                            .value(|builder| builder.logical_not(t_bool, None, ra.id))
+                            .map_err(crate::SerializeError::builder::<Self>)?,
+                    ))
+                }
+
+                fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                    let ra = self.inner.evaluate(evaluator, input);
+                    let res = !evaluator.load(ra);
+                    evaluator.store(res)
+                }
+            }
+        )+
+    };
+}
+
+impl_not!(bool, BVec2, BVec3, BVec4);
+
+///Implements the vector reduction `$op` via `$builder_fn` which is evaluated via the mask's `$rustfn`.
+macro_rules! impl_reduce {
+    ($op:ident, $builder_fn:ident, $rustfn:ident, $($ty:ty),+) => {
+        $(
+            impl<I> Operation for $op<I, $ty> {
+                type Input = I;
+                type Output = DataId<bool>;
+
+                fn serialize(
+                    &mut self,
+                    serializer: &mut crate::Serializer,
+                    input: Self::Input,
+                ) -> Result<Self::Output, crate::SerializeError> {
+                    let ra = self.inner.serialize(serializer, input)?;
+                    let t_bool = serializer.type_id::<bool, Self>()?;
+                    Ok(DataId::from(
+                        serializer
+                            .value(|builder| builder.$builder_fn(t_bool, None, ra.id))
+                            .map_err(crate::SerializeError::builder::<Self>)?,
+                    ))
+                }
+
+                fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                    let ra = self.inner.evaluate(evaluator, input);
+                    let res = evaluator.load(ra).$rustfn();
+                    evaluator.store(res)
+                }
+            }
+        )+
+    };
+}
+
+impl_reduce!(Any, any, any, BVec2, BVec3, BVec4);
+impl_reduce!(All, all, all, BVec2, BVec3, BVec4);
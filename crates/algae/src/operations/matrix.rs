@@ -0,0 +1,227 @@
+//! Linear algebra operations on square float matrices.
+//!
+//! Matrices are column major, like glam's and SpirV's matrices.
+
+use glam::{Mat2, Mat3, Mat4, Vec2, Vec3, Vec4};
+use rspirv::dr::Operand;
+
+use crate::{BoxOperation, DataId, Evaluator, Operation};
+
+///Transforms the column vector `vector` by `matrix`: `result = matrix * vector`.
+pub struct MatrixTimesVector<M, V, I> {
+    pub matrix: BoxOperation<I, M>,
+    pub vector: BoxOperation<I, V>,
+}
+
+///Transforms the row vector `vector` by `matrix`: `result = vector * matrix`. Same as transforming by the transposed matrix.
+pub struct VectorTimesMatrix<M, V, I> {
+    pub vector: BoxOperation<I, V>,
+    pub matrix: BoxOperation<I, M>,
+}
+
+///Matrix product of two matrices: `result = a * b`. When transforming a vector by the result, `b` is applied first.
+pub struct MatrixTimesMatrix<M, I> {
+    pub a: BoxOperation<I, M>,
+    pub b: BoxOperation<I, M>,
+}
+
+///Returns the transposed `inner` matrix.
+pub struct Transpose<M, I> {
+    pub inner: BoxOperation<I, M>,
+}
+
+///Returns the determinant of the `inner` matrix.
+pub struct Determinant<M, I> {
+    pub inner: BoxOperation<I, M>,
+}
+
+///Returns the inverse of the `inner` matrix. The result is undefined if the matrix is singular, i.e. its determinant is 0.
+pub struct MatrixInverse<M, I> {
+    pub inner: BoxOperation<I, M>,
+}
+
+macro_rules! impl_matrix_ops {
+    ($matty:ty, $vecty:ty) => {
+        impl<I: Clone> Operation for MatrixTimesVector<$matty, $vecty, I> {
+            type Input = I;
+            type Output = DataId<$vecty>;
+
+            fn serialize(
+                &mut self,
+                serializer: &mut crate::Serializer,
+                input: Self::Input,
+            ) -> Result<Self::Output, crate::SerializeError> {
+                let rm = self.matrix.serialize(serializer, input.clone())?;
+                let rv = self.vector.serialize(serializer, input)?;
+                let tvec = serializer.type_id::<$vecty, Self>()?;
+                Ok(DataId::from(
+                    serializer
+                        .value(|builder| builder.matrix_times_vector(tvec, None, rm.id, rv.id))
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let rm = self.matrix.evaluate(evaluator, input.clone());
+                let rv = self.vector.evaluate(evaluator, input);
+                let res = evaluator.load(rm) * evaluator.load(rv);
+                evaluator.store(res)
+            }
+        }
+
+        impl<I: Clone> Operation for VectorTimesMatrix<$matty, $vecty, I> {
+            type Input = I;
+            type Output = DataId<$vecty>;
+
+            fn serialize(
+                &mut self,
+                serializer: &mut crate::Serializer,
+                input: Self::Input,
+            ) -> Result<Self::Output, crate::SerializeError> {
+                let rv = self.vector.serialize(serializer, input.clone())?;
+                let rm = self.matrix.serialize(serializer, input)?;
+                let tvec = serializer.type_id::<$vecty, Self>()?;
+                Ok(DataId::from(
+                    serializer
+                        .value(|builder| builder.vector_times_matrix(tvec, None, rv.id, rm.id))
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let rv = self.vector.evaluate(evaluator, input.clone());
+                let rm = self.matrix.evaluate(evaluator, input);
+                let res = evaluator.load(rm).transpose() * evaluator.load(rv);
+                evaluator.store(res)
+            }
+        }
+
+        impl<I: Clone> Operation for MatrixTimesMatrix<$matty, I> {
+            type Input = I;
+            type Output = DataId<$matty>;
+
+            fn serialize(
+                &mut self,
+                serializer: &mut crate::Serializer,
+                input: Self::Input,
+            ) -> Result<Self::Output, crate::SerializeError> {
+                let ra = self.a.serialize(serializer, input.clone())?;
+                let rb = self.b.serialize(serializer, input)?;
+                let tmat = serializer.type_id::<$matty, Self>()?;
+                Ok(DataId::from(
+                    serializer
+                        .value(|builder| builder.matrix_times_matrix(tmat, None, ra.id, rb.id))
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let ra = self.a.evaluate(evaluator, input.clone());
+                let rb = self.b.evaluate(evaluator, input);
+                let res = evaluator.load(ra) * evaluator.load(rb);
+                evaluator.store(res)
+            }
+        }
+
+        impl<I> Operation for Transpose<$matty, I> {
+            type Input = I;
+            type Output = DataId<$matty>;
+
+            fn serialize(
+                &mut self,
+                serializer: &mut crate::Serializer,
+                input: Self::Input,
+            ) -> Result<Self::Output, crate::SerializeError> {
+                let res = self.inner.serialize(serializer, input)?;
+                let tmat = serializer.type_id::<$matty, Self>()?;
+                Ok(DataId::from(
+                    serializer
+                        .value(|builder| builder.transpose(tmat, None, res.id))
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let ra = self.inner.evaluate(evaluator, input);
+                let res = evaluator.load(ra).transpose();
+                evaluator.store(res)
+            }
+        }
+
+        impl<I> Operation for Determinant<$matty, I> {
+            type Input = I;
+            type Output = DataId<f32>;
+
+            fn serialize(
+                &mut self,
+                serializer: &mut crate::Serializer,
+                input: Self::Input,
+            ) -> Result<Self::Output, crate::SerializeError> {
+                let res = self.inner.serialize(serializer, input)?;
+                let tf32 = serializer.type_id::<f32, Self>()?;
+
+                //Load instructionset
+                let ext_instset_id = serializer.glsl_std_450();
+                Ok(DataId::from(
+                    serializer
+                        .value(|builder| {
+                            builder.ext_inst(
+                                tf32,
+                                None,
+                                ext_instset_id,
+                                33,
+                                [Operand::IdRef(res.id)],
+                            )
+                        })
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let ra = self.inner.evaluate(evaluator, input);
+                let res = evaluator.load(ra).determinant();
+                evaluator.store(res)
+            }
+        }
+
+        impl<I> Operation for MatrixInverse<$matty, I> {
+            type Input = I;
+            type Output = DataId<$matty>;
+
+            fn serialize(
+                &mut self,
+                serializer: &mut crate::Serializer,
+                input: Self::Input,
+            ) -> Result<Self::Output, crate::SerializeError> {
+                let res = self.inner.serialize(serializer, input)?;
+                let tmat = serializer.type_id::<$matty, Self>()?;
+
+                //Load instructionset
+                let ext_instset_id = serializer.glsl_std_450();
+                Ok(DataId::from(
+                    serializer
+                        .value(|builder| {
+                            builder.ext_inst(
+                                tmat,
+                                None,
+                                ext_instset_id,
+                                34,
+                                [Operand::IdRef(res.id)],
+                            )
+                        })
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let ra = self.inner.evaluate(evaluator, input);
+                let res = evaluator.load(ra).inverse();
+                evaluator.store(res)
+            }
+        }
+    };
+}
+
+impl_matrix_ops!(Mat2, Vec2);
+impl_matrix_ops!(Mat3, Vec3);
+impl_matrix_ops!(Mat4, Vec4);
@@ -1,12 +1,38 @@
 pub(crate) mod arithmetic;
+pub(crate) mod binding;
+pub(crate) mod control_flow;
+pub(crate) mod conversion;
+pub(crate) mod logic;
+pub(crate) mod matrix;
 pub(crate) mod native;
+pub(crate) mod normal;
 pub(crate) mod vector;
 pub(crate) mod op_order;
 
 pub use arithmetic::{
-    trigonomy::{Cosine, Sine, Tangent},
-    Abs, Addition, Division, Max, Min, Multiplication, Sqrt, Square, Subtraction,
+    trigonomy::{
+        ArcCosine, ArcSine, ArcTangent, ArcTangent2, Cosine, HyperbolicArcCosine,
+        HyperbolicArcSine, HyperbolicArcTangent, HyperbolicCosine, HyperbolicSine,
+        HyperbolicTangent, Sine, Tangent,
+    },
+    Abs, Addition, BitwiseAnd, BitwiseOr, BitwiseXor, Ceil, Clamp, Division, Exp, Exp2, Floor, Fma,
+    Fract, InverseSqrt, Log, Log2, Max, Min, Mix, Modulo, Multiplication, Negate, Pow, Remainder,
+    Round, ShiftLeft, ShiftRight, Sign, SmoothStep, Sqrt, Square, Step, Subtraction, Trunc,
+    VectorDividedByScalar, VectorMinusScalar, VectorPlusScalar, VectorTimesScalar,
 };
-pub use native::{Constant, MapInput, ReturnInput, Variable};
-pub use vector::{Cross, Length, Normalize, VecSelectElement};
-pub use op_order::{AccessResult, OrderedOperations, ResultContext};
+pub use binding::{Binding, Let};
+pub use control_flow::{IfElse, Loop, Select};
+pub use conversion::Convert;
+pub use logic::{
+    All, And, Any, Equal, Greater, GreaterEqual, Less, LessEqual, Not, NotEqual, Or,
+};
+pub use matrix::{
+    Determinant, MatrixInverse, MatrixTimesMatrix, MatrixTimesVector, Transpose, VectorTimesMatrix,
+};
+pub use native::{Constant, MapInput, ReturnInput, Variable, WithInput};
+pub use normal::{CentralDifferenceNormal, TetrahedronNormal};
+pub use vector::{
+    Cross, Distance, Dot, FaceForward, Length, Normalize, Reflect, Refract, Splat, Swizzle,
+    VecConstruct, VecSelectElement,
+};
+pub use op_order::{AccessOrDefault, AccessResult, OrderedOperations, ResultContext};
@@ -2,7 +2,9 @@
 
 use std::{any::Any, marker::PhantomData};
 
-use crate::{spv_fi::IntoSpvType, BoxOperation, DataId, Operation, Serializer};
+use crate::{
+    spv_fi::IntoSpvType, BoxOperation, DataId, Evaluator, Operation, SerializeError, Serializer,
+};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Constant<I, T> {
@@ -22,14 +24,22 @@ impl<I, T: 'static> Constant<I, T>{
 ///Implements Constant for any type that can be also expressed as a SpirvType
 impl<I, T> Operation for Constant<I, T>
 where
-    T: IntoSpvType,
+    T: IntoSpvType + Clone + 'static,
 {
     type Input = I;
     type Output = DataId<T>;
 
-    fn serialize(&mut self, serializer: &mut Serializer, _input: Self::Input) -> Self::Output {
+    fn serialize(
+        &mut self,
+        serializer: &mut Serializer,
+        _input: Self::Input,
+    ) -> Result<Self::Output, SerializeError> {
         self.value.constant_serialize(serializer)
     }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, _input: Self::Input) -> Self::Output {
+        evaluator.store(self.value.clone())
+    }
 }
 
 ///Data id implements Operation a well, which allows us to use formerly calculated values as input
@@ -38,7 +48,15 @@ impl<T: Clone> Operation for DataId<T>{
     type Input = ();
     type Output = DataId<T>;
 
-    fn serialize(&mut self, _serializer: &mut Serializer, _input: Self::Input) -> Self::Output {
+    fn serialize(
+        &mut self,
+        _serializer: &mut Serializer,
+        _input: Self::Input,
+    ) -> Result<Self::Output, SerializeError> {
+        Ok(self.clone())
+    }
+
+    fn evaluate(&mut self, _evaluator: &mut Evaluator, _input: Self::Input) -> Self::Output {
         self.clone()
     }
 }
@@ -58,7 +76,15 @@ impl<T> Operation for ReturnInput<T> {
     type Input = DataId<T>;
     type Output = DataId<T>;
 
-    fn serialize(&mut self, _serializer: &mut Serializer, input: Self::Input) -> Self::Output {
+    fn serialize(
+        &mut self,
+        _serializer: &mut Serializer,
+        input: Self::Input,
+    ) -> Result<Self::Output, SerializeError> {
+        Ok(input)
+    }
+
+    fn evaluate(&mut self, _evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
         input
     }
 }
@@ -82,10 +108,45 @@ impl<I, NI, O> Operation for MapInput<I, NI, O> {
     type Input = I;
     type Output = DataId<O>;
 
-    fn serialize(&mut self, serializer: &mut Serializer, input: Self::Input) -> Self::Output {
+    fn serialize(
+        &mut self,
+        serializer: &mut Serializer,
+        input: Self::Input,
+    ) -> Result<Self::Output, SerializeError> {
         let mapped_input: NI = (self.mapping)(input);
         self.inner_operation.serialize(serializer, mapped_input)
     }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        let mapped_input: NI = (self.mapping)(input);
+        self.inner_operation.evaluate(evaluator, mapped_input)
+    }
+}
+
+///Calculates `input` and passes its result to `inner` as input. Unlike [MapInput], the new input is calculated at runtime,
+/// for instance the position a signed distance field is sampled at.
+pub struct WithInput<I, T, O> {
+    pub input: BoxOperation<I, T>,
+    pub inner: BoxOperation<DataId<T>, O>,
+}
+
+impl<I, T, O> Operation for WithInput<I, T, O> {
+    type Input = I;
+    type Output = DataId<O>;
+
+    fn serialize(
+        &mut self,
+        serializer: &mut Serializer,
+        input: Self::Input,
+    ) -> Result<Self::Output, SerializeError> {
+        let new_input = self.input.serialize(serializer, input)?;
+        self.inner.serialize(serializer, new_input)
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        let new_input = self.input.evaluate(evaluator, input);
+        self.inner.evaluate(evaluator, new_input)
+    }
 }
 
 ///Runtime setable variable identified by the given name. Type safety is checked at runtime.
@@ -116,7 +177,15 @@ where
     type Input = I;
     type Output = DataId<T>;
 
-    fn serialize(&mut self, serializer: &mut Serializer, _input: Self::Input) -> Self::Output {
+    fn serialize(
+        &mut self,
+        serializer: &mut Serializer,
+        _input: Self::Input,
+    ) -> Result<Self::Output, SerializeError> {
         serializer.get_variable::<T>(&self.name, self.default_value.value.clone())
     }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, _input: Self::Input) -> Self::Output {
+        evaluator.get_variable::<T>(&self.name, self.default_value.value.clone())
+    }
 }
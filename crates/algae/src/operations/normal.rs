@@ -0,0 +1,148 @@
+//! Normals of signed distance fields that are approximated by sampling the field around a position. Works for any field,
+//! including custom operations that have no analytic gradient.
+use glam::{Vec2, Vec3};
+
+use crate::{
+    operations::{Addition, Constant, Normalize, VectorTimesScalar},
+    spv_fi::IntoSpvType,
+    BoxOperation, DataId, Evaluator, Operation, SerializeError, Serializer,
+};
+
+///Normal of the signed distance field `inner` at the input position, approximated by central differences. Samples `inner`
+/// at `position ± epsilon` along each axis, which needs two samples per dimension.
+///
+/// `inner` receives the sampled position as its input, for instance via [ReturnInput](crate::operations::ReturnInput).
+pub struct CentralDifferenceNormal<V> {
+    pub inner: BoxOperation<DataId<V>, f32>,
+    ///Distance of the samples to the position.
+    pub epsilon: f32,
+}
+
+impl<V> CentralDifferenceNormal<V> {
+    pub fn new(inner: BoxOperation<DataId<V>, f32>, epsilon: f32) -> Self {
+        CentralDifferenceNormal { inner, epsilon }
+    }
+}
+
+///Normal of the signed distance field `inner` at the input position, approximated by the tetrahedron technique. Samples
+/// `inner` at the corners of a tetrahedron around the position, which needs four samples for a `Vec3`. A `Vec2` is
+/// sampled at the three corners of a triangle.
+///
+/// Needs fewer samples than [CentralDifferenceNormal], but is slightly less accurate.
+pub struct TetrahedronNormal<V> {
+    pub inner: BoxOperation<DataId<V>, f32>,
+    ///Distance of the samples to the position.
+    pub epsilon: f32,
+}
+
+impl<V> TetrahedronNormal<V> {
+    pub fn new(inner: BoxOperation<DataId<V>, f32>, epsilon: f32) -> Self {
+        TetrahedronNormal { inner, epsilon }
+    }
+}
+
+///`position + offset`
+fn offset_position<V>(position: DataId<V>, offset: V) -> Addition<(), V>
+where
+    V: IntoSpvType + Clone + 'static,
+{
+    Addition {
+        a: Box::new(position),
+        b: Box::new(Constant::new(offset)),
+    }
+}
+
+///`normalize(sum(direction * sample))`. For directions that are symmetric around the origin this is the direction of the
+/// gradient.
+fn weighted_sum<V>(directions: &[V], samples: Vec<DataId<f32>>) -> Normalize<V, ()>
+where
+    V: IntoSpvType + Copy + 'static,
+    Addition<(), V>: Operation<Input = (), Output = DataId<V>>,
+    VectorTimesScalar<(), V>: Operation<Input = (), Output = DataId<V>>,
+{
+    let mut terms =
+        directions
+            .iter()
+            .zip(samples)
+            .map(|(direction, sample)| -> BoxOperation<(), V> {
+                Box::new(VectorTimesScalar {
+                    vector: Box::new(Constant::new(*direction)),
+                    scalar: Box::new(sample),
+                })
+            });
+    let first = terms.next().expect("Normal needs at least one sample");
+    Normalize {
+        inner: terms.fold(first, |a, b| Box::new(Addition { a, b })),
+    }
+}
+
+macro_rules! impl_sampled_normal {
+    ($normal:ident, $vecty:ty, [$($direction:expr),+]) => {
+        impl Operation for $normal<$vecty> {
+            type Input = DataId<$vecty>;
+            type Output = DataId<$vecty>;
+
+            fn serialize(
+                &mut self,
+                serializer: &mut Serializer,
+                input: Self::Input,
+            ) -> Result<Self::Output, SerializeError> {
+                let directions = [$($direction),+];
+                let samples = directions
+                    .iter()
+                    .map(|direction| {
+                        let position = offset_position(input, *direction * self.epsilon)
+                            .serialize(serializer, ())?;
+                        self.inner.serialize(serializer, position)
+                    })
+                    .collect::<Result<_, _>>()?;
+                weighted_sum(&directions, samples).serialize(serializer, ())
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let directions = [$($direction),+];
+                let samples = directions
+                    .iter()
+                    .map(|direction| {
+                        let position = offset_position(input, *direction * self.epsilon)
+                            .evaluate(evaluator, ());
+                        self.inner.evaluate(evaluator, position)
+                    })
+                    .collect();
+                weighted_sum(&directions, samples).evaluate(evaluator, ())
+            }
+        }
+    };
+}
+
+impl_sampled_normal!(
+    CentralDifferenceNormal,
+    Vec2,
+    [Vec2::X, -Vec2::X, Vec2::Y, -Vec2::Y]
+);
+impl_sampled_normal!(
+    CentralDifferenceNormal,
+    Vec3,
+    [Vec3::X, -Vec3::X, Vec3::Y, -Vec3::Y, Vec3::Z, -Vec3::Z]
+);
+//Corners of an equilateral triangle
+impl_sampled_normal!(
+    TetrahedronNormal,
+    Vec2,
+    [
+        Vec2::X,
+        Vec2::new(-0.5, 0.75f32.sqrt()),
+        Vec2::new(-0.5, -(0.75f32.sqrt()))
+    ]
+);
+//Corners of a regular tetrahedron, normalized so that they are as far from the position as `epsilon`
+impl_sampled_normal!(
+    TetrahedronNormal,
+    Vec3,
+    [
+        Vec3::new(1.0, -1.0, -1.0).normalize(),
+        Vec3::new(-1.0, -1.0, 1.0).normalize(),
+        Vec3::new(-1.0, 1.0, -1.0).normalize(),
+        Vec3::new(1.0, 1.0, 1.0).normalize()
+    ]
+);
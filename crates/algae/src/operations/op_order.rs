@@ -1,60 +1,161 @@
 
-use std::{marker::PhantomData, any::TypeId, hash::BuildHasherDefault};
+use std::{marker::PhantomData, any::{TypeId, type_name}, rc::Rc};
 
-use fxhash::FxHashMap;
 use rspirv::spirv::Word;
 
-use crate::{BoxOperation, DataId, Operation, Serializer};
+use crate::{BoxOperation, DataId, Evaluator, Operation, SerializeError, Serializer};
+
+use super::Constant;
 
 ///A result where the type is only known at runtime.
 #[derive(Clone, Debug)]
 struct AnonymResult{
     id: Word,
     ty: TypeId,
+    ///Name of the type, used for error messages.
+    ty_name: &'static str,
+}
+
+impl AnonymResult{
+    fn new<T: 'static>(res: DataId<T>) -> Self{
+        AnonymResult{
+            id: res.id,
+            ty: TypeId::of::<T>(),
+            ty_name: type_name::<T>(),
+        }
+    }
+
+    ///Casts the result back to its type `T`. `operation` and `name` are reported if the types do not match.
+    fn typed<T: 'static>(&self, operation: &str, name: &str) -> Result<DataId<T>, SerializeError>{
+        if self.ty == TypeId::of::<T>(){
+            Ok(DataId::from(self.id))
+        }else{
+            Err(SerializeError::TypeMismatch{
+                operation: operation.to_string(),
+                name: name.to_string(),
+                expected: type_name::<T>().to_string(),
+                found: self.ty_name.to_string(),
+            })
+        }
+    }
+}
+
+///One result of a [ResultContext], and the scope it was defined in.
+#[derive(Debug)]
+struct Scope{
+    name: String,
+    ///Set if the result was defined by a [Let](super::Let), which is accessed via its [Binding](super::Binding) instead of the name.
+    binding: Option<u64>,
+    result: AnonymResult,
+    parent: Option<Rc<Scope>>,
 }
 
 ///Provides a runtime accessor for defined results based on a name.
+///
+/// The context is a chain of scopes, where each scope defines one result and shares all outer scopes with the context it was
+/// created from. Therefore cloning a context and defining a result are both O(1), and a definition is only visible to the
+/// operations that get the extended context, for instance the following steps of an [OrderedOperations] chain or the body of a
+/// [Loop](super::Loop).
+///
+/// # Shadowing
+/// A result can be defined with a name that is already in use, even with another type. Like a shadowed `let` binding in Rust the
+/// new result hides the former one for everything that uses the extended context, while the former one stays untouched for any
+/// context that does not contain the new result. Accessing a name always returns the innermost result, a shadowed result is never
+/// used as a fallback if the types do not match. Instead the access fails with [SerializeError::TypeMismatch].
 #[derive(Clone, Debug)]
 pub struct ResultContext{
-    results: FxHashMap<String, AnonymResult>,
+    innermost: Option<Rc<Scope>>,
 }
 
-impl<'a> ResultContext{
-    ///If available returns a result of type `T` with the given name.
-    fn get<T: 'static>(&self, name: &str) -> Option<DataId<T>>{
-        let tid = TypeId::of::<T>();
-        if let Some(r) = self.results.get(name){
-            //Name is the same, check that the types match.
-            if r.ty == tid{
-                Some(DataId::from(r.id))
-            }else{
-                None
+impl ResultContext{
+    pub(crate) fn new() -> Self{
+        ResultContext{
+            innermost: None
+        }
+    }
+
+    ///Returns the innermost scope for which `matches` is true, if there is any.
+    fn find_scope(&self, matches: impl Fn(&Scope) -> bool) -> Option<&Scope>{
+        let mut scope = self.innermost.as_deref();
+        while let Some(s) = scope{
+            if matches(s){
+                return Some(s);
             }
+            scope = s.parent.as_deref();
+        }
+        None
+    }
+
+    ///Returns the innermost result with the given name, if there is any.
+    fn find(&self, name: &str) -> Option<&AnonymResult>{
+        self.find_scope(|s| s.name == name).map(|s| &s.result)
+    }
+
+    ///Returns the result of the binding with the given id. `operation` is reported if the binding is not defined, which only
+    /// happens if a [Binding](super::Binding) is used outside of the body of its [Let](super::Let).
+    pub(crate) fn get_binding<T: 'static>(&self, operation: &str, binding: u64) -> Result<DataId<T>, SerializeError>{
+        match self.find_scope(|s| s.binding == Some(binding)){
+            Some(s) => s.result.typed(operation, &s.name),
+            None => Err(SerializeError::UnknownResult{
+                operation: operation.to_string(),
+                name: format!("binding #{}", binding),
+            })
+        }
+    }
+
+    ///Returns the result of type `T` with the given name. Fails if there is no such result, or if it has another type.
+    /// `operation` is the operation that accesses the result, it is reported in the error.
+    fn get<T: 'static>(&self, operation: &str, name: &str) -> Result<DataId<T>, SerializeError>{
+        if let Some(r) = self.find(name){
+            //Name is the same, check that the types match.
+            r.typed(operation, name)
         }else{
-            None
+            Err(SerializeError::UnknownResult{
+                operation: operation.to_string(),
+                name: name.to_string(),
+            })
         }
     }
 
+    ///Inserts the typed result `res` under `name`. Used by operations that provide values to their inner operations.
+    pub(crate) fn insert_result<T: 'static>(&mut self, name: impl Into<String>, res: DataId<T>){
+        self.insert(name.into(), AnonymResult::new(res));
+    }
+
+    ///Inserts the typed result `res` of the binding with the given id. `name` is the binding's debug name, the result can also be
+    /// accessed via this name.
+    pub(crate) fn insert_binding<T: 'static>(&mut self, binding: u64, name: impl Into<String>, res: DataId<T>){
+        self.push_scope(name.into(), Some(binding), AnonymResult::new(res));
+    }
+
+    ///Defines `res` in a new innermost scope, see [Shadowing](ResultContext#shadowing).
     fn insert(&mut self, name: String, res: AnonymResult){
+        self.push_scope(name, None, res);
+    }
+
+    fn push_scope(&mut self, name: String, binding: Option<u64>, res: AnonymResult){
         #[cfg(feature="logging")]
-        let lname = name.clone();
-        
-        if let Some(_old) = self.results.insert(name, res){
-            #[cfg(feature="logging")]
-            log::warn!("result with name {} existed in context. Overwriting...", lname);
-            //TODO/FIXME: Currently this will just silently pass. If the types of the old value
-            //            At that name and the new values do not match, this can lead to panics, or
-            //            unexpected behaviour.
-            //            With checks enabled however this could be a mechanic to allow semi
-            //            mutable values via overwrite.
+        if let Some(old) = self.find(&name){
+            if old.ty != res.ty{
+                log::warn!("result {} of type {} shadows result of type {}", name, res.ty_name, old.ty_name);
+            }
         }
+
+        let parent = self.innermost.take();
+        self.innermost = Some(Rc::new(Scope{
+            name,
+            binding,
+            result: res,
+            parent,
+        }));
     }
 }
 
 ///Operation that allows JIT-Compiletime access to a intermediate result of an [ResultContext] of type `T` and a given name.
 ///
-/// # Safety
-/// Panics if the result can't be accessed. Use [AccessOrDefault] to return a default value instead if the result does not exist.
+/// # Errors
+/// Serializing fails with [SerializeError::UnknownResult] if there is no result with that name, and with [SerializeError::TypeMismatch]
+/// if the result is not of type `T`. Evaluating panics in both cases. Use [AccessOrDefault] to return a default value instead if the result does not exist.
 pub struct AccessResult<T>{
     name: String,
     ty: PhantomData<T>
@@ -69,13 +170,81 @@ impl<T> AccessResult<T>{
     }
 }
 
-//FIXME: The `ResultContext` should be passed by reference.
 impl<T: 'static> Operation for AccessResult<T>{
     type Input = ResultContext;
     type Output = DataId<T>;
 
-    fn serialize(&mut self, _serializer: &mut Serializer, input: Self::Input) -> Self::Output {
-        input.get(&self.name).expect(&format!("Expected result with name {}", self.name))
+    fn serialize(&mut self, _serializer: &mut Serializer, input: Self::Input) -> Result<Self::Output, SerializeError> {
+        input.get(type_name::<Self>(), &self.name)
+    }
+
+    fn evaluate(&mut self, _evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        input.get(type_name::<Self>(), &self.name).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+///Like [AccessResult], but returns `default` if there is no result with the given name. Useful for operations that can be used
+/// within several contexts, where only some of them provide the result.
+///
+/// # Errors
+/// Serializing still fails with [SerializeError::TypeMismatch] if the result exists, but is not of type `T`. Evaluating panics in
+/// that case.
+pub struct AccessOrDefault<T>{
+    name: String,
+    default: Constant<ResultContext, T>,
+}
+
+impl<T: 'static> AccessOrDefault<T>{
+    pub fn new(name: impl Into<String>, default: T) -> Self{
+        AccessOrDefault{
+            name: name.into(),
+            default: Constant::new(default),
+        }
+    }
+}
+
+impl<T: 'static> Operation for AccessOrDefault<T>
+where
+    Constant<ResultContext, T>: Operation<Input = ResultContext, Output = DataId<T>>,
+{
+    type Input = ResultContext;
+    type Output = DataId<T>;
+
+    fn serialize(&mut self, serializer: &mut Serializer, input: Self::Input) -> Result<Self::Output, SerializeError> {
+        match input.find(&self.name){
+            Some(r) => r.typed(type_name::<Self>(), &self.name),
+            None => self.default.serialize(serializer, input),
+        }
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        match input.find(&self.name){
+            Some(r) => r.typed(type_name::<Self>(), &self.name).unwrap_or_else(|e| panic!("{}", e)),
+            None => self.default.evaluate(evaluator, input),
+        }
+    }
+}
+
+///Operation whose output type is only known at runtime. Used to store differently typed operations in one [OrderedOperations] chain.
+trait AnonymOperation{
+    fn serialize(&mut self, serializer: &mut Serializer, ctx: ResultContext) -> Result<AnonymResult, SerializeError>;
+    fn evaluate(&mut self, evaluator: &mut Evaluator, ctx: ResultContext) -> AnonymResult;
+}
+
+///Masks the typed inner operation by wrapping its result into an [AnonymResult].
+struct Anonymized<R>{
+    inner: BoxOperation<ResultContext, R>,
+}
+
+impl<R: 'static> AnonymOperation for Anonymized<R>{
+    fn serialize(&mut self, serializer: &mut Serializer, ctx: ResultContext) -> Result<AnonymResult, SerializeError>{
+        let typed_res = self.inner.serialize(serializer, ctx)?;
+        Ok(AnonymResult::new(typed_res))
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, ctx: ResultContext) -> AnonymResult{
+        let typed_res = self.inner.evaluate(evaluator, ctx);
+        AnonymResult::new(typed_res)
     }
 }
 
@@ -88,9 +257,12 @@ impl<T: 'static> Operation for AccessResult<T>{
 /// the supplied super context. This happens for instance if a OrderedOperation is called within a OrderedOperation.
 ///
 /// Returns the result of the last operation
+///
+/// Results are accessed by name and type, which are only checked when the chain is serialized. Use [Let](super::Let) to access
+/// results via typed handles instead.
 pub struct OrderedOperations<I, O>{
     //order of operations string is the name that is used for accessing the result.
-    operations: Vec<(String, Box<dyn FnMut(&mut Serializer, ResultContext) -> AnonymResult + 'static>)>,
+    operations: Vec<(String, Box<dyn AnonymOperation>)>,
     input: PhantomData<I>,    
     output: PhantomData<O>,
 }
@@ -117,18 +289,8 @@ impl<I: 'static, O: 'static> OrderedOperations<I, O>{
         
         let OrderedOperations { input, mut operations, output: _ } = self;
         
-        let anym_function: Box<dyn FnMut(&mut Serializer, ResultContext) -> AnonymResult + 'static> = Box::new({
-            let mut innerop = op;
-            move |serialized, ctx|{
-                //Mask the inner operation by warapping it into the anonym map.
-                let typed_res = innerop.serialize(serialized, ctx);
-                AnonymResult{
-                    id: typed_res.id,
-                    ty: TypeId::of::<R>(),
-                }
-            }
-        });
-        operations.push((name, anym_function));
+        //Mask the inner operation by warapping it into the anonym map.
+        operations.push((name, Box::new(Anonymized{inner: op})));
         
         OrderedOperations{
             input,
@@ -144,25 +306,37 @@ impl<O: 'static> Operation for OrderedOperations<ResultContext, O>{
     type Input = ResultContext;
     type Output = DataId<O>;
 
-    fn serialize(&mut self, serializer: &mut Serializer, input: Self::Input) -> Self::Output {
+    fn serialize(&mut self, serializer: &mut Serializer, input: Self::Input) -> Result<Self::Output, SerializeError> {
         let mut context = input;
 
         let mut last_result = None;
         //Now serialize each operation with context
         for (opname, op) in self.operations.iter_mut(){
-            //FIXME: hashmap clone should not be 
-            let res = op(serializer, context.clone());
+            //Cloning only shares the scopes, see ResultContext.
+            let res = op.serialize(serializer, context.clone())?;
             //update last known result id
-            last_result = Some(res.clone());
+            last_result = Some((opname.clone(), res.clone()));
             //Push the new runtime result id into the context
             context.insert(opname.clone(), res);
         }
 
-        //assert that the type id is correct for sanity purposes
-        let result = last_result.unwrap();
-        assert!(TypeId::of::<O>() == result.ty, "result type Id did not match");
-        //Should be save to cast to actual dataid
-        DataId::from(result.id)
+        //check that the type is correct for sanity purposes, `new` ensures that there is at least one operation.
+        let (name, result) = last_result.expect("OrderedOperations without operations");
+        result.typed(type_name::<Self>(), &name)
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        let mut context = input;
+
+        let mut last_result = None;
+        for (opname, op) in self.operations.iter_mut(){
+            let res = op.evaluate(evaluator, context.clone());
+            last_result = Some((opname.clone(), res.clone()));
+            context.insert(opname.clone(), res);
+        }
+
+        let (name, result) = last_result.expect("OrderedOperations without operations");
+        result.typed(type_name::<Self>(), &name).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
@@ -171,12 +345,28 @@ impl<O: 'static> Operation for OrderedOperations<(), O>{
     type Input = ();
     type Output = DataId<O>;
 
-    fn serialize(&mut self, serializer: &mut Serializer, input: Self::Input) -> Self::Output {
+    fn serialize(&mut self, serializer: &mut Serializer, _input: Self::Input) -> Result<Self::Output, SerializeError> {
         //Create a local context and use the inheriting implementation
-        let context = ResultContext{
-            results: FxHashMap::with_capacity_and_hasher(2, BuildHasherDefault::default())
-        };
+        let mut metaop = self.as_inheriting();
+        let result = metaop.serialize(serializer, ResultContext::new());
+        //Swap back before propagating a possible error, otherwise the chain would be left empty.
+        core::mem::swap(&mut self.operations, &mut metaop.operations);
+        
+        result
+    }
 
+    fn evaluate(&mut self, evaluator: &mut Evaluator, _input: Self::Input) -> Self::Output {
+        let mut metaop = self.as_inheriting();
+        let result = metaop.evaluate(evaluator, ResultContext::new());
+        core::mem::swap(&mut self.operations, &mut metaop.operations);
+        
+        result
+    }
+}
+
+impl<O: 'static> OrderedOperations<(), O>{
+    ///Temporarly moves all operations into an inheriting chain. The caller has to swap them back after use.
+    fn as_inheriting(&mut self) -> OrderedOperations<ResultContext, O>{
         let mut metaop: OrderedOperations<ResultContext, O> = OrderedOperations{
             input: PhantomData,
             output: PhantomData,
@@ -185,12 +375,6 @@ impl<O: 'static> Operation for OrderedOperations<(), O>{
 
         //Swap ops
         core::mem::swap(&mut self.operations, &mut metaop.operations);
-        
-        let result = metaop.serialize(serializer, context);
-
-        //Swap back
-        core::mem::swap(&mut self.operations, &mut metaop.operations);
-        
-        result
+        metaop
     }
 }
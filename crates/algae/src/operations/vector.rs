@@ -1,10 +1,11 @@
+use std::marker::PhantomData;
+
 use glam::{Vec2, Vec3, Vec4};
 use rspirv::dr::Operand;
 
 use crate::{
     operations::{Abs, Max, Min},
-    spv_fi::IntoSpvType,
-    BoxOperation, DataId, Operation,
+    BoxOperation, DataId, Evaluator, Operation,
 };
 
 ///Normalizes the `inner` vector. I.e. makes it the length 1.
@@ -22,20 +23,27 @@ macro_rules! impl_normalize {
                 &mut self,
                 serializer: &mut crate::Serializer,
                 input: Self::Input,
-            ) -> Self::Output {
+            ) -> Result<Self::Output, crate::SerializeError> {
                 //Uses the extended instructionset to get the length of an vector
-                let res = self.inner.serialize(serializer, input);
-                let tv = <$vecty>::spirv_type_id(serializer).unwrap();
+                let res = self.inner.serialize(serializer, input)?;
+                let tv = serializer.type_id::<$vecty, Self>()?;
 
                 //Load instructionset
-                let ext_instset_id = serializer.builder_mut().ext_inst_import("GLSL.std.450");
+                let ext_instset_id = serializer.glsl_std_450();
                 //Call
-                DataId::from(
+                Ok(DataId::from(
                     serializer
-                        .builder_mut()
-                        .ext_inst(tv, None, ext_instset_id, 69, [Operand::IdRef(res.id)])
-                        .unwrap(),
-                )
+                        .value(|builder| {
+                            builder.ext_inst(tv, None, ext_instset_id, 69, [Operand::IdRef(res.id)])
+                        })
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let ra = self.inner.evaluate(evaluator, input);
+                let res = evaluator.load(ra).normalize();
+                evaluator.store(res)
             }
         }
     };
@@ -61,20 +69,33 @@ macro_rules! impl_length {
                 &mut self,
                 serializer: &mut crate::Serializer,
                 input: Self::Input,
-            ) -> Self::Output {
+            ) -> Result<Self::Output, crate::SerializeError> {
                 //Uses the extended instructionset to get the length of an vector
-                let res = self.inner.serialize(serializer, input);
-                let tf32 = f32::spirv_type_id(serializer).unwrap();
+                let res = self.inner.serialize(serializer, input)?;
+                let tf32 = serializer.type_id::<f32, Self>()?;
 
                 //Load instructionset
-                let ext_instset_id = serializer.builder_mut().ext_inst_import("GLSL.std.450");
+                let ext_instset_id = serializer.glsl_std_450();
                 //Call
-                DataId::from(
+                Ok(DataId::from(
                     serializer
-                        .builder_mut()
-                        .ext_inst(tf32, None, ext_instset_id, 66, [Operand::IdRef(res.id)])
-                        .unwrap(),
-                )
+                        .value(|builder| {
+                            builder.ext_inst(
+                                tf32,
+                                None,
+                                ext_instset_id,
+                                66,
+                                [Operand::IdRef(res.id)],
+                            )
+                        })
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let ra = self.inner.evaluate(evaluator, input);
+                let res = evaluator.load(ra).length();
+                evaluator.store(res)
             }
         }
     };
@@ -84,15 +105,60 @@ impl_length!(Vec2);
 impl_length!(Vec3);
 impl_length!(Vec4);
 
-///Returns the [cross product](https://en.wikipedia.org/wiki/Cross_product) between two vectors of the same type.
-pub struct Cross<V, I> {
+///Returns the [cross product](https://en.wikipedia.org/wiki/Cross_product) between two 3D vectors.
+pub struct Cross<I> {
+    pub a: BoxOperation<I, Vec3>,
+    pub b: BoxOperation<I, Vec3>,
+}
+
+impl<I: Clone> Operation for Cross<I> {
+    type Input = I;
+    type Output = DataId<Vec3>;
+
+    fn serialize(
+        &mut self,
+        serializer: &mut crate::Serializer,
+        input: Self::Input,
+    ) -> Result<Self::Output, crate::SerializeError> {
+        let ra = self.a.serialize(serializer, input.clone())?;
+        let rb = self.b.serialize(serializer, input)?;
+        let tvec = serializer.type_id::<Vec3, Self>()?;
+
+        //Load instructionset
+        let ext_instset_id = serializer.glsl_std_450();
+        //Call
+        Ok(DataId::from(
+            serializer
+                .value(|builder| {
+                    builder.ext_inst(
+                        tvec,
+                        None,
+                        ext_instset_id,
+                        68,
+                        [Operand::IdRef(ra.id), Operand::IdRef(rb.id)],
+                    )
+                })
+                .map_err(crate::SerializeError::builder::<Self>)?,
+        ))
+    }
+
+    fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+        let ra = self.a.evaluate(evaluator, input.clone());
+        let rb = self.b.evaluate(evaluator, input);
+        let res = evaluator.load(ra).cross(evaluator.load(rb));
+        evaluator.store(res)
+    }
+}
+
+///Returns the [dot product](https://en.wikipedia.org/wiki/Dot_product) of two vectors of the same type.
+pub struct Dot<V, I> {
     pub a: BoxOperation<I, V>,
     pub b: BoxOperation<I, V>,
 }
 
-macro_rules! impl_cross {
+macro_rules! impl_dot {
     ($vecty:ty) => {
-        impl<I: Clone> Operation for Cross<$vecty, I> {
+        impl<I: Clone> Operation for Dot<$vecty, I> {
             type Input = I;
             type Output = DataId<f32>;
 
@@ -100,39 +166,281 @@ macro_rules! impl_cross {
                 &mut self,
                 serializer: &mut crate::Serializer,
                 input: Self::Input,
-            ) -> Self::Output {
-                //Uses the extended instructionset to get the length of an vector
-                let ra = self.a.serialize(serializer, input.clone());
-                let rb = self.b.serialize(serializer, input);
-                let tvec = <$vecty>::spirv_type_id(serializer).unwrap();
+            ) -> Result<Self::Output, crate::SerializeError> {
+                let ra = self.a.serialize(serializer, input.clone())?;
+                let rb = self.b.serialize(serializer, input)?;
+                let tf32 = serializer.type_id::<f32, Self>()?;
+                Ok(DataId::from(
+                    serializer
+                        .value(|builder| builder.dot(tf32, None, ra.id, rb.id))
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let ra = self.a.evaluate(evaluator, input.clone());
+                let rb = self.b.evaluate(evaluator, input);
+                let res = evaluator.load(ra).dot(evaluator.load(rb));
+                evaluator.store(res)
+            }
+        }
+    };
+}
+
+impl_dot!(Vec2);
+impl_dot!(Vec3);
+impl_dot!(Vec4);
+
+///Returns the euclidian distance between the two points `a` and `b`.
+pub struct Distance<V, I> {
+    pub a: BoxOperation<I, V>,
+    pub b: BoxOperation<I, V>,
+}
+
+macro_rules! impl_distance {
+    ($vecty:ty) => {
+        impl<I: Clone> Operation for Distance<$vecty, I> {
+            type Input = I;
+            type Output = DataId<f32>;
+
+            fn serialize(
+                &mut self,
+                serializer: &mut crate::Serializer,
+                input: Self::Input,
+            ) -> Result<Self::Output, crate::SerializeError> {
+                let ra = self.a.serialize(serializer, input.clone())?;
+                let rb = self.b.serialize(serializer, input)?;
+                let tf32 = serializer.type_id::<f32, Self>()?;
+
+                //Load instructionset
+                let ext_instset_id = serializer.glsl_std_450();
+                //Call
+                Ok(DataId::from(
+                    serializer
+                        .value(|builder| {
+                            builder.ext_inst(
+                                tf32,
+                                None,
+                                ext_instset_id,
+                                67,
+                                [Operand::IdRef(ra.id), Operand::IdRef(rb.id)],
+                            )
+                        })
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let ra = self.a.evaluate(evaluator, input.clone());
+                let rb = self.b.evaluate(evaluator, input);
+                let res = evaluator.load(ra).distance(evaluator.load(rb));
+                evaluator.store(res)
+            }
+        }
+    };
+}
+
+impl_distance!(Vec2);
+impl_distance!(Vec3);
+impl_distance!(Vec4);
+
+///Reflects the `incident` vector at the plane defined by `normal`: `result = incident - 2 * dot(normal, incident) * normal`.
+///
+/// `normal` should be normalized.
+pub struct Reflect<V, I> {
+    pub incident: BoxOperation<I, V>,
+    pub normal: BoxOperation<I, V>,
+}
+
+macro_rules! impl_reflect {
+    ($vecty:ty) => {
+        impl<I: Clone> Operation for Reflect<$vecty, I> {
+            type Input = I;
+            type Output = DataId<$vecty>;
+
+            fn serialize(
+                &mut self,
+                serializer: &mut crate::Serializer,
+                input: Self::Input,
+            ) -> Result<Self::Output, crate::SerializeError> {
+                let ri = self.incident.serialize(serializer, input.clone())?;
+                let rn = self.normal.serialize(serializer, input)?;
+                let tvec = serializer.type_id::<$vecty, Self>()?;
 
                 //Load instructionset
-                let ext_instset_id = serializer.builder_mut().ext_inst_import("GLSL.std.450");
+                let ext_instset_id = serializer.glsl_std_450();
                 //Call
-                DataId::from(
+                Ok(DataId::from(
                     serializer
-                        .builder_mut()
-                        .ext_inst(
-                            tvec,
-                            None,
-                            ext_instset_id,
-                            68,
-                            [Operand::IdRef(ra.id), Operand::IdRef(rb.id)],
-                        )
-                        .unwrap(),
-                )
+                        .value(|builder| {
+                            builder.ext_inst(
+                                tvec,
+                                None,
+                                ext_instset_id,
+                                71,
+                                [Operand::IdRef(ri.id), Operand::IdRef(rn.id)],
+                            )
+                        })
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let ri = self.incident.evaluate(evaluator, input.clone());
+                let rn = self.normal.evaluate(evaluator, input);
+                let (i, n) = (evaluator.load(ri), evaluator.load(rn));
+                let res = i - 2.0 * n.dot(i) * n;
+                evaluator.store(res)
             }
         }
     };
 }
 
-impl_cross!(Vec2);
-impl_cross!(Vec3);
-impl_cross!(Vec4);
+impl_reflect!(Vec2);
+impl_reflect!(Vec3);
+impl_reflect!(Vec4);
+
+///Refracts the `incident` vector at the surface defined by `normal`, where `eta` is the ratio of the indices of refraction.
+/// Returns a zero vector in case of total internal reflection.
+///
+/// Both, `incident` and `normal` should be normalized.
+pub struct Refract<V, I> {
+    pub incident: BoxOperation<I, V>,
+    pub normal: BoxOperation<I, V>,
+    pub eta: BoxOperation<I, f32>,
+}
+
+macro_rules! impl_refract {
+    ($vecty:ty) => {
+        impl<I: Clone> Operation for Refract<$vecty, I> {
+            type Input = I;
+            type Output = DataId<$vecty>;
+
+            fn serialize(
+                &mut self,
+                serializer: &mut crate::Serializer,
+                input: Self::Input,
+            ) -> Result<Self::Output, crate::SerializeError> {
+                let ri = self.incident.serialize(serializer, input.clone())?;
+                let rn = self.normal.serialize(serializer, input.clone())?;
+                let reta = self.eta.serialize(serializer, input)?;
+                let tvec = serializer.type_id::<$vecty, Self>()?;
+
+                //Load instructionset
+                let ext_instset_id = serializer.glsl_std_450();
+                //Call
+                Ok(DataId::from(
+                    serializer
+                        .value(|builder| {
+                            builder.ext_inst(
+                                tvec,
+                                None,
+                                ext_instset_id,
+                                72,
+                                [
+                                    Operand::IdRef(ri.id),
+                                    Operand::IdRef(rn.id),
+                                    Operand::IdRef(reta.id),
+                                ],
+                            )
+                        })
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let ri = self.incident.evaluate(evaluator, input.clone());
+                let rn = self.normal.evaluate(evaluator, input.clone());
+                let reta = self.eta.evaluate(evaluator, input);
+                let (i, n, eta) = (evaluator.load(ri), evaluator.load(rn), evaluator.load(reta));
+
+                //Same as GLSL's refract
+                let ndi = n.dot(i);
+                let k = 1.0 - eta * eta * (1.0 - ndi * ndi);
+                let res = if k < 0.0 {
+                    <$vecty>::ZERO
+                } else {
+                    eta * i - (eta * ndi + k.sqrt()) * n
+                };
+                evaluator.store(res)
+            }
+        }
+    };
+}
+
+impl_refract!(Vec2);
+impl_refract!(Vec3);
+impl_refract!(Vec4);
+
+///Returns `normal` if `dot(reference, incident) < 0`, otherwise `-normal`.
+pub struct FaceForward<V, I> {
+    pub normal: BoxOperation<I, V>,
+    pub incident: BoxOperation<I, V>,
+    pub reference: BoxOperation<I, V>,
+}
+
+macro_rules! impl_face_forward {
+    ($vecty:ty) => {
+        impl<I: Clone> Operation for FaceForward<$vecty, I> {
+            type Input = I;
+            type Output = DataId<$vecty>;
+
+            fn serialize(
+                &mut self,
+                serializer: &mut crate::Serializer,
+                input: Self::Input,
+            ) -> Result<Self::Output, crate::SerializeError> {
+                let rn = self.normal.serialize(serializer, input.clone())?;
+                let ri = self.incident.serialize(serializer, input.clone())?;
+                let rref = self.reference.serialize(serializer, input)?;
+                let tvec = serializer.type_id::<$vecty, Self>()?;
+
+                //Load instructionset
+                let ext_instset_id = serializer.glsl_std_450();
+                //Call
+                Ok(DataId::from(
+                    serializer
+                        .value(|builder| {
+                            builder.ext_inst(
+                                tvec,
+                                None,
+                                ext_instset_id,
+                                70,
+                                [
+                                    Operand::IdRef(rn.id),
+                                    Operand::IdRef(ri.id),
+                                    Operand::IdRef(rref.id),
+                                ],
+                            )
+                        })
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let rn = self.normal.evaluate(evaluator, input.clone());
+                let ri = self.incident.evaluate(evaluator, input.clone());
+                let rref = self.reference.evaluate(evaluator, input);
+                let n = evaluator.load(rn);
+                let res = if evaluator.load(rref).dot(evaluator.load(ri)) < 0.0 {
+                    n
+                } else {
+                    -n
+                };
+                evaluator.store(res)
+            }
+        }
+    };
+}
+
+impl_face_forward!(Vec2);
+impl_face_forward!(Vec3);
+impl_face_forward!(Vec4);
 
 ///Selects the `element` of the vector.
 ///
-/// Note that `element` must be within the number of elements of the concrete vector `V`
+/// Note that `element` must be within the number of elements of the concrete vector `V`, otherwise serializing fails with
+/// [SerializeError::ElementOutOfBounds](crate::SerializeError::ElementOutOfBounds).
 pub struct VecSelectElement<V, I> {
     pub element: u32,
     pub inner: Box<dyn Operation<Input = I, Output = DataId<V>>>,
@@ -148,23 +456,37 @@ macro_rules! impl_vec_select {
                 &mut self,
                 serializer: &mut crate::Serializer,
                 input: Self::Input,
-            ) -> Self::Output {
+            ) -> Result<Self::Output, crate::SerializeError> {
+                if self.element >= $num_comp {
+                    return Err(crate::SerializeError::ElementOutOfBounds {
+                        operation: std::any::type_name::<Self>().to_string(),
+                        element: self.element,
+                        elements: $num_comp,
+                    });
+                }
+                let tyf32 = serializer.type_id::<f32, Self>()?;
+
+                let vector_return = self.inner.serialize(serializer, input)?;
+
+                Ok(DataId::from(
+                    serializer
+                        .value(|builder| {
+                            builder.composite_extract(tyf32, None, vector_return.id, [self.element])
+                        })
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
                 assert!(
                     self.element < $num_comp,
                     "Tried to select element {}, but vector is of length {}",
                     self.element,
                     $num_comp
                 );
-                let tyf32 = f32::spirv_type_id(serializer).unwrap();
-
-                let vector_return = self.inner.serialize(serializer, input);
-
-                DataId::from(
-                    serializer
-                        .builder_mut()
-                        .composite_extract(tyf32, None, vector_return.id, [self.element])
-                        .unwrap(),
-                )
+                let vector_return = self.inner.evaluate(evaluator, input);
+                let res = evaluator.load(vector_return)[self.element as usize];
+                evaluator.store(res)
             }
         }
     };
@@ -174,6 +496,231 @@ impl_vec_select!(Vec2, 2);
 impl_vec_select!(Vec3, 3);
 impl_vec_select!(Vec4, 4);
 
+///Constructs a vector `V` from one scalar operation per component.
+///
+/// Note that the number of `elements` must match the number of components of the concrete vector `V`.
+pub struct VecConstruct<V, I> {
+    pub elements: Vec<BoxOperation<I, f32>>,
+    pub ty: PhantomData<V>,
+}
+
+impl<V, I> VecConstruct<V, I> {
+    pub fn new(elements: Vec<BoxOperation<I, f32>>) -> Self {
+        VecConstruct {
+            elements,
+            ty: PhantomData,
+        }
+    }
+}
+
+macro_rules! impl_vec_construct {
+    ($vecty:ty, $num_comp:expr) => {
+        impl<I: Clone> Operation for VecConstruct<$vecty, I> {
+            type Input = I;
+            type Output = DataId<$vecty>;
+
+            fn serialize(
+                &mut self,
+                serializer: &mut crate::Serializer,
+                input: Self::Input,
+            ) -> Result<Self::Output, crate::SerializeError> {
+                if self.elements.len() != $num_comp {
+                    return Err(crate::SerializeError::ElementCountMismatch {
+                        operation: std::any::type_name::<Self>().to_string(),
+                        expected: $num_comp,
+                        found: self.elements.len(),
+                    });
+                }
+                let mut ids = [0; $num_comp];
+                for (id, element) in ids.iter_mut().zip(self.elements.iter_mut()) {
+                    *id = element.serialize(serializer, input.clone())?.id;
+                }
+                let tvec = serializer.type_id::<$vecty, Self>()?;
+                Ok(DataId::from(
+                    serializer
+                        .value(|builder| builder.composite_construct(tvec, None, ids))
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                assert!(
+                    self.elements.len() == $num_comp,
+                    "Tried to construct vector of length {} from {} elements",
+                    $num_comp,
+                    self.elements.len()
+                );
+                let mut values = [0.0; $num_comp];
+                for (value, element) in values.iter_mut().zip(self.elements.iter_mut()) {
+                    let res = element.evaluate(evaluator, input.clone());
+                    *value = evaluator.load(res);
+                }
+                evaluator.store(<$vecty>::from(values))
+            }
+        }
+    };
+}
+
+impl_vec_construct!(Vec2, 2);
+impl_vec_construct!(Vec3, 3);
+impl_vec_construct!(Vec4, 4);
+
+///Constructs a vector `V` where each component is set to the `inner` scalar.
+pub struct Splat<V, I> {
+    pub inner: BoxOperation<I, f32>,
+    pub ty: PhantomData<V>,
+}
+
+impl<V, I> Splat<V, I> {
+    pub fn new(inner: BoxOperation<I, f32>) -> Self {
+        Splat {
+            inner,
+            ty: PhantomData,
+        }
+    }
+}
+
+macro_rules! impl_splat {
+    ($vecty:ty, $num_comp:expr) => {
+        impl<I> Operation for Splat<$vecty, I> {
+            type Input = I;
+            type Output = DataId<$vecty>;
+
+            fn serialize(
+                &mut self,
+                serializer: &mut crate::Serializer,
+                input: Self::Input,
+            ) -> Result<Self::Output, crate::SerializeError> {
+                let res = self.inner.serialize(serializer, input)?;
+                let tvec = serializer.type_id::<$vecty, Self>()?;
+                Ok(DataId::from(
+                    serializer
+                        .value(|builder| {
+                            builder.composite_construct(tvec, None, [res.id; $num_comp])
+                        })
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let res = self.inner.evaluate(evaluator, input);
+                let res = <$vecty>::splat(evaluator.load(res));
+                evaluator.store(res)
+            }
+        }
+    };
+}
+
+impl_splat!(Vec2, 2);
+impl_splat!(Vec3, 3);
+impl_splat!(Vec4, 4);
+
+///Rearranges the components of the `inner` vector `V` into a new vector `S`, like GLSL's `p.xz` or `p.zyx`.
+///
+/// Each entry of `components` selects the component of `V` that is written to the same position of `S`. Therefore
+/// the number of `components` must match the number of components of `S`.
+pub struct Swizzle<V, S, I> {
+    pub components: Vec<u32>,
+    pub inner: BoxOperation<I, V>,
+    pub ty: PhantomData<S>,
+}
+
+impl<V, S, I> Swizzle<V, S, I> {
+    ///Creates the swizzle from a GLSL like `pattern` made of `x`, `y`, `z` and `w`. For instance `"zyx"` reverses a `Vec3`.
+    ///
+    /// # Panics
+    /// if `pattern` contains any other character.
+    pub fn new(pattern: &str, inner: BoxOperation<I, V>) -> Self {
+        let components = pattern
+            .chars()
+            .map(|c| match c {
+                'x' => 0,
+                'y' => 1,
+                'z' => 2,
+                'w' => 3,
+                _ => panic!("Invalid swizzle component {} in {}", c, pattern),
+            })
+            .collect();
+
+        Swizzle {
+            components,
+            inner,
+            ty: PhantomData,
+        }
+    }
+}
+
+macro_rules! impl_swizzle {
+    ($vecty:ty, $num_comp:expr; $($swizzlety:ty, $num_swizzle:expr);+) => {
+        $(
+            impl<I> Operation for Swizzle<$vecty, $swizzlety, I> {
+                type Input = I;
+                type Output = DataId<$swizzlety>;
+
+                fn serialize(
+                    &mut self,
+                    serializer: &mut crate::Serializer,
+                    input: Self::Input,
+                ) -> Result<Self::Output, crate::SerializeError> {
+                    let operation = || std::any::type_name::<Self>().to_string();
+                    if self.components.len() != $num_swizzle {
+                        return Err(crate::SerializeError::ElementCountMismatch {
+                            operation: operation(),
+                            expected: $num_swizzle,
+                            found: self.components.len(),
+                        });
+                    }
+                    if let Some(component) = self.components.iter().find(|c| **c >= $num_comp) {
+                        return Err(crate::SerializeError::ElementOutOfBounds {
+                            operation: operation(),
+                            element: *component,
+                            elements: $num_comp,
+                        });
+                    }
+                    let res = self.inner.serialize(serializer, input)?;
+                    let tvec = serializer.type_id::<$swizzlety, Self>()?;
+                    //Both vector operands are the same, therefore only the first one's components are addressed.
+                    Ok(DataId::from(
+                        serializer
+                            .value(|builder| {
+                                builder.vector_shuffle(
+                                    tvec,
+                                    None,
+                                    res.id,
+                                    res.id,
+                                    self.components.iter().copied(),
+                                )
+                            })
+                            .map_err(crate::SerializeError::builder::<Self>)?,
+                    ))
+                }
+
+                fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                    assert!(
+                        self.components.len() == $num_swizzle
+                            && self.components.iter().all(|c| *c < $num_comp),
+                        "Swizzle {:?} is invalid for {} to {}",
+                        self.components,
+                        stringify!($vecty),
+                        stringify!($swizzlety)
+                    );
+                    let res = self.inner.evaluate(evaluator, input);
+                    let vector = evaluator.load(res);
+                    let mut values = [0.0; $num_swizzle];
+                    for (value, component) in values.iter_mut().zip(self.components.iter()) {
+                        *value = vector[*component as usize];
+                    }
+                    evaluator.store(<$swizzlety>::from(values))
+                }
+            }
+        )+
+    };
+}
+
+impl_swizzle!(Vec2, 2; Vec2, 2; Vec3, 3; Vec4, 4);
+impl_swizzle!(Vec3, 3; Vec2, 2; Vec3, 3; Vec4, 4);
+impl_swizzle!(Vec4, 4; Vec2, 2; Vec3, 3; Vec4, 4);
+
 macro_rules! impl_vec_fabs {
     ($vecty:ty) => {
         ///Uses the extended instruction set to implement abs via the `FAbs` instruction for [$vecty](glam::<$vecty>).
@@ -185,20 +732,27 @@ macro_rules! impl_vec_fabs {
                 &mut self,
                 serializer: &mut crate::Serializer,
                 input: Self::Input,
-            ) -> Self::Output {
+            ) -> Result<Self::Output, crate::SerializeError> {
                 //Uses the extended instructionset to get the length of an vector
-                let res = self.inner.serialize(serializer, input);
-                let tv = <$vecty>::spirv_type_id(serializer).unwrap();
+                let res = self.inner.serialize(serializer, input)?;
+                let tv = serializer.type_id::<$vecty, Self>()?;
                 //Load extended instruction set
-                let ext_instset_id = serializer.builder_mut().ext_inst_import("GLSL.std.450");
+                let ext_instset_id = serializer.glsl_std_450();
 
                 //Now execute the sinus function
-                DataId::from(
+                Ok(DataId::from(
                     serializer
-                        .builder_mut()
-                        .ext_inst(tv, None, ext_instset_id, 4, [Operand::IdRef(res.id)])
-                        .unwrap(),
-                )
+                        .value(|builder| {
+                            builder.ext_inst(tv, None, ext_instset_id, 4, [Operand::IdRef(res.id)])
+                        })
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let ra = self.inner.evaluate(evaluator, input);
+                let res = evaluator.load(ra).abs();
+                evaluator.store(res)
             }
         }
     };
@@ -218,27 +772,35 @@ macro_rules! impl_maxf {
                 &mut self,
                 serializer: &mut crate::Serializer,
                 input: Self::Input,
-            ) -> Self::Output {
+            ) -> Result<Self::Output, crate::SerializeError> {
                 //Uses the extended instructionset to get the length of an vector
-                let ra = self.a.serialize(serializer, input.clone());
-                let rb = self.b.serialize(serializer, input);
-                let tvec = <$vecty>::spirv_type_id(serializer).unwrap();
+                let ra = self.a.serialize(serializer, input.clone())?;
+                let rb = self.b.serialize(serializer, input)?;
+                let tvec = serializer.type_id::<$vecty, Self>()?;
 
                 //Load instructionset
-                let ext_instset_id = serializer.builder_mut().ext_inst_import("GLSL.std.450");
+                let ext_instset_id = serializer.glsl_std_450();
                 //Call
-                DataId::from(
+                Ok(DataId::from(
                     serializer
-                        .builder_mut()
-                        .ext_inst(
-                            tvec,
-                            None,
-                            ext_instset_id,
-                            40,
-                            [Operand::IdRef(ra.id), Operand::IdRef(rb.id)],
-                        )
-                        .unwrap(),
-                )
+                        .value(|builder| {
+                            builder.ext_inst(
+                                tvec,
+                                None,
+                                ext_instset_id,
+                                40,
+                                [Operand::IdRef(ra.id), Operand::IdRef(rb.id)],
+                            )
+                        })
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let ra = self.a.evaluate(evaluator, input.clone());
+                let rb = self.b.evaluate(evaluator, input);
+                let res = evaluator.load(ra).max(evaluator.load(rb));
+                evaluator.store(res)
             }
         }
     };
@@ -258,27 +820,35 @@ macro_rules! impl_minf {
                 &mut self,
                 serializer: &mut crate::Serializer,
                 input: Self::Input,
-            ) -> Self::Output {
+            ) -> Result<Self::Output, crate::SerializeError> {
                 //Uses the extended instructionset to get the length of an vector
-                let ra = self.a.serialize(serializer, input.clone());
-                let rb = self.b.serialize(serializer, input);
-                let tvec = <$vecty>::spirv_type_id(serializer).unwrap();
+                let ra = self.a.serialize(serializer, input.clone())?;
+                let rb = self.b.serialize(serializer, input)?;
+                let tvec = serializer.type_id::<$vecty, Self>()?;
 
                 //Load instructionset
-                let ext_instset_id = serializer.builder_mut().ext_inst_import("GLSL.std.450");
+                let ext_instset_id = serializer.glsl_std_450();
                 //Call
-                DataId::from(
+                Ok(DataId::from(
                     serializer
-                        .builder_mut()
-                        .ext_inst(
-                            tvec,
-                            None,
-                            ext_instset_id,
-                            37,
-                            [Operand::IdRef(ra.id), Operand::IdRef(rb.id)],
-                        )
-                        .unwrap(),
-                )
+                        .value(|builder| {
+                            builder.ext_inst(
+                                tvec,
+                                None,
+                                ext_instset_id,
+                                37,
+                                [Operand::IdRef(ra.id), Operand::IdRef(rb.id)],
+                            )
+                        })
+                        .map_err(crate::SerializeError::builder::<Self>)?,
+                ))
+            }
+
+            fn evaluate(&mut self, evaluator: &mut Evaluator, input: Self::Input) -> Self::Output {
+                let ra = self.a.evaluate(evaluator, input.clone());
+                let rb = self.b.evaluate(evaluator, input);
+                let res = evaluator.load(ra).min(evaluator.load(rb));
+                evaluator.store(res)
             }
         }
     };
@@ -0,0 +1,110 @@
+//! Boolean operations on fields. The smooth variants blend the surfaces within the distance `k` with Inigo Quilez's
+//! polynomial smooth minimum.
+use glam::{Vec2, Vec3};
+
+use super::{impl_field, Parameter};
+use crate::{expr::*, BoxOperation, DataId};
+
+macro_rules! combinator {
+    ($(#[$doc:meta])* $name:ident) => {
+        $(#[$doc])*
+        pub struct $name<V> {
+            pub a: BoxOperation<DataId<V>, f32>,
+            pub b: BoxOperation<DataId<V>, f32>,
+        }
+
+        impl<V> $name<V> {
+            pub fn new(a: impl Parameter<DataId<V>, f32>, b: impl Parameter<DataId<V>, f32>) -> Self {
+                $name {
+                    a: a.into_operation(),
+                    b: b.into_operation(),
+                }
+            }
+        }
+    };
+}
+
+macro_rules! smooth_combinator {
+    ($(#[$doc:meta])* $name:ident) => {
+        $(#[$doc])*
+        pub struct $name<V> {
+            pub a: BoxOperation<DataId<V>, f32>,
+            pub b: BoxOperation<DataId<V>, f32>,
+            ///Distance within which both surfaces are blended.
+            pub k: BoxOperation<DataId<V>, f32>,
+        }
+
+        impl<V> $name<V> {
+            pub fn new(
+                a: impl Parameter<DataId<V>, f32>,
+                b: impl Parameter<DataId<V>, f32>,
+                k: impl Parameter<DataId<V>, f32>,
+            ) -> Self {
+                $name {
+                    a: a.into_operation(),
+                    b: b.into_operation(),
+                    k: k.into_operation(),
+                }
+            }
+        }
+    };
+}
+
+combinator!(
+    ///Union of `a` and `b`.
+    Union
+);
+combinator!(
+    ///Intersection of `a` and `b`.
+    Intersection
+);
+combinator!(
+    ///`a` without `b`.
+    Difference
+);
+smooth_combinator!(
+    ///Union of `a` and `b` with a smooth transition between both.
+    SmoothUnion
+);
+smooth_combinator!(
+    ///Intersection of `a` and `b` with a smooth transition between both.
+    SmoothIntersection
+);
+smooth_combinator!(
+    ///`a` without `b`, with a smooth transition between both.
+    SmoothDifference
+);
+
+///`clamp(0.5 + 0.5 * x / k, 0, 1)`, the blend factor of the smooth combinators.
+fn blend(x: Expr<f32>, k: DataId<f32>) -> Expr<f32> {
+    saturate(add(constant(0.5), mul(constant(0.5), div(x, value(k)))))
+}
+
+///`k * h * (1 - h)`, the correction of the smooth combinators for the blend factor `h`.
+fn correction(h: impl Fn() -> Expr<f32>, k: DataId<f32>) -> Expr<f32> {
+    mul(value(k), mul(h(), sub(constant(1.0), h())))
+}
+
+macro_rules! impl_combinators {
+    ($($pos:ty),+) => {
+        $(
+            impl_field!(Union<$pos>, $pos, |_, a, b| min(value(a), value(b)));
+            impl_field!(Intersection<$pos>, $pos, |_, a, b| max(value(a), value(b)));
+            impl_field!(Difference<$pos>, $pos, |_, a, b| max(value(a), neg(value(b))));
+            impl_field!(SmoothUnion<$pos>, $pos, |_, a, b, k| {
+                let h = || blend(sub(value(b), value(a)), k);
+                sub(mix(value(b), value(a), h()), correction(h, k))
+            });
+            impl_field!(SmoothIntersection<$pos>, $pos, |_, a, b, k| {
+                let h = || blend(sub(value(a), value(b)), k);
+                add(mix(value(b), value(a), h()), correction(h, k))
+            });
+            impl_field!(SmoothDifference<$pos>, $pos, |_, a, b, k| {
+                let h = || blend(neg(add(value(a), value(b))), k);
+                add(mix(value(a), neg(value(b)), h()), correction(h, k))
+            });
+        )+
+    };
+}
+
+impl_combinators!(Vec2, Vec3);
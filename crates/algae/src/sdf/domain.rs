@@ -0,0 +1,178 @@
+//! Operations that transform the position before `inner` is sampled.
+use glam::{Mat2, Mat3, Vec2, Vec3};
+
+use super::Parameter;
+use crate::{expr::*, BoxOperation, DataId};
+
+///Moves `inner` by `offset`.
+pub struct Translate<V> {
+    pub inner: BoxOperation<DataId<V>, f32>,
+    pub offset: BoxOperation<DataId<V>, V>,
+}
+
+impl<V> Translate<V> {
+    pub fn new(
+        inner: impl Parameter<DataId<V>, f32>,
+        offset: impl Parameter<DataId<V>, V>,
+    ) -> Self {
+        Translate {
+            inner: inner.into_operation(),
+            offset: offset.into_operation(),
+        }
+    }
+}
+
+///Rotates `inner` around the origin by the `rotation` matrix, which must be orthonormal.
+pub struct Rotate<V, M> {
+    pub inner: BoxOperation<DataId<V>, f32>,
+    pub rotation: BoxOperation<DataId<V>, M>,
+}
+
+impl<V, M> Rotate<V, M> {
+    pub fn new(
+        inner: impl Parameter<DataId<V>, f32>,
+        rotation: impl Parameter<DataId<V>, M>,
+    ) -> Self {
+        Rotate {
+            inner: inner.into_operation(),
+            rotation: rotation.into_operation(),
+        }
+    }
+}
+
+///Scales `inner` uniformly around the origin by `factor`.
+pub struct Scale<V> {
+    pub inner: BoxOperation<DataId<V>, f32>,
+    pub factor: BoxOperation<DataId<V>, f32>,
+}
+
+impl<V> Scale<V> {
+    pub fn new(
+        inner: impl Parameter<DataId<V>, f32>,
+        factor: impl Parameter<DataId<V>, f32>,
+    ) -> Self {
+        Scale {
+            inner: inner.into_operation(),
+            factor: factor.into_operation(),
+        }
+    }
+}
+
+///Mirrors the half of `inner` in front of the plane through the origin with the given `normal` to the back of the plane.
+/// The part of `inner` behind the plane is replaced. `normal` must be normalized.
+pub struct Mirror<V> {
+    pub inner: BoxOperation<DataId<V>, f32>,
+    pub normal: BoxOperation<DataId<V>, V>,
+}
+
+impl<V> Mirror<V> {
+    pub fn new(
+        inner: impl Parameter<DataId<V>, f32>,
+        normal: impl Parameter<DataId<V>, V>,
+    ) -> Self {
+        Mirror {
+            inner: inner.into_operation(),
+            normal: normal.into_operation(),
+        }
+    }
+}
+
+///Repeats `inner` infinitely, with the distance `period` between two copies on each axis. `inner` should fit into a cell
+/// of size `period` around the origin, otherwise the distances are not exact.
+pub struct Repeat<V> {
+    pub inner: BoxOperation<DataId<V>, f32>,
+    pub period: BoxOperation<DataId<V>, V>,
+}
+
+impl<V> Repeat<V> {
+    pub fn new(
+        inner: impl Parameter<DataId<V>, f32>,
+        period: impl Parameter<DataId<V>, V>,
+    ) -> Self {
+        Repeat {
+            inner: inner.into_operation(),
+            period: period.into_operation(),
+        }
+    }
+}
+
+///Implements the domain operation `$op` that samples `inner` at the position calculated by `$position` from the sampled
+/// position `$p` and the fields `$param` of the struct. The optional `$distance` maps the distance `$d` of `inner`.
+macro_rules! impl_domain {
+    ($op:ty, $pos:ty, |$p:ident $(, $param:ident)*| $position:expr $(, |$d:ident| $distance:expr)?) => {
+        impl crate::Operation for $op {
+            type Input = DataId<$pos>;
+            type Output = DataId<f32>;
+
+            fn serialize(
+                &mut self,
+                serializer: &mut crate::Serializer,
+                input: Self::Input,
+            ) -> Result<Self::Output, crate::SerializeError> {
+                $(let $param = self.$param.serialize(serializer, input)?;)*
+                let $p = input;
+                let mut position: Expr<$pos> = $position;
+                let position = position.serialize(serializer, ())?;
+                let distance = self.inner.serialize(serializer, position)?;
+                $(
+                    let $d = distance;
+                    let mut distance: Expr<f32> = $distance;
+                    let distance = distance.serialize(serializer, ())?;
+                )?
+                Ok(distance)
+            }
+
+            fn evaluate(
+                &mut self,
+                evaluator: &mut crate::Evaluator,
+                input: Self::Input,
+            ) -> Self::Output {
+                $(let $param = self.$param.evaluate(evaluator, input);)*
+                let $p = input;
+                let mut position: Expr<$pos> = $position;
+                let position = position.evaluate(evaluator, ());
+                let distance = self.inner.evaluate(evaluator, position);
+                $(
+                    let $d = distance;
+                    let mut distance: Expr<f32> = $distance;
+                    let distance = distance.evaluate(evaluator, ());
+                )?
+                distance
+            }
+        }
+    };
+}
+
+macro_rules! impl_domains {
+    ($($pos:ty, $mat:ty);+) => {
+        $(
+            impl_domain!(Translate<$pos>, $pos, |p, offset| sub(value(p), value(offset)));
+            //The inverse of an orthonormal matrix is its transpose, and p * R = transpose(R) * p.
+            impl_domain!(Rotate<$pos, $mat>, $pos, |p, rotation| vector_times_matrix(
+                value(p),
+                value(rotation)
+            ));
+            impl_domain!(
+                Scale<$pos>,
+                $pos,
+                |p, factor| scale(value(p), div(constant(1.0), value(factor))),
+                |distance| mul(value(distance), value(factor))
+            );
+            //p - 2 * min(dot(p, n), 0) * n
+            impl_domain!(Mirror<$pos>, $pos, |p, normal| sub(
+                value(p),
+                scale(
+                    value(normal),
+                    mul(constant(2.0), min(dot(value(p), value(normal)), constant(0.0)))
+                )
+            ));
+            //p - period * round(p / period)
+            impl_domain!(Repeat<$pos>, $pos, |p, period| sub(
+                value(p),
+                mul(value(period), round(div(value(p), value(period))))
+            ));
+        )+
+    };
+}
+
+impl_domains!(Vec2, Mat2; Vec3, Mat3);
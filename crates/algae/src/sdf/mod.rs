@@ -0,0 +1,123 @@
+//! Signed distance fields, built from the existing operations. Each field is an [Operation] that takes the position it is
+//! sampled at as input, and returns the signed distance of that position to the surface. Negative distances are inside of
+//! the surface.
+//!
+//! 3D primitives, like [Sphere], are sampled at a `Vec3`. 2D primitives, like [Circle], are sampled at a `Vec2`, for
+//! instance for UI elements or glyphs.
+//!
+//! Fields are composed via combinators, like [Union] or [SmoothDifference], and domain operations, like [Translate] or
+//! [Repeat], which transform the position before it is passed to the inner field. Parameters can either be values, which
+//! become [Constant]s, or any operation, for instance a runtime [Variable](crate::operations::Variable).
+//!
+//! Most primitives follow Inigo Quilez's [distance functions](https://iquilezles.org/articles/distfunctions/).
+//!
+//! # Example
+//! ```rust
+//! use algae::{
+//!     glam::Vec3,
+//!     operations::{Constant, WithInput},
+//!     sdf::{Cuboid, Sphere, SmoothUnion, Translate},
+//!     Evaluator,
+//! };
+//!
+//! let mut field = WithInput {
+//!     input: Box::new(Constant::new(Vec3::new(0.0, 3.0, 0.0))),
+//!     inner: Box::new(SmoothUnion::new(
+//!         Sphere::new(1.0f32),
+//!         Translate::new(Cuboid::new(Vec3::ONE), Vec3::new(4.0, 0.0, 0.0)),
+//!         0.5f32,
+//!     )),
+//! };
+//! assert_eq!(Evaluator::new().evaluate(&mut field, ()), 2.0);
+//! ```
+use glam::{Mat2, Mat3, Vec2, Vec3, Vec4};
+
+use crate::{operations::Constant, BoxOperation, DataId, Operation};
+
+mod combinators;
+mod domain;
+mod primitives;
+mod primitives_2d;
+
+pub use combinators::{
+    Difference, Intersection, SmoothDifference, SmoothIntersection, SmoothUnion, Union,
+};
+pub use domain::{Mirror, Repeat, Rotate, Scale, Translate};
+pub use primitives::{Capsule, Cone, Cuboid, Cylinder, Plane, RoundCuboid, Sphere, Torus};
+pub use primitives_2d::{
+    Arc, Circle, OrientedRectangle, Polygon, Rectangle, Ring, RoundRectangle, Segment, Star,
+    Triangle,
+};
+
+///Parameter of a signed distance field, that is calculated from the input `I`. Implemented for values, which become
+/// [Constant]s, and for any operation, which receives the same input as the field.
+pub trait Parameter<I, T> {
+    fn into_operation(self) -> BoxOperation<I, T>;
+}
+
+macro_rules! impl_value_parameter {
+    ($($ty:ty),+) => {
+        $(
+            impl<I: 'static> Parameter<I, $ty> for $ty {
+                fn into_operation(self) -> BoxOperation<I, $ty> {
+                    Box::new(Constant::new(self))
+                }
+            }
+        )+
+    };
+}
+
+impl_value_parameter!(f32, Vec2, Vec3, Vec4, Mat2, Mat3);
+
+impl<I, T, O> Parameter<I, T> for O
+where
+    O: Operation<Input = I, Output = DataId<T>> + 'static,
+{
+    fn into_operation(self) -> BoxOperation<I, T> {
+        Box::new(self)
+    }
+}
+
+impl<I, T> Parameter<I, T> for BoxOperation<I, T> {
+    fn into_operation(self) -> BoxOperation<I, T> {
+        self
+    }
+}
+
+///Implements the field `$field` that is sampled at positions of type `$pos`. The body calculates the distance from the
+/// position `$p` and the already calculated fields `$param` of the struct. If `$this` is given, the body can access the
+/// struct through it.
+macro_rules! impl_field {
+    ($field:ty $(as $this:ident)?, $pos:ty, |$p:pat_param $(, $param:ident)*| $body:expr) => {
+        impl crate::Operation for $field {
+            type Input = crate::DataId<$pos>;
+            type Output = crate::DataId<f32>;
+
+            fn serialize(
+                &mut self,
+                serializer: &mut crate::Serializer,
+                input: Self::Input,
+            ) -> Result<Self::Output, crate::SerializeError> {
+                $(let $param = self.$param.serialize(serializer, input)?;)*
+                let $p = input;
+                $(let $this = &*self;)?
+                let mut distance: crate::expr::Expr<f32> = $body;
+                distance.serialize(serializer, ())
+            }
+
+            fn evaluate(
+                &mut self,
+                evaluator: &mut crate::Evaluator,
+                input: Self::Input,
+            ) -> Self::Output {
+                $(let $param = self.$param.evaluate(evaluator, input);)*
+                let $p = input;
+                $(let $this = &*self;)?
+                let mut distance: crate::expr::Expr<f32> = $body;
+                distance.evaluate(evaluator, ())
+            }
+        }
+    };
+}
+
+pub(crate) use impl_field;
@@ -0,0 +1,227 @@
+//! Primitive fields in 3D. All primitives are centered at the origin, fields that have a main axis are aligned to the y axis.
+use glam::{Vec2, Vec3};
+
+use super::{impl_field, Parameter};
+use crate::{expr::*, BoxOperation, DataId};
+
+type Param<T> = BoxOperation<DataId<Vec3>, T>;
+
+///Sphere with the given `radius`.
+pub struct Sphere {
+    pub radius: Param<f32>,
+}
+
+impl Sphere {
+    pub fn new(radius: impl Parameter<DataId<Vec3>, f32>) -> Self {
+        Sphere {
+            radius: radius.into_operation(),
+        }
+    }
+}
+
+///Box that reaches from `-extent` to `extent`.
+pub struct Cuboid {
+    ///Half the size of the box.
+    pub extent: Param<Vec3>,
+}
+
+impl Cuboid {
+    pub fn new(extent: impl Parameter<DataId<Vec3>, Vec3>) -> Self {
+        Cuboid {
+            extent: extent.into_operation(),
+        }
+    }
+}
+
+///Box that reaches from `-extent` to `extent`, whose edges are rounded with `radius`. The rounding stays within the
+/// extent of the box.
+pub struct RoundCuboid {
+    ///Half the size of the box.
+    pub extent: Param<Vec3>,
+    pub radius: Param<f32>,
+}
+
+impl RoundCuboid {
+    pub fn new(
+        extent: impl Parameter<DataId<Vec3>, Vec3>,
+        radius: impl Parameter<DataId<Vec3>, f32>,
+    ) -> Self {
+        RoundCuboid {
+            extent: extent.into_operation(),
+            radius: radius.into_operation(),
+        }
+    }
+}
+
+///Torus around the y axis. The ring of the torus has the given `radius`, its tube has the given `thickness`.
+pub struct Torus {
+    pub radius: Param<f32>,
+    ///Radius of the tube.
+    pub thickness: Param<f32>,
+}
+
+impl Torus {
+    pub fn new(
+        radius: impl Parameter<DataId<Vec3>, f32>,
+        thickness: impl Parameter<DataId<Vec3>, f32>,
+    ) -> Self {
+        Torus {
+            radius: radius.into_operation(),
+            thickness: thickness.into_operation(),
+        }
+    }
+}
+
+///Capsule around the line segment from `a` to `b`.
+pub struct Capsule {
+    pub a: Param<Vec3>,
+    pub b: Param<Vec3>,
+    pub radius: Param<f32>,
+}
+
+impl Capsule {
+    pub fn new(
+        a: impl Parameter<DataId<Vec3>, Vec3>,
+        b: impl Parameter<DataId<Vec3>, Vec3>,
+        radius: impl Parameter<DataId<Vec3>, f32>,
+    ) -> Self {
+        Capsule {
+            a: a.into_operation(),
+            b: b.into_operation(),
+            radius: radius.into_operation(),
+        }
+    }
+}
+
+///Capped cylinder around the y axis that reaches from `-height` to `height`.
+pub struct Cylinder {
+    pub radius: Param<f32>,
+    ///Half the height of the cylinder.
+    pub height: Param<f32>,
+}
+
+impl Cylinder {
+    pub fn new(
+        radius: impl Parameter<DataId<Vec3>, f32>,
+        height: impl Parameter<DataId<Vec3>, f32>,
+    ) -> Self {
+        Cylinder {
+            radius: radius.into_operation(),
+            height: height.into_operation(),
+        }
+    }
+}
+
+///Cone whose tip is at the origin. It opens towards negative y, until its base with the given `radius` at `-height`.
+pub struct Cone {
+    ///Radius of the base.
+    pub radius: Param<f32>,
+    pub height: Param<f32>,
+}
+
+impl Cone {
+    pub fn new(
+        radius: impl Parameter<DataId<Vec3>, f32>,
+        height: impl Parameter<DataId<Vec3>, f32>,
+    ) -> Self {
+        Cone {
+            radius: radius.into_operation(),
+            height: height.into_operation(),
+        }
+    }
+}
+
+///Infinite plane with the given `normal`, shifted by `offset` against its normal. `normal` must be normalized.
+pub struct Plane {
+    pub normal: Param<Vec3>,
+    pub offset: Param<f32>,
+}
+
+impl Plane {
+    pub fn new(
+        normal: impl Parameter<DataId<Vec3>, Vec3>,
+        offset: impl Parameter<DataId<Vec3>, f32>,
+    ) -> Self {
+        Plane {
+            normal: normal.into_operation(),
+            offset: offset.into_operation(),
+        }
+    }
+}
+
+///`length(max(q, 0)) + min(max(q.x, max(q.y, q.z)), 0)`, the distance to a box for `q = abs(p) - extent`.
+fn box_distance(q: impl Fn() -> Expr<Vec3>) -> Expr<f32> {
+    add(
+        length(max(q(), constant(Vec3::ZERO))),
+        min(
+            max(element(q(), 0), max(element(q(), 1), element(q(), 2))),
+            constant(0.0),
+        ),
+    )
+}
+
+///`vec2(length(p.xz), p.y)`, which maps rotationally symmetric fields around the y axis to 2D.
+fn radial(p: DataId<Vec3>) -> Expr<Vec2> {
+    vec2(
+        length(swizzle::<Vec3, Vec2>("xz", value(p))),
+        element(value(p), 1),
+    )
+}
+
+impl_field!(Sphere, Vec3, |p, radius| sub(
+    length(value(p)),
+    value(radius)
+));
+impl_field!(Cuboid, Vec3, |p, extent| box_distance(|| sub(
+    abs(value(p)),
+    value(extent)
+)));
+impl_field!(RoundCuboid, Vec3, |p, extent, radius| sub(
+    box_distance(|| sub(abs(value(p)), sub(value(extent), splat(value(radius))))),
+    value(radius)
+));
+impl_field!(Torus, Vec3, |p, radius, thickness| sub(
+    length(sub(radial(p), vec2(value(radius), constant(0.0)))),
+    value(thickness)
+));
+impl_field!(Capsule, Vec3, |p, a, b, radius| {
+    let pa = || sub(value(p), value(a));
+    let ba = || sub(value(b), value(a));
+    let h = saturate(div(dot(pa(), ba()), dot(ba(), ba())));
+    sub(length(sub(pa(), scale(ba(), h))), value(radius))
+});
+impl_field!(Cylinder, Vec3, |p, radius, height| {
+    let d = || sub(abs(radial(p)), vec2(value(radius), value(height)));
+    add(
+        min(max(element(d(), 0), element(d(), 1)), constant(0.0)),
+        length(max(d(), constant(Vec2::ZERO))),
+    )
+});
+//Inigo Quilez's exact cone with q = (radius, -height), where the sign of q.y is always negative.
+impl_field!(Cone, Vec3, |p, radius, height| {
+    let w = || radial(p);
+    let q = || vec2(value(radius), neg(value(height)));
+    let a = || sub(w(), scale(q(), saturate(div(dot(w(), q()), dot(q(), q())))));
+    let b = || {
+        vec2(
+            sub(
+                element(w(), 0),
+                mul(value(radius), saturate(div(element(w(), 0), value(radius)))),
+            ),
+            add(element(w(), 1), value(height)),
+        )
+    };
+    let squared_distance = min(dot(a(), a()), dot(b(), b()));
+    let side = max(
+        add(
+            mul(element(w(), 0), value(height)),
+            mul(element(w(), 1), value(radius)),
+        ),
+        neg(add(element(w(), 1), value(height))),
+    );
+    mul(sqrt(squared_distance), sign(side))
+});
+impl_field!(Plane, Vec3, |p, normal, offset| add(
+    dot(value(p), value(normal)),
+    value(offset)
+));
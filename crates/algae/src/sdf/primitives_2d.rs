@@ -0,0 +1,383 @@
+//! Primitive fields in 2D. Unless they are placed via points, all primitives are centered at the origin.
+use std::f32::consts::PI;
+
+use glam::Vec2;
+
+use super::{impl_field, Parameter};
+use crate::{expr::*, BoxOperation, DataId};
+
+type Param<T> = BoxOperation<DataId<Vec2>, T>;
+
+///Circle with the given `radius`.
+pub struct Circle {
+    pub radius: Param<f32>,
+}
+
+impl Circle {
+    pub fn new(radius: impl Parameter<DataId<Vec2>, f32>) -> Self {
+        Circle {
+            radius: radius.into_operation(),
+        }
+    }
+}
+
+///Rectangle that reaches from `-extent` to `extent`.
+pub struct Rectangle {
+    ///Half the size of the rectangle.
+    pub extent: Param<Vec2>,
+}
+
+impl Rectangle {
+    pub fn new(extent: impl Parameter<DataId<Vec2>, Vec2>) -> Self {
+        Rectangle {
+            extent: extent.into_operation(),
+        }
+    }
+}
+
+///Rectangle that reaches from `-extent` to `extent`, whose corners are rounded with `radius`. The rounding stays within
+/// the extent of the rectangle.
+pub struct RoundRectangle {
+    ///Half the size of the rectangle.
+    pub extent: Param<Vec2>,
+    pub radius: Param<f32>,
+}
+
+impl RoundRectangle {
+    pub fn new(
+        extent: impl Parameter<DataId<Vec2>, Vec2>,
+        radius: impl Parameter<DataId<Vec2>, f32>,
+    ) -> Self {
+        RoundRectangle {
+            extent: extent.into_operation(),
+            radius: radius.into_operation(),
+        }
+    }
+}
+
+///Rectangle from the center of its edge at `a` to the center of its opposite edge at `b`.
+pub struct OrientedRectangle {
+    pub a: Param<Vec2>,
+    pub b: Param<Vec2>,
+    ///Length of the edges at `a` and `b`.
+    pub thickness: Param<f32>,
+}
+
+impl OrientedRectangle {
+    pub fn new(
+        a: impl Parameter<DataId<Vec2>, Vec2>,
+        b: impl Parameter<DataId<Vec2>, Vec2>,
+        thickness: impl Parameter<DataId<Vec2>, f32>,
+    ) -> Self {
+        OrientedRectangle {
+            a: a.into_operation(),
+            b: b.into_operation(),
+            thickness: thickness.into_operation(),
+        }
+    }
+}
+
+///Line segment from `a` to `b`. The distance is never negative, since the segment has no inside.
+pub struct Segment {
+    pub a: Param<Vec2>,
+    pub b: Param<Vec2>,
+}
+
+impl Segment {
+    pub fn new(
+        a: impl Parameter<DataId<Vec2>, Vec2>,
+        b: impl Parameter<DataId<Vec2>, Vec2>,
+    ) -> Self {
+        Segment {
+            a: a.into_operation(),
+            b: b.into_operation(),
+        }
+    }
+}
+
+///Triangle with the corners `a`, `b` and `c`, in any winding order.
+pub struct Triangle {
+    pub a: Param<Vec2>,
+    pub b: Param<Vec2>,
+    pub c: Param<Vec2>,
+}
+
+impl Triangle {
+    pub fn new(
+        a: impl Parameter<DataId<Vec2>, Vec2>,
+        b: impl Parameter<DataId<Vec2>, Vec2>,
+        c: impl Parameter<DataId<Vec2>, Vec2>,
+    ) -> Self {
+        Triangle {
+            a: a.into_operation(),
+            b: b.into_operation(),
+            c: c.into_operation(),
+        }
+    }
+}
+
+///Closed polygon through the constant `points`, in any winding order. The polygon may be concave, but its edges must not
+/// intersect each other.
+///
+/// Each edge is serialized on its own, therefore the size of the serialized code grows with the number of points.
+pub struct Polygon {
+    pub points: Vec<Vec2>,
+}
+
+impl Polygon {
+    ///Creates the polygon. Panics if there are less than three `points`.
+    pub fn new(points: Vec<Vec2>) -> Self {
+        assert!(
+            points.len() >= 3,
+            "Polygon needs at least 3 points, got {}",
+            points.len()
+        );
+        Polygon { points }
+    }
+}
+
+///Arc of a circle with `radius` around the positive y axis, which spans `aperture` radians to either side of the axis.
+pub struct Arc {
+    ///Half the angle of the arc, between 0 and π.
+    pub aperture: Param<f32>,
+    pub radius: Param<f32>,
+    ///Radius of the arc's line.
+    pub thickness: Param<f32>,
+}
+
+impl Arc {
+    pub fn new(
+        aperture: impl Parameter<DataId<Vec2>, f32>,
+        radius: impl Parameter<DataId<Vec2>, f32>,
+        thickness: impl Parameter<DataId<Vec2>, f32>,
+    ) -> Self {
+        Arc {
+            aperture: aperture.into_operation(),
+            radius: radius.into_operation(),
+            thickness: thickness.into_operation(),
+        }
+    }
+}
+
+///Ring with the given `radius`. Its line reaches `thickness` to either side of the circle.
+pub struct Ring {
+    pub radius: Param<f32>,
+    pub thickness: Param<f32>,
+}
+
+impl Ring {
+    pub fn new(
+        radius: impl Parameter<DataId<Vec2>, f32>,
+        thickness: impl Parameter<DataId<Vec2>, f32>,
+    ) -> Self {
+        Ring {
+            radius: radius.into_operation(),
+            thickness: thickness.into_operation(),
+        }
+    }
+}
+
+///Star with the given number of `points`, whose tips lie on a circle with `radius`. The first tip points along the
+/// positive y axis.
+pub struct Star {
+    pub radius: Param<f32>,
+    ///Number of tips, at least 2.
+    pub points: u32,
+    ///Controls the angle of the tips. Between 2 and `points`, where 2 gives a regular polygon and higher values give
+    /// sharper tips.
+    pub density: Param<f32>,
+}
+
+impl Star {
+    ///Creates the star. Panics if there are less than two `points`.
+    pub fn new(
+        radius: impl Parameter<DataId<Vec2>, f32>,
+        points: u32,
+        density: impl Parameter<DataId<Vec2>, f32>,
+    ) -> Self {
+        assert!(points >= 2, "Star needs at least 2 points, got {}", points);
+        Star {
+            radius: radius.into_operation(),
+            points,
+            density: density.into_operation(),
+        }
+    }
+}
+
+///`length(max(q, 0)) + min(max(q.x, q.y), 0)`, the distance to a rectangle for `q = abs(p) - extent`.
+fn rectangle_distance(q: impl Fn() -> Expr<Vec2>) -> Expr<f32> {
+    add(
+        length(max(q(), constant(Vec2::ZERO))),
+        min(max(element(q(), 0), element(q(), 1)), constant(0.0)),
+    )
+}
+
+///`a.x * b.y - a.y * b.x`, the z component of the cross product of `a` and `b`.
+fn cross(a: impl Fn() -> Expr<Vec2>, b: impl Fn() -> Expr<Vec2>) -> Expr<f32> {
+    sub(
+        mul(element(a(), 0), element(b(), 1)),
+        mul(element(a(), 1), element(b(), 0)),
+    )
+}
+
+///`p - a` moved to the closest point on the segment `a + t * edge` for t in `[0, 1]`.
+fn to_segment(pa: impl Fn() -> Expr<Vec2>, edge: impl Fn() -> Expr<Vec2>) -> Expr<Vec2> {
+    sub(
+        pa(),
+        scale(
+            edge(),
+            saturate(div(dot(pa(), edge()), dot(edge(), edge()))),
+        ),
+    )
+}
+
+///`vec2(abs(p.x), p.y)`, which mirrors fields that are symmetric to the y axis.
+fn mirror_x(p: DataId<Vec2>) -> Expr<Vec2> {
+    vec2(abs(element(value(p), 0)), element(value(p), 1))
+}
+
+impl_field!(Circle, Vec2, |p, radius| sub(
+    length(value(p)),
+    value(radius)
+));
+impl_field!(Rectangle, Vec2, |p, extent| rectangle_distance(|| sub(
+    abs(value(p)),
+    value(extent)
+)));
+impl_field!(RoundRectangle, Vec2, |p, extent, radius| sub(
+    rectangle_distance(|| sub(abs(value(p)), sub(value(extent), splat(value(radius))))),
+    value(radius)
+));
+impl_field!(OrientedRectangle, Vec2, |p, a, b, thickness| {
+    let ba = || sub(value(b), value(a));
+    let direction = || scale(ba(), div(constant(1.0), length(ba())));
+    let centered = || sub(value(p), scale(add(value(a), value(b)), constant(0.5)));
+    //Rotates the rectangle onto the x axis
+    let rotated = || vec2(dot(direction(), centered()), cross(direction, centered));
+    let half_size = || scale(vec2(length(ba()), value(thickness)), constant(0.5));
+    rectangle_distance(|| sub(abs(rotated()), half_size()))
+});
+impl_field!(Segment, Vec2, |p, a, b| length(to_segment(
+    || sub(value(p), value(a)),
+    || sub(value(b), value(a))
+)));
+//The closest point on the edges gives the distance, the side of the edges gives the sign.
+impl_field!(Triangle, Vec2, |p, a, b, c| {
+    let corners = [a, b, c];
+    let edge = |i: usize| move || sub(value(corners[(i + 1) % 3]), value(corners[i]));
+    let to_corner = |i: usize| move || sub(value(p), value(corners[i]));
+    //Squared distance to the edge, and distance to the edge's line, which is positive inside of the triangle.
+    let edge_distance = |i: usize| {
+        vec2(
+            dot(
+                to_segment(to_corner(i), edge(i)),
+                to_segment(to_corner(i), edge(i)),
+            ),
+            mul(sign(cross(edge(0), edge(2))), cross(to_corner(i), edge(i))),
+        )
+    };
+    let d = || min(min(edge_distance(0), edge_distance(1)), edge_distance(2));
+    mul(neg(sqrt(element(d(), 0))), sign(element(d(), 1)))
+});
+impl_field!(Arc, Vec2, |p, aperture, radius, thickness| {
+    let q = || mirror_x(p);
+    let end = || vec2(sin(value(aperture)), cos(value(aperture)));
+    //Beyond the end of the arc, the end point is the closest point.
+    let beyond = greater(
+        mul(element(end(), 1), element(q(), 0)),
+        mul(element(end(), 0), element(q(), 1)),
+    );
+    sub(
+        select(
+            beyond,
+            length(sub(q(), scale(end(), value(radius)))),
+            abs(sub(length(q()), value(radius))),
+        ),
+        value(thickness),
+    )
+});
+impl_field!(Ring, Vec2, |p, radius, thickness| sub(
+    abs(sub(length(value(p)), value(radius))),
+    value(thickness)
+));
+
+impl Star {
+    ///Inigo Quilez's star. The position is folded into the sector of the first tip, which is then bounded by a single line.
+    fn distance(&self, p: DataId<Vec2>, radius: DataId<f32>, density: DataId<f32>) -> Expr<f32> {
+        let sector = PI / self.points as f32;
+        let tip = Vec2::new(sector.cos(), sector.sin());
+        let inner_angle = || div(constant(PI), value(density));
+        let inner = || vec2(cos(inner_angle()), sin(inner_angle()));
+
+        let angle = || {
+            sub(
+                modulo(
+                    atan2(element(value(p), 0), element(value(p), 1)),
+                    constant(2.0 * sector),
+                ),
+                constant(sector),
+            )
+        };
+        let folded = || {
+            sub(
+                scale(vec2(cos(angle()), abs(sin(angle()))), length(value(p))),
+                scale(constant(tip), value(radius)),
+            )
+        };
+        let q = || {
+            add(
+                folded(),
+                scale(
+                    inner(),
+                    clamp(
+                        neg(dot(folded(), inner())),
+                        constant(0.0),
+                        div(mul(value(radius), constant(tip.y)), element(inner(), 1)),
+                    ),
+                ),
+            )
+        };
+        mul(length(q()), sign(element(q(), 0)))
+    }
+}
+
+impl Polygon {
+    ///Inigo Quilez's polygon. The closest point on the edges gives the distance. The sign flips for each edge that is
+    /// crossed by a ray from the position along the positive x axis.
+    fn distance(&self, p: DataId<Vec2>) -> Expr<f32> {
+        let first = self.points[0];
+        let mut squared_distance = dot(
+            sub(value(p), constant(first)),
+            sub(value(p), constant(first)),
+        );
+        let mut side = constant(1.0);
+        let previous = std::iter::once(self.points.len() - 1).chain(0..);
+        for (i, j) in (0..self.points.len()).zip(previous) {
+            let (start, end) = (self.points[i], self.points[j]);
+            let edge = end - start;
+            let w = || sub(value(p), constant(start));
+            let closest = || to_segment(w, || constant(edge));
+            squared_distance = min(squared_distance, dot(closest(), closest()));
+
+            let y = || element(value(p), 1);
+            let conditions = || {
+                [
+                    greater_equal(y(), constant(start.y)),
+                    less(y(), constant(end.y)),
+                    greater(
+                        mul(constant(edge.x), element(w(), 1)),
+                        mul(constant(edge.y), element(w(), 0)),
+                    ),
+                ]
+            };
+            let all = |[a, b, c]: [Expr<bool>; 3]| and(a, and(b, c));
+            let crosses = or(all(conditions()), all(conditions().map(not)));
+            side = mul(side, select(crosses, constant(-1.0), constant(1.0)));
+        }
+        mul(side, sqrt(squared_distance))
+    }
+}
+
+impl_field!(Polygon as polygon, Vec2, |p| polygon.distance(p));
+impl_field!(Star as star, Vec2, |p, radius, density| star
+    .distance(p, radius, density));
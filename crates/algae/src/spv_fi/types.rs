@@ -1,4 +1,6 @@
-use glam::{IVec2, IVec3, IVec4, Mat2, Mat3, Mat4, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4};
+use glam::{
+    BVec2, BVec3, BVec4, IVec2, IVec3, IVec4, Mat2, Mat3, Mat4, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4,
+};
 use rspirv::{
     dr::{
         Instruction, Module,
@@ -7,7 +9,7 @@ use rspirv::{
     spirv::{Op, Word},
 };
 
-use crate::{DataId, Serializer};
+use crate::{DataId, SerializeError, Serializer};
 
 use super::SpvError;
 
@@ -15,6 +17,7 @@ use super::SpvError;
 ///Runtime representation of a spirv type. Can either be parsed from an instruction,
 ///or derived from a rust type at runtime via the [IntoSpvType](IntoSpvType) trait.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize, serde::Deserialize))]
 pub enum SpvType {
     Bool,
     Int {
@@ -368,7 +371,10 @@ pub trait IntoSpvType {
     ///Returns the SpvType version of `Self`.
     fn into_spv_type() -> SpvType;
     ///Serializes `self` as a constant into `serializer`, returning the `DataId<Self>` to this constant.
-    fn constant_serialize(&self, serializer: &mut Serializer) -> DataId<Self>
+    fn constant_serialize(
+        &self,
+        serializer: &mut Serializer,
+    ) -> Result<DataId<Self>, SerializeError>
     where
         Self: Sized;
     ///Shorthand for `Self::into_spv_type().spirv_type_id(serializer)`. Returns a types id in the context
@@ -382,12 +388,15 @@ impl IntoSpvType for bool {
     fn into_spv_type() -> SpvType {
         SpvType::Bool
     }
-    fn constant_serialize(&self, serializer: &mut Serializer) -> DataId<Self> {
-        let ty = Self::spirv_type_id(serializer).unwrap();
+    fn constant_serialize(
+        &self,
+        serializer: &mut Serializer,
+    ) -> Result<DataId<Self>, SerializeError> {
+        let ty = serializer.type_id::<Self, Self>()?;
         if *self {
-            DataId::from(serializer.builder_mut().constant_true(ty))
+            Ok(DataId::from(serializer.constant(|builder| builder.constant_true(ty))))
         } else {
-            DataId::from(serializer.builder_mut().constant_false(ty))
+            Ok(DataId::from(serializer.constant(|builder| builder.constant_false(ty))))
         }
     }
 }
@@ -395,18 +404,24 @@ impl IntoSpvType for f32 {
     fn into_spv_type() -> SpvType {
         SpvType::Float { width: 32 }
     }
-    fn constant_serialize(&self, serializer: &mut Serializer) -> DataId<Self> {
-        let ty = Self::spirv_type_id(serializer).unwrap();
-        DataId::from(serializer.builder_mut().constant_f32(ty, *self))
+    fn constant_serialize(
+        &self,
+        serializer: &mut Serializer,
+    ) -> Result<DataId<Self>, SerializeError> {
+        let ty = serializer.type_id::<Self, Self>()?;
+        Ok(DataId::from(serializer.constant(|builder| builder.constant_f32(ty, *self))))
     }
 }
 impl IntoSpvType for f64 {
     fn into_spv_type() -> SpvType {
         SpvType::Float { width: 64 }
     }
-    fn constant_serialize(&self, serializer: &mut Serializer) -> DataId<Self> {
-        let ty = Self::spirv_type_id(serializer).unwrap();
-        DataId::from(serializer.builder_mut().constant_f64(ty, *self))
+    fn constant_serialize(
+        &self,
+        serializer: &mut Serializer,
+    ) -> Result<DataId<Self>, SerializeError> {
+        let ty = serializer.type_id::<Self, Self>()?;
+        Ok(DataId::from(serializer.constant(|builder| builder.constant_f64(ty, *self))))
     }
 }
 impl IntoSpvType for i32 {
@@ -416,13 +431,17 @@ impl IntoSpvType for i32 {
             width: 32,
         }
     }
-    fn constant_serialize(&self, serializer: &mut Serializer) -> DataId<Self> {
-        let ty = Self::spirv_type_id(serializer).unwrap();
-        DataId::from(
+    fn constant_serialize(
+        &self,
+        serializer: &mut Serializer,
+    ) -> Result<DataId<Self>, SerializeError> {
+        let ty = serializer.type_id::<Self, Self>()?;
+        Ok(DataId::from(
             serializer
-                .builder_mut()
-                .constant_u32(ty, u32::from_be_bytes(self.to_be_bytes())),
-        ) //note constructing unsigend version of the i32.
+                .constant(|builder| {
+                    builder.constant_u32(ty, u32::from_be_bytes(self.to_be_bytes()))
+                }),
+        )) //note constructing unsigend version of the i32.
     }
 }
 impl IntoSpvType for i64 {
@@ -432,13 +451,17 @@ impl IntoSpvType for i64 {
             width: 64,
         }
     }
-    fn constant_serialize(&self, serializer: &mut Serializer) -> DataId<Self> {
-        let ty = Self::spirv_type_id(serializer).unwrap();
-        DataId::from(
+    fn constant_serialize(
+        &self,
+        serializer: &mut Serializer,
+    ) -> Result<DataId<Self>, SerializeError> {
+        let ty = serializer.type_id::<Self, Self>()?;
+        Ok(DataId::from(
             serializer
-                .builder_mut()
-                .constant_u64(ty, u64::from_be_bytes(self.to_be_bytes())),
-        ) //note constructing unsigend version of the i32.
+                .constant(|builder| {
+                    builder.constant_u64(ty, u64::from_be_bytes(self.to_be_bytes()))
+                }),
+        )) //note constructing unsigend version of the i32.
     }
 }
 impl IntoSpvType for u32 {
@@ -448,9 +471,12 @@ impl IntoSpvType for u32 {
             width: 32,
         }
     }
-    fn constant_serialize(&self, serializer: &mut Serializer) -> DataId<Self> {
-        let ty = Self::spirv_type_id(serializer).unwrap();
-        DataId::from(serializer.builder_mut().constant_u32(ty, *self))
+    fn constant_serialize(
+        &self,
+        serializer: &mut Serializer,
+    ) -> Result<DataId<Self>, SerializeError> {
+        let ty = serializer.type_id::<Self, Self>()?;
+        Ok(DataId::from(serializer.constant(|builder| builder.constant_u32(ty, *self))))
     }
 }
 impl IntoSpvType for u64 {
@@ -460,9 +486,12 @@ impl IntoSpvType for u64 {
             width: 64,
         }
     }
-    fn constant_serialize(&self, serializer: &mut Serializer) -> DataId<Self> {
-        let ty = Self::spirv_type_id(serializer).unwrap();
-        DataId::from(serializer.builder_mut().constant_u64(ty, *self))
+    fn constant_serialize(
+        &self,
+        serializer: &mut Serializer,
+    ) -> Result<DataId<Self>, SerializeError> {
+        let ty = serializer.type_id::<Self, Self>()?;
+        Ok(DataId::from(serializer.constant(|builder| builder.constant_u64(ty, *self))))
     }
 }
 
@@ -476,20 +505,20 @@ macro_rules! impl_into_spv_vec{
                     num_elements: $ne,
                 }
             }
-            fn constant_serialize(&self, serializer: &mut Serializer) -> DataId<Self>{
-                let ty = Self::spirv_type_id(serializer).unwrap();
+            fn constant_serialize(&self, serializer: &mut Serializer) -> Result<DataId<Self>, SerializeError>{
+                let ty = serializer.type_id::<Self, Self>()?;
 
                 let ids = [
                     $(
-                        self.$element_name.constant_serialize(serializer).id
+                        self.$element_name.constant_serialize(serializer)?.id
                     ),+
                 ];
-                DataId::from(
-                    serializer.builder_mut().constant_composite(
+                Ok(DataId::from(
+                    serializer.constant(|builder| builder.constant_composite(
                         ty,
                         ids
-                    )
-                )
+                    ))
+                ))
             }
         }
     }
@@ -507,35 +536,75 @@ impl_into_spv_vec!(UVec2, u32, 2, x, y);
 impl_into_spv_vec!(UVec3, u32, 3, x, y, z);
 impl_into_spv_vec!(UVec4, u32, 4, x, y, z, w);
 
-/* TODO implement matrix types as constant
-impl IntoSpvType for Mat2 {
-    fn into_spv_type() -> SpvType {
-        SpvType::Matrix {
-            data_type: Box::new(f32::into_spv_type()),
-            width: 2,
-            height: 2,
-        }
-    }
-}
-impl IntoSpvType for Mat3 {
-    fn into_spv_type() -> SpvType {
-        SpvType::Matrix {
-            data_type: Box::new(f32::into_spv_type()),
-            width: 3,
-            height: 3,
+///Implements IntoSpvType for a glam boolean vector. Those do not expose their elements as fields, therefore they are
+/// serialized from their array representation.
+macro_rules! impl_into_spv_bvec{
+    ($vecty:ty, $ne:expr) => {
+        impl IntoSpvType for $vecty {
+            fn into_spv_type() -> SpvType {
+                SpvType::Vec {
+                    data_type: Box::new(bool::into_spv_type()),
+                    num_elements: $ne,
+                }
+            }
+            fn constant_serialize(&self, serializer: &mut Serializer) -> Result<DataId<Self>, SerializeError>{
+                let ty = serializer.type_id::<Self, Self>()?;
+
+                let elements: [bool; $ne] = (*self).into();
+                let mut ids = [0; $ne];
+                for (id, element) in ids.iter_mut().zip(elements) {
+                    *id = element.constant_serialize(serializer)?.id;
+                }
+                Ok(DataId::from(
+                    serializer.constant(|builder| builder.constant_composite(
+                        ty,
+                        ids
+                    ))
+                ))
+            }
         }
     }
 }
-impl IntoSpvType for Mat4 {
-    fn into_spv_type() -> SpvType {
-        SpvType::Matrix {
-            data_type: Box::new(f32::into_spv_type()),
-            width: 4,
-            height: 4,
+
+impl_into_spv_bvec!(BVec2, 2);
+impl_into_spv_bvec!(BVec3, 3);
+impl_into_spv_bvec!(BVec4, 4);
+
+///Implements IntoSpvType for a square glam float matrix. Matrices are serialized column major, i.e. the constant is
+/// a composite of the column vectors.
+macro_rules! impl_into_spv_mat{
+    ($matty:ty, $ne:expr, $($column_name:ident),+) => {
+        impl IntoSpvType for $matty {
+            fn into_spv_type() -> SpvType {
+                SpvType::Matrix {
+                    data_type: Box::new(f32::into_spv_type()),
+                    width: $ne,
+                    height: $ne,
+                }
+            }
+            fn constant_serialize(&self, serializer: &mut Serializer) -> Result<DataId<Self>, SerializeError>{
+                let ty = serializer.type_id::<Self, Self>()?;
+
+                let ids = [
+                    $(
+                        self.$column_name.constant_serialize(serializer)?.id
+                    ),+
+                ];
+                Ok(DataId::from(
+                    serializer.constant(|builder| builder.constant_composite(
+                        ty,
+                        ids
+                    ))
+                ))
+            }
         }
     }
 }
-*/
+
+impl_into_spv_mat!(Mat2, 2, x_axis, y_axis);
+impl_into_spv_mat!(Mat3, 3, x_axis, y_axis, z_axis);
+impl_into_spv_mat!(Mat4, 4, x_axis, y_axis, z_axis, w_axis);
+
 fn is_op_type(op: &Op) -> bool {
     match op {
         Op::TypeVoid
@@ -0,0 +1,1097 @@
+use algae::{
+    dynamic::{DynError, DynOp, DynOperation, DynValue, Gradient, ParseErrorKind, Parser},
+    glam::{BVec2, IVec2, Mat2, Mat3, UVec2, Vec2, Vec3, Vec4},
+    operations::{
+        AccessOrDefault, AccessResult, Addition, All, Any, ArcTangent2, BitwiseAnd, BitwiseOr,
+        BitwiseXor, CentralDifferenceNormal, Constant, Convert, Cross, Determinant, Dot,
+        FaceForward, Fract, Length, Less, Let, Loop, MatrixInverse, MatrixTimesVector, Max, Min,
+        Mix, Modulo, Multiplication, NotEqual, OrderedOperations, Reflect, Refract, Remainder,
+        ResultContext, ReturnInput, ShiftLeft, ShiftRight, Sign, SmoothStep, Splat, Subtraction,
+        Swizzle, TetrahedronNormal, Variable, VecConstruct, VecSelectElement,
+        VectorDividedByScalar, VectorMinusScalar, VectorTimesMatrix, VectorTimesScalar, WithInput,
+    },
+    sdf::{
+        Arc, Capsule, Circle, Cone, Cuboid, Cylinder, Difference, Intersection, Mirror,
+        OrientedRectangle, Parameter, Plane, Polygon, Rectangle, Repeat, Ring, Rotate, RoundCuboid,
+        RoundRectangle, Scale, Segment, SmoothDifference, SmoothIntersection, SmoothUnion, Sphere,
+        Star, Torus, Translate, Triangle, Union,
+    },
+    spv_fi::IntoSpvType,
+    BoxOperation, DataId, Evaluator,
+};
+
+///Inigo Quilez's 2D box sdf with an extent of (1, 2).
+fn box_sdf() -> OrderedOperations<(), f32> {
+    OrderedOperations::new(
+        "d",
+        Box::new(Subtraction {
+            minuent: Box::new(algae::operations::Abs {
+                inner: Box::new(Variable::new("coord", Vec2::ZERO)),
+            }),
+            subtrahend: Box::new(Constant::new(Vec2::new(1.0, 2.0))),
+        }),
+    )
+    .push(
+        "result",
+        Box::new(Addition {
+            a: Box::new(Length {
+                inner: Box::new(Max {
+                    a: Box::new(AccessResult::<Vec2>::new("d")),
+                    b: Box::new(Constant::new(Vec2::ZERO)),
+                }),
+            }),
+            b: Box::new(Min {
+                a: Box::new(Max {
+                    a: Box::new(VecSelectElement::<Vec2, _> {
+                        element: 0,
+                        inner: Box::new(AccessResult::<Vec2>::new("d")),
+                    }),
+                    b: Box::new(VecSelectElement::<Vec2, _> {
+                        element: 1,
+                        inner: Box::new(AccessResult::<Vec2>::new("d")),
+                    }),
+                }),
+                b: Box::new(Constant::new(0.0f32)),
+            }),
+        }),
+    )
+}
+
+#[test]
+fn evaluate_box_sdf() {
+    let mut sdf = box_sdf();
+    let mut evaluator = Evaluator::new();
+
+    //Not set, therefore uses the default value at the center of the box
+    assert_eq!(evaluator.evaluate(&mut sdf, ()), -1.0);
+
+    evaluator.set_variable("coord", Vec2::new(4.0, 6.0));
+    assert_eq!(evaluator.evaluate(&mut sdf, ()), 5.0);
+
+    evaluator.set_variable("coord", Vec2::new(0.0, -3.0));
+    assert_eq!(evaluator.evaluate(&mut sdf, ()), 1.0);
+}
+
+#[test]
+fn variable_type_mismatch_uses_default() {
+    let mut var = Variable::<(), f32>::new("radius", 2.0);
+    let mut evaluator = Evaluator::new();
+
+    evaluator.set_variable("radius", Vec2::ONE);
+    assert_eq!(evaluator.evaluate(&mut var, ()), 2.0);
+
+    evaluator.set_variable("radius", 3.0f32);
+    assert_eq!(evaluator.evaluate(&mut var, ()), 3.0);
+}
+
+#[test]
+fn integer_remainder_and_modulo() {
+    let mut evaluator = Evaluator::new();
+
+    let mut rem = Remainder::<(), i32> {
+        dividend: Box::new(Constant::new(-7)),
+        divisor: Box::new(Constant::new(3)),
+    };
+    let mut modulo = Modulo::<(), i32> {
+        dividend: Box::new(Constant::new(-7)),
+        divisor: Box::new(Constant::new(3)),
+    };
+    assert_eq!(evaluator.evaluate(&mut rem, ()), -1);
+    assert_eq!(evaluator.evaluate(&mut modulo, ()), 2);
+
+    let mut wrapping = Addition::<(), UVec2> {
+        a: Box::new(Constant::new(UVec2::new(u32::MAX, 1))),
+        b: Box::new(Constant::new(UVec2::new(2, 2))),
+    };
+    assert_eq!(evaluator.evaluate(&mut wrapping, ()), UVec2::new(1, 3));
+}
+
+#[test]
+fn bit_operations_and_conversion() {
+    let mut evaluator = Evaluator::new();
+    let bits = || Box::new(Constant::new(0b1100u32));
+    let mask = || Box::new(Constant::new(0b1010u32));
+
+    let mut and = BitwiseAnd::<(), u32> {
+        a: bits(),
+        b: mask(),
+    };
+    let mut or = BitwiseOr::<(), u32> {
+        a: bits(),
+        b: mask(),
+    };
+    let mut xor = BitwiseXor::<(), u32> {
+        a: bits(),
+        b: mask(),
+    };
+    assert_eq!(evaluator.evaluate(&mut and, ()), 0b1000);
+    assert_eq!(evaluator.evaluate(&mut or, ()), 0b1110);
+    assert_eq!(evaluator.evaluate(&mut xor, ()), 0b0110);
+
+    let mut shift_left = ShiftLeft::<(), UVec2> {
+        base: Box::new(Constant::new(UVec2::new(u32::MAX, 1))),
+        shift: Box::new(Constant::new(UVec2::new(4, 32))),
+    };
+    assert_eq!(
+        evaluator.evaluate(&mut shift_left, ()),
+        UVec2::new(u32::MAX << 4, 0)
+    );
+    //Signed integers keep their sign, unsigned ones are filled with zeros.
+    let mut arithmetic = ShiftRight::<(), i32> {
+        base: Box::new(Constant::new(-16)),
+        shift: Box::new(Constant::new(2)),
+    };
+    let mut logical = ShiftRight::<(), u32> {
+        base: Box::new(Constant::new(u32::MAX)),
+        shift: Box::new(Constant::new(28)),
+    };
+    assert_eq!(evaluator.evaluate(&mut arithmetic, ()), -4);
+    assert_eq!(evaluator.evaluate(&mut logical, ()), 0xf);
+
+    let mut to_int = Convert::<Vec2, IVec2, ()>::new(Box::new(Constant::new(Vec2::new(-1.5, 2.9))));
+    assert_eq!(evaluator.evaluate(&mut to_int, ()), IVec2::new(-1, 2));
+    let mut to_float = Convert::<u32, f32, ()>::new(Box::new(Constant::new(7u32)));
+    assert_eq!(evaluator.evaluate(&mut to_float, ()), 7.0);
+    let mut bitcast = Convert::<i32, u32, ()>::new(Box::new(Constant::new(-1)));
+    assert_eq!(evaluator.evaluate(&mut bitcast, ()), u32::MAX);
+}
+
+#[test]
+fn compare_and_reduce() {
+    let mut evaluator = Evaluator::new();
+
+    let mut less = Less::<(), Vec2> {
+        a: Box::new(Constant::new(Vec2::new(1.0, 3.0))),
+        b: Box::new(Constant::new(Vec2::new(2.0, 2.0))),
+    };
+    assert_eq!(evaluator.evaluate(&mut less, ()), BVec2::new(true, false));
+
+    let mut any = Any::<(), BVec2> {
+        inner: Box::new(Constant::new(BVec2::new(true, false))),
+    };
+    let mut all = All::<(), BVec2> {
+        inner: Box::new(Constant::new(BVec2::new(true, false))),
+    };
+    assert!(evaluator.evaluate(&mut any, ()));
+    assert!(!evaluator.evaluate(&mut all, ()));
+
+    let mut not_equal = NotEqual::<(), f32> {
+        a: Box::new(Constant::new(f32::NAN)),
+        b: Box::new(Constant::new(f32::NAN)),
+    };
+    assert!(evaluator.evaluate(&mut not_equal, ()));
+}
+
+#[test]
+fn loop_carries_value() {
+    let mut evaluator = Evaluator::new();
+
+    //Sum of 0..n
+    let mut sum = Loop::<(), u32>::new(
+        Box::new(Variable::new("n", 0u32)),
+        Box::new(Constant::new(0u32)),
+        Box::new(Addition {
+            a: Box::new(AccessResult::<u32>::new(Loop::<(), u32>::DEFAULT_VALUE_NAME)),
+            b: Box::new(AccessResult::<u32>::new(Loop::<(), u32>::DEFAULT_INDEX_NAME)),
+        }),
+    );
+    //no iteration returns the initial value
+    assert_eq!(evaluator.evaluate(&mut sum, ()), 0);
+    evaluator.set_variable("n", 10u32);
+    assert_eq!(evaluator.evaluate(&mut sum, ()), 45);
+}
+
+#[test]
+fn results_are_shadowed_and_defaulted() {
+    let mut evaluator = Evaluator::new();
+
+    //"x" is shadowed by a result of another type, the innermost one is used.
+    let mut shadowed = OrderedOperations::<(), u32>::new("x", Box::new(Constant::new(1u32)))
+        .push("x", Box::new(Constant::new(2.0f32)))
+        .push("y", Box::new(AccessResult::<f32>::new("x")));
+    assert_eq!(evaluator.evaluate(&mut shadowed, ()), 2.0);
+
+    let mut defaulted = OrderedOperations::<(), f32>::new("a", Box::new(Constant::new(2.0f32)))
+        .push(
+            "sum",
+            Box::new(Addition {
+                a: Box::new(AccessOrDefault::new("a", 0.0f32)),
+                b: Box::new(AccessOrDefault::new("missing", 3.0f32)),
+            }),
+        );
+    assert_eq!(evaluator.evaluate(&mut defaulted, ()), 5.0);
+
+    //Results of the loop body are not visible after the loop.
+    let mut scoped = OrderedOperations::<(), u32>::new(
+        "sum",
+        Box::new(Loop::<ResultContext, u32>::new(
+            Box::new(Constant::new(3u32)),
+            Box::new(Constant::new(0u32)),
+            Box::new(AccessResult::<u32>::new(
+                Loop::<(), u32>::DEFAULT_INDEX_NAME,
+            )),
+        )),
+    )
+    .push(
+        "result",
+        Box::new(Addition {
+            a: Box::new(AccessResult::<u32>::new("sum")),
+            b: Box::new(AccessOrDefault::new(
+                Loop::<(), u32>::DEFAULT_INDEX_NAME,
+                10u32,
+            )),
+        }),
+    );
+    assert_eq!(evaluator.evaluate(&mut scoped, ()), 12);
+}
+
+#[test]
+fn let_bindings() {
+    let mut evaluator = Evaluator::new();
+
+    //(x + 1) * (x + 1) + y, where y = 2 * (x + 1) is bound within the body of x + 1.
+    let mut bound = Let::<(), f32, f32>::new(
+        "x1",
+        Box::new(Addition {
+            a: Box::new(Variable::new("x", 0.0f32)),
+            b: Box::new(Constant::new(1.0f32)),
+        }),
+        |x1| {
+            Box::new(Let::<ResultContext, f32, f32>::new(
+                "y",
+                Box::new(Multiplication {
+                    a: Box::new(Constant::new(2.0f32)),
+                    b: Box::new(x1),
+                }),
+                |y| {
+                    Box::new(Addition {
+                        a: Box::new(Multiplication {
+                            a: Box::new(x1),
+                            b: Box::new(x1),
+                        }),
+                        b: Box::new(y),
+                    })
+                },
+            ))
+        },
+    );
+    assert_eq!(evaluator.evaluate(&mut bound, ()), 3.0);
+    evaluator.set_variable("x", 2.0f32);
+    assert_eq!(evaluator.evaluate(&mut bound, ()), 15.0);
+}
+
+#[test]
+fn matrix_transform() {
+    let mut evaluator = Evaluator::new();
+    let transform = Mat3::from_scale_angle_translation(Vec2::new(2.0, 2.0), 0.0, Vec2::new(1.0, 0.0));
+
+    let mut transformed = MatrixTimesVector::<Mat3, Vec3, ()> {
+        matrix: Box::new(MatrixInverse::<Mat3, ()> {
+            inner: Box::new(Constant::new(transform)),
+        }),
+        vector: Box::new(Constant::new(Vec3::new(3.0, 2.0, 1.0))),
+    };
+    assert_eq!(evaluator.evaluate(&mut transformed, ()), Vec3::new(1.0, 1.0, 1.0));
+
+    //row vector times matrix is the same as transforming by the transposed matrix
+    let mut row = VectorTimesMatrix::<Mat3, Vec3, ()> {
+        vector: Box::new(Constant::new(Vec3::new(1.0, 2.0, 3.0))),
+        matrix: Box::new(Constant::new(transform)),
+    };
+    assert_eq!(
+        evaluator.evaluate(&mut row, ()),
+        transform.transpose() * Vec3::new(1.0, 2.0, 3.0)
+    );
+
+    let mut det = Determinant::<Mat3, ()> {
+        inner: Box::new(Constant::new(transform)),
+    };
+    assert_eq!(evaluator.evaluate(&mut det, ()), 4.0);
+}
+
+#[test]
+fn geometric_vector_operations() {
+    let mut evaluator = Evaluator::new();
+
+    let mut cross = Cross::<()> {
+        a: Box::new(Constant::new(Vec3::X)),
+        b: Box::new(Constant::new(Vec3::Y)),
+    };
+    assert_eq!(evaluator.evaluate(&mut cross, ()), Vec3::Z);
+
+    let mut dot = Dot::<Vec3, ()> {
+        a: Box::new(Constant::new(Vec3::new(1.0, 2.0, 3.0))),
+        b: Box::new(Constant::new(Vec3::new(1.0, 2.0, 3.0))),
+    };
+    assert_eq!(evaluator.evaluate(&mut dot, ()), 14.0);
+
+    let mut reflect = Reflect::<Vec2, ()> {
+        incident: Box::new(Constant::new(Vec2::new(1.0, -1.0))),
+        normal: Box::new(Constant::new(Vec2::Y)),
+    };
+    assert_eq!(evaluator.evaluate(&mut reflect, ()), Vec2::new(1.0, 1.0));
+
+    //Leaving a dense medium at a flat angle results in total internal reflection
+    let mut refract = Refract::<Vec2, ()> {
+        incident: Box::new(Constant::new(Vec2::new(1.0, -0.1).normalize())),
+        normal: Box::new(Constant::new(Vec2::Y)),
+        eta: Box::new(Constant::new(1.5f32)),
+    };
+    assert_eq!(evaluator.evaluate(&mut refract, ()), Vec2::ZERO);
+
+    let mut face_forward = FaceForward::<Vec2, ()> {
+        normal: Box::new(Constant::new(Vec2::Y)),
+        incident: Box::new(Constant::new(Vec2::Y)),
+        reference: Box::new(Constant::new(Vec2::Y)),
+    };
+    assert_eq!(evaluator.evaluate(&mut face_forward, ()), -Vec2::Y);
+}
+
+#[test]
+fn extended_float_operations() {
+    let mut evaluator = Evaluator::new();
+
+    let mut fract = Fract::<(), Vec2> {
+        inner: Box::new(Constant::new(Vec2::new(1.25, -0.25))),
+    };
+    assert_eq!(evaluator.evaluate(&mut fract, ()), Vec2::new(0.25, 0.75));
+
+    //GLSL's mod takes the sign of the divisor
+    let mut modulo = Modulo::<(), f32> {
+        dividend: Box::new(Constant::new(-1.0f32)),
+        divisor: Box::new(Constant::new(3.0f32)),
+    };
+    assert_eq!(evaluator.evaluate(&mut modulo, ()), 2.0);
+
+    let mut sign = Sign::<(), Vec3> {
+        inner: Box::new(Constant::new(Vec3::new(-2.0, 0.0, 3.0))),
+    };
+    assert_eq!(evaluator.evaluate(&mut sign, ()), Vec3::new(-1.0, 0.0, 1.0));
+
+    let mut smooth = SmoothStep::<(), f32> {
+        edge0: Box::new(Constant::new(0.0f32)),
+        edge1: Box::new(Constant::new(2.0f32)),
+        x: Box::new(Constant::new(1.0f32)),
+    };
+    assert_eq!(evaluator.evaluate(&mut smooth, ()), 0.5);
+
+    let mut mix = Mix::<(), Vec2> {
+        a: Box::new(Constant::new(Vec2::ZERO)),
+        b: Box::new(Constant::new(Vec2::new(2.0, 4.0))),
+        t: Box::new(Constant::new(Vec2::splat(0.25))),
+    };
+    assert_eq!(evaluator.evaluate(&mut mix, ()), Vec2::new(0.5, 1.0));
+
+    let mut atan2 = ArcTangent2::<(), f32> {
+        y: Box::new(Constant::new(1.0f32)),
+        x: Box::new(Constant::new(-1.0f32)),
+    };
+    assert_eq!(
+        evaluator.evaluate(&mut atan2, ()),
+        3.0 * std::f32::consts::FRAC_PI_4
+    );
+}
+
+#[test]
+fn construct_and_swizzle() {
+    let mut evaluator = Evaluator::new();
+
+    //vec2(length(p.xz), p.y)
+    let mut op = VecConstruct::<Vec2, ()>::new(vec![
+        Box::new(Length {
+            inner: Box::new(Swizzle::<Vec3, Vec2, ()>::new(
+                "xz",
+                Box::new(Variable::new("p", Vec3::ZERO)),
+            )),
+        }),
+        Box::new(VecSelectElement {
+            element: 1,
+            inner: Box::new(Variable::<(), Vec3>::new("p", Vec3::ZERO)),
+        }),
+    ]);
+    evaluator.set_variable("p", Vec3::new(3.0, 2.0, 4.0));
+    assert_eq!(evaluator.evaluate(&mut op, ()), Vec2::new(5.0, 2.0));
+
+    let mut swizzle = Swizzle::<Vec2, Vec4, ()>::new(
+        "xxyy",
+        Box::new(Splat::<Vec2, ()>::new(Box::new(Constant::new(1.0f32)))),
+    );
+    assert_eq!(evaluator.evaluate(&mut swizzle, ()), Vec4::ONE);
+}
+
+#[test]
+fn scalar_broadcasting() {
+    let mut evaluator = Evaluator::new();
+    evaluator.set_variable("s", 2.0f32);
+
+    let mut scaled = VectorTimesScalar::<(), Vec3> {
+        vector: Box::new(Constant::new(Vec3::new(1.0, 2.0, 3.0))),
+        scalar: Box::new(Variable::new("s", 1.0f32)),
+    };
+    assert_eq!(evaluator.evaluate(&mut scaled, ()), Vec3::new(2.0, 4.0, 6.0));
+
+    let mut offset = VectorMinusScalar::<(), Vec2> {
+        vector: Box::new(Constant::new(Vec2::new(1.0, 2.0))),
+        scalar: Box::new(Variable::new("s", 1.0f32)),
+    };
+    assert_eq!(evaluator.evaluate(&mut offset, ()), Vec2::new(-1.0, 0.0));
+
+    let mut divided = VectorDividedByScalar::<(), Vec4> {
+        vector: Box::new(Constant::new(Vec4::ONE)),
+        scalar: Box::new(Variable::new("s", 1.0f32)),
+    };
+    assert_eq!(evaluator.evaluate(&mut divided, ()), Vec4::splat(0.5));
+}
+
+#[test]
+fn dynamic_operations_are_type_checked() {
+    let p = || DynOperation::variable("p", Vec3::ZERO);
+
+    //length(p.xy * 2.0) + p.z
+    let scaled = DynOperation::new(
+        DynOp::Mul,
+        vec![
+            DynOperation::new(DynOp::Swizzle(vec![0, 1]), vec![p()]).unwrap(),
+            DynOperation::constant(2.0f32),
+        ],
+    )
+    .unwrap();
+    let length = DynOperation::new(DynOp::Length, vec![scaled]).unwrap();
+    let mut sum = DynOperation::new(
+        DynOp::Add,
+        vec![
+            length,
+            DynOperation::new(DynOp::Element(2), vec![p()]).unwrap(),
+        ],
+    )
+    .unwrap();
+    assert_eq!(sum.ty(), &f32::into_spv_type());
+
+    let mut evaluator = Evaluator::new();
+    evaluator.set_variable("p", Vec3::new(3.0, 4.0, 1.0));
+    assert_eq!(evaluator.evaluate_dyn(&mut sum), DynValue::F32(11.0));
+
+    //Also usable within typed operations
+    let mut typed = Subtraction {
+        minuent: Box::new(sum.clone().into_typed::<(), f32>().unwrap()),
+        subtrahend: Box::new(Constant::new(1.0f32)),
+    };
+    assert_eq!(evaluator.evaluate(&mut typed, ()), 10.0);
+    assert!(matches!(
+        sum.into_typed::<(), Vec3>(),
+        Err(DynError::TypeMismatch { .. })
+    ));
+
+    assert!(matches!(
+        DynOperation::new(
+            DynOp::Add,
+            vec![DynOperation::constant(1.0f32), DynOperation::constant(1u32)]
+        ),
+        Err(DynError::UnsupportedTypes { .. })
+    ));
+    assert!(matches!(
+        DynOperation::new(DynOp::Swizzle(vec![0, 3]), vec![p()]),
+        Err(DynError::UnsupportedTypes { .. })
+    ));
+    assert!(matches!(
+        DynOperation::new(DynOp::Length, vec![p(), p()]),
+        Err(DynError::ArgumentCount {
+            expected: 1,
+            found: 2,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn parse_formula() {
+    let parser = Parser::new()
+        .with_variable("p", Vec3::ZERO)
+        .with_variable("b", Vec3::ONE)
+        .with_variable("d", Vec2::ZERO);
+    let mut operation = parser
+        .parse("length(max(abs(p) - b, 0.0)) + min(max(d.x, d.y), 0.0)")
+        .unwrap();
+    assert_eq!(operation.ty(), &f32::into_spv_type());
+
+    let mut evaluator = Evaluator::new();
+    evaluator.set_variable("p", Vec3::new(-4.0, 0.0, 5.0));
+    evaluator.set_variable("d", Vec2::new(-1.0, -2.0));
+    assert_eq!(evaluator.evaluate_dyn(&mut operation), DynValue::F32(4.0));
+
+    //Precedence, unary minus, |abs| and swizzles
+    let mut operation = parser.parse("-2 * |p.zx - 1| .y + 3 % 2").unwrap();
+    assert_eq!(evaluator.evaluate_dyn(&mut operation), DynValue::F32(-9.0));
+    let mut operation = parser.parse("vec2(1.5e1, -.5e0 * 0) + d.yx").unwrap();
+    assert_eq!(
+        evaluator.evaluate_dyn(&mut operation),
+        DynValue::Vec2(Vec2::new(13.0, -1.0))
+    );
+}
+
+#[test]
+fn parse_errors_have_positions() {
+    let parser = Parser::new().with_variable("p", Vec3::ZERO);
+    let error = |source| {
+        let error = parser.parse(source).unwrap_err();
+        (error.line, error.column, error.kind)
+    };
+
+    assert!(matches!(
+        error("length(p) +\n   q"),
+        (2, 4, ParseErrorKind::UnknownVariable(_))
+    ));
+    assert!(matches!(
+        error("lenght(p)"),
+        (1, 1, ParseErrorKind::UnknownFunction(_))
+    ));
+    assert!(matches!(
+        error("p.xq"),
+        (1, 3, ParseErrorKind::InvalidSwizzle(_))
+    ));
+    assert!(matches!(
+        error("max(p, 1.0) + 1u"),
+        (1, 15, ParseErrorKind::InvalidNumber(_))
+    ));
+    assert!(matches!(
+        error("p + p.xy"),
+        (
+            1,
+            3,
+            ParseErrorKind::Type(DynError::UnsupportedTypes { .. })
+        )
+    ));
+    assert!(matches!(
+        error("(p.x + 1"),
+        (1, 9, ParseErrorKind::UnexpectedEnd { .. })
+    ));
+    assert!(matches!(
+        error("p.x $"),
+        (1, 5, ParseErrorKind::UnexpectedCharacter('$'))
+    ));
+    assert_eq!(
+        parser.parse("p.x p").unwrap_err().to_string(),
+        "1:5: Expected end of formula, found 'p'"
+    );
+}
+
+#[test]
+fn deeply_nested_formula_is_an_error() {
+    let parser = Parser::new().with_variable("p", Vec3::ZERO);
+    let nested = |open: &str, close: &str, depth| {
+        format!("{}p.x{}", open.repeat(depth), close.repeat(depth))
+    };
+
+    //The outermost expression is the first level
+    assert!(parser.parse(&nested("(", ")", 255)).is_ok());
+    for formula in [
+        nested("(", ")", 1000),
+        nested("|", "|", 1000),
+        nested("abs(", ")", 1000),
+        nested("-", "", 1000),
+    ] {
+        let error = parser.parse(&formula).unwrap_err();
+        assert!(
+            matches!(error.kind, ParseErrorKind::TooDeep(256)),
+            "{}",
+            error
+        );
+        assert_eq!(error.line, 1);
+    }
+    let error = parser.parse(&nested("(", ")", 1000)).unwrap_err();
+    assert_eq!(error.column, 257);
+}
+
+#[test]
+fn gradient_matches_central_differences() {
+    let parser = Parser::new().with_variable("p", Vec3::ZERO);
+    let formulas = [
+        "length(max(abs(p) - vec3(1.0, 0.5, 0.25), 0.0)) + min(max(p.x, max(p.y, p.z)), 0.0)",
+        "sin(p.x) * cos(p.y) + tan(p.z * 0.5) + pow(abs(p.z), 3.0) + pow(2.0, p.y)",
+        "smoothstep(-1.0, 1.0, p.x) / (2.0 + p.y * p.y) - clamp(p.z, -0.5, 0.5)",
+        "atan2(p.y, p.x) + exp(p.z) + log2(2.0 + p.x * p.x) + sqrt(1.0 + dot(p, p))",
+        "dot(normalize(p + 1.0), cross(p, vec3(0.0, 1.0, 2.0))) + distance(p.zxy, p) * 0.5",
+        "length(mix(p, p * p, 0.25) - reflect(p, vec3(0.0, 1.0, 0.0))) + mod(p.x, 0.7)",
+        "mix(p.x, p.y * p.y, p.z) + smoothstep(p.y - 1.0, 2.0 + p.z, p.x) + fma(p.x, p.y, p.z)",
+        "reflect(p, normalize(p.yzx + 2.0)).x + distance(p, p * 0.5 + 1.0)",
+        "faceforward(p * p, p, vec3(0.0, 1.0, 0.0)).x + length(normalize(reflect(p * 2.0, p)))",
+    ];
+    let points = [
+        Vec3::new(0.3, -1.2, 0.8),
+        Vec3::new(-2.0, 0.1, 0.45),
+        Vec3::new(0.05, 0.2, -0.3),
+    ];
+
+    let mut evaluator = Evaluator::new();
+    let mut eval_at = |operation: &mut DynOperation, p: Vec3| {
+        evaluator.set_variable("p", p);
+        evaluator.evaluate_dyn(operation)
+    };
+
+    for formula in formulas {
+        let mut f = parser.parse(formula).unwrap();
+        let mut gradient = Gradient::new("p").apply(&f).unwrap();
+        for p in points {
+            let analytic = match eval_at(&mut gradient, p) {
+                DynValue::Vec3(g) => g,
+                other => panic!("Unexpected gradient {:?}", other),
+            };
+            let h = 1e-3;
+            let mut numeric = Vec3::ZERO;
+            for axis in 0..3 {
+                let mut offset = Vec3::ZERO;
+                offset[axis] = h;
+                match (eval_at(&mut f, p + offset), eval_at(&mut f, p - offset)) {
+                    (DynValue::F32(a), DynValue::F32(b)) => numeric[axis] = (a - b) / (2.0 * h),
+                    other => panic!("Unexpected value {:?}", other),
+                }
+            }
+            assert!(
+                (analytic - numeric).abs().max_element() < 1e-2,
+                "{} at {}: {} != {}",
+                formula,
+                p,
+                analytic,
+                numeric
+            );
+        }
+    }
+
+    let scalar = Parser::new().with_variable("x", 2.0f32);
+    let mut derivative = Gradient::new("x")
+        .apply(&scalar.parse("x * x * x - 4.0 * x").unwrap())
+        .unwrap();
+    assert_eq!(eval_at(&mut derivative, Vec3::ZERO), DynValue::F32(8.0));
+
+    assert!(matches!(
+        Gradient::new("q").apply(&parser.parse("length(p)").unwrap()),
+        Err(DynError::UnknownVariable(_))
+    ));
+    assert!(matches!(
+        Gradient::new("p").apply(&parser.parse("p * 2.0").unwrap()),
+        Err(DynError::TypeMismatch { .. })
+    ));
+    assert!(matches!(
+        Gradient::new("p").apply(&parser.parse("refract(p, p, 1.5).x").unwrap()),
+        Err(DynError::NotDifferentiable { .. })
+    ));
+}
+
+///Number of nodes of `operation`, where shared sub graphs are counted once per use.
+fn node_count(operation: &DynOperation) -> usize {
+    1 + operation.args().iter().map(node_count).sum::<usize>()
+}
+
+#[test]
+fn gradient_of_nested_operations_is_not_exponential() {
+    let parser = Parser::new().with_variable("p", Vec3::ZERO);
+    //Gradient of `f` nested `depth` times into `p`
+    let gradient_size = |f: &str, depth| {
+        let mut formula = String::from("p");
+        for _ in 0..depth {
+            formula = f.replace("{}", &formula);
+        }
+        let f = parser.parse(&format!("length({})", formula)).unwrap();
+        node_count(&Gradient::new("p").apply(&f).unwrap())
+    };
+
+    //Each level copies its arguments a constant number of times, therefore the gradient grows quadratically with the depth.
+    for f in [
+        "mix({}, p.yzx, p.z)",
+        "normalize({})",
+        "reflect({}, normalize(p + 2.0))",
+        "reflect(p, normalize({}))",
+        "faceforward({}, p, p.zxy)",
+        "smoothstep(p - 1.0, p + 2.0, {})",
+    ] {
+        let (shallow, deep) = (gradient_size(f, 6), gradient_size(f, 12));
+        assert!(
+            deep < 4 * shallow,
+            "{}: {} nodes for 6 levels, {} nodes for 12 levels",
+            f,
+            shallow,
+            deep
+        );
+    }
+}
+
+#[test]
+fn bound_values_are_shared() {
+    //let q = p * p; q.x * q.y + q.z
+    let p = DynOperation::variable("p", Vec3::ZERO);
+    let q = || {
+        DynOperation::new(
+            DynOp::Binding {
+                id: 0,
+                ty: Vec3::into_spv_type(),
+            },
+            Vec::new(),
+        )
+        .unwrap()
+    };
+    let element = |i| DynOperation::new(DynOp::Element(i), vec![q()]).unwrap();
+    let body = DynOperation::new(
+        DynOp::Add,
+        vec![
+            DynOperation::new(DynOp::Mul, vec![element(0), element(1)]).unwrap(),
+            element(2),
+        ],
+    )
+    .unwrap();
+    let square = DynOperation::new(DynOp::Mul, vec![p.clone(), p]).unwrap();
+    let mut f = DynOperation::new(DynOp::Let(0), vec![square.clone(), body]).unwrap();
+    let mut gradient = Gradient::new("p").apply(&f).unwrap();
+
+    let mut evaluator = Evaluator::new();
+    evaluator.set_variable("p", Vec3::new(1.0, 2.0, 3.0));
+    assert_eq!(evaluator.evaluate_dyn(&mut f), DynValue::F32(13.0));
+    //(2 * x * y², 2 * x² * y, 2 * z)
+    assert_eq!(
+        evaluator.evaluate_dyn(&mut gradient),
+        DynValue::Vec3(Vec3::new(8.0, 4.0, 6.0))
+    );
+
+    //The binding of a scalar as a vector
+    assert!(matches!(
+        DynOperation::new(
+            DynOp::Let(0),
+            vec![DynOperation::constant(1.0f32), element(0)]
+        ),
+        Err(DynError::TypeMismatch { .. })
+    ));
+    //An inner `Let` of the same id shadows the outer one
+    let inner = DynOperation::new(DynOp::Let(0), vec![square, element(1)]).unwrap();
+    let mut shadowed =
+        DynOperation::new(DynOp::Let(0), vec![DynOperation::constant(1.0f32), inner]).unwrap();
+    assert_eq!(evaluator.evaluate_dyn(&mut shadowed), DynValue::F32(4.0));
+}
+
+///Sphere of radius 1 around the input position.
+fn sphere_sdf() -> Box<Subtraction<DataId<Vec3>, f32>> {
+    Box::new(Subtraction {
+        minuent: Box::new(Length {
+            inner: Box::new(ReturnInput::<Vec3>::new()),
+        }),
+        subtrahend: Box::new(Constant::new(1.0f32)),
+    })
+}
+
+///2D box with the extent (1, 2) at the input position.
+fn box_sdf_2d() -> Box<Length<Vec2, DataId<Vec2>>> {
+    Box::new(Length {
+        inner: Box::new(Max {
+            a: Box::new(Subtraction {
+                minuent: Box::new(algae::operations::Abs {
+                    inner: Box::new(ReturnInput::<Vec2>::new()),
+                }),
+                subtrahend: Box::new(Constant::new(Vec2::new(1.0, 2.0))),
+            }),
+            b: Box::new(Constant::new(Vec2::ZERO)),
+        }),
+    })
+}
+
+#[test]
+fn sampled_normals() {
+    let mut evaluator = Evaluator::new();
+    let position = Vec3::new(1.0, 2.0, -2.0);
+    let exact = position.normalize();
+
+    let mut central = WithInput {
+        input: Box::new(Variable::new("p", position)),
+        inner: Box::new(CentralDifferenceNormal::new(sphere_sdf(), 1e-3)),
+    };
+    let normal = evaluator.evaluate(&mut central, ());
+    assert!(normal.abs_diff_eq(exact, 1e-3), "{}", normal);
+
+    let mut tetrahedron = WithInput {
+        input: Box::new(Variable::new("p", position)),
+        inner: Box::new(TetrahedronNormal::new(sphere_sdf(), 1e-3)),
+    };
+    let normal = evaluator.evaluate(&mut tetrahedron, ());
+    assert!(normal.abs_diff_eq(exact, 1e-3), "{}", normal);
+
+    //Outside of the box' corner the normal points away from the corner
+    let position = Vec2::new(4.0, 6.0);
+    let exact = Vec2::new(3.0, 4.0).normalize();
+    let mut central = WithInput {
+        input: Box::new(Constant::new(position)),
+        inner: Box::new(CentralDifferenceNormal::new(box_sdf_2d(), 1e-3)),
+    };
+    let normal = evaluator.evaluate(&mut central, ());
+    assert!(normal.abs_diff_eq(exact, 1e-3), "{}", normal);
+
+    let mut triangle = WithInput {
+        input: Box::new(Constant::new(position)),
+        inner: Box::new(TetrahedronNormal::new(box_sdf_2d(), 1e-3)),
+    };
+    let normal = evaluator.evaluate(&mut triangle, ());
+    assert!(normal.abs_diff_eq(exact, 1e-3), "{}", normal);
+}
+
+///Distance of `field` at `position`.
+fn distance(
+    evaluator: &mut Evaluator,
+    field: impl Parameter<DataId<Vec3>, f32>,
+    position: Vec3,
+) -> f32 {
+    let mut sampled = WithInput {
+        input: Box::new(Constant::new(position)),
+        inner: field.into_operation(),
+    };
+    evaluator.evaluate(&mut sampled, ())
+}
+
+#[test]
+fn sdf_primitives() {
+    let mut evaluator = Evaluator::new();
+    let mut check =
+        |field: &dyn Fn() -> BoxOperation<DataId<Vec3>, f32>, position, expected: f32| {
+            let result = distance(&mut evaluator, field(), position);
+            assert!(
+                (result - expected).abs() < 1e-5,
+                "{} != {} at {}",
+                result,
+                expected,
+                position
+            );
+        };
+
+    let sphere = || Sphere::new(1.0f32).into_operation();
+    check(&sphere, Vec3::new(0.0, 3.0, 0.0), 2.0);
+    check(&sphere, Vec3::ZERO, -1.0);
+    let cuboid = || Cuboid::new(Vec3::new(1.0, 2.0, 3.0)).into_operation();
+    check(&cuboid, Vec3::new(3.0, 0.0, 0.0), 2.0);
+    check(&cuboid, Vec3::new(3.0, 4.0, 0.0), 8.0f32.sqrt());
+    check(&cuboid, Vec3::ZERO, -1.0);
+    let round_cuboid = || RoundCuboid::new(Vec3::ONE, 0.5f32).into_operation();
+    check(&round_cuboid, Vec3::new(3.0, 0.0, 0.0), 2.0);
+    check(&round_cuboid, Vec3::new(2.0, 2.0, 0.0), 4.5f32.sqrt() - 0.5);
+    let torus = || Torus::new(2.0f32, 0.5f32).into_operation();
+    check(&torus, Vec3::new(0.0, 1.0, 2.0), 0.5);
+    check(&torus, Vec3::ZERO, 1.5);
+    let capsule = || Capsule::new(Vec3::new(0.0, -1.0, 0.0), Vec3::Y, 0.5f32).into_operation();
+    check(&capsule, Vec3::new(0.0, 3.0, 0.0), 1.5);
+    check(&capsule, Vec3::new(2.0, 0.5, 0.0), 1.5);
+    let cylinder = || Cylinder::new(1.0f32, 2.0f32).into_operation();
+    check(&cylinder, Vec3::new(3.0, 0.0, 0.0), 2.0);
+    check(&cylinder, Vec3::new(0.0, 5.0, 0.0), 3.0);
+    check(&cylinder, Vec3::new(2.0, 4.0, 0.0), 5.0f32.sqrt());
+    check(&cylinder, Vec3::ZERO, -1.0);
+    let cone = || Cone::new(1.0f32, 1.0f32).into_operation();
+    check(&cone, Vec3::new(0.0, 1.0, 0.0), 1.0);
+    check(&cone, Vec3::new(0.0, -2.0, 0.0), 1.0);
+    check(&cone, Vec3::new(0.0, -0.5, 0.0), -(0.125f32.sqrt()));
+    check(&cone, Vec3::new(0.0, 0.0, 2.0), 2.0f32.sqrt());
+    let plane = || Plane::new(Vec3::Y, 1.0f32).into_operation();
+    check(&plane, Vec3::new(5.0, 3.0, 0.0), 4.0);
+
+    //Parameters can be calculated at runtime
+    evaluator.set_variable("r", 2.0f32);
+    let sphere = Sphere::new(Variable::new("r", 1.0f32));
+    assert_eq!(
+        distance(&mut evaluator, sphere, Vec3::new(0.0, 3.0, 0.0)),
+        1.0
+    );
+}
+
+#[test]
+fn sdf_combinators_and_domains() {
+    let mut evaluator = Evaluator::new();
+    let spheres = || {
+        (
+            Sphere::new(1.0f32),
+            Translate::new(Sphere::new(1.0f32), Vec3::new(2.0, 0.0, 0.0)),
+        )
+    };
+
+    //Both surfaces touch at (1, 0, 0), where the smooth variants differ by k / 4.
+    let touching = Vec3::X;
+    let far = Vec3::new(-2.0, 0.0, 0.0);
+    for (position, expected) in [
+        (touching, [0.0, 0.0, 0.0, -0.25, 0.25, 0.25]),
+        (far, [1.0, 3.0, 1.0, 1.0, 3.0, 1.0]),
+    ] {
+        let fields: [BoxOperation<DataId<Vec3>, f32>; 6] = [
+            {
+                let (a, b) = spheres();
+                Box::new(Union::new(a, b))
+            },
+            {
+                let (a, b) = spheres();
+                Box::new(Intersection::new(a, b))
+            },
+            {
+                let (a, b) = spheres();
+                Box::new(Difference::new(a, b))
+            },
+            {
+                let (a, b) = spheres();
+                Box::new(SmoothUnion::new(a, b, 1.0f32))
+            },
+            {
+                let (a, b) = spheres();
+                Box::new(SmoothIntersection::new(a, b, 1.0f32))
+            },
+            {
+                let (a, b) = spheres();
+                Box::new(SmoothDifference::new(a, b, 1.0f32))
+            },
+        ];
+        for (field, expected) in fields.into_iter().zip(expected) {
+            assert_eq!(distance(&mut evaluator, field, position), expected);
+        }
+    }
+
+    let translated = Translate::new(Sphere::new(1.0f32), Vec3::new(0.0, 2.0, 0.0));
+    assert_eq!(
+        distance(&mut evaluator, translated, Vec3::new(0.0, 5.0, 0.0)),
+        2.0
+    );
+    //The capsule from the origin to (0, 2, 0) is rotated to (-2, 0, 0)
+    let rotated = || {
+        Rotate::new(
+            Capsule::new(Vec3::ZERO, Vec3::new(0.0, 2.0, 0.0), 0.5f32),
+            Mat3::from_rotation_z(std::f32::consts::FRAC_PI_2),
+        )
+    };
+    assert!((distance(&mut evaluator, rotated(), Vec3::new(-2.0, 0.0, 0.0)) + 0.5).abs() < 1e-5);
+    assert!((distance(&mut evaluator, rotated(), Vec3::new(2.0, 0.0, 0.0)) - 1.5).abs() < 1e-5);
+    let scaled = Scale::new(Sphere::new(1.0f32), 2.0f32);
+    assert_eq!(
+        distance(&mut evaluator, scaled, Vec3::new(0.0, 5.0, 0.0)),
+        3.0
+    );
+    let mirrored = Mirror::new(
+        Translate::new(Sphere::new(1.0f32), Vec3::new(2.0, 0.0, 0.0)),
+        Vec3::X,
+    );
+    assert_eq!(
+        distance(&mut evaluator, mirrored, Vec3::new(-2.0, 0.0, 0.0)),
+        -1.0
+    );
+    let repeated = || Repeat::new(Sphere::new(1.0f32), Vec3::splat(4.0));
+    assert_eq!(
+        distance(&mut evaluator, repeated(), Vec3::new(8.5, 0.0, 0.0)),
+        -0.5
+    );
+    assert_eq!(
+        distance(&mut evaluator, repeated(), Vec3::new(6.0, 0.0, 4.0)),
+        1.0
+    );
+}
+
+///Distance of the 2D `field` at `position`.
+fn distance_2d(
+    evaluator: &mut Evaluator,
+    field: impl Parameter<DataId<Vec2>, f32>,
+    position: Vec2,
+) -> f32 {
+    let mut sampled = WithInput {
+        input: Box::new(Constant::new(position)),
+        inner: field.into_operation(),
+    };
+    evaluator.evaluate(&mut sampled, ())
+}
+
+#[test]
+fn sdf_primitives_2d() {
+    let mut evaluator = Evaluator::new();
+    let mut check =
+        |field: &dyn Fn() -> BoxOperation<DataId<Vec2>, f32>, position, expected: f32| {
+            let result = distance_2d(&mut evaluator, field(), position);
+            assert!(
+                (result - expected).abs() < 1e-5,
+                "{} != {} at {}",
+                result,
+                expected,
+                position
+            );
+        };
+
+    let circle = || Circle::new(1.0f32).into_operation();
+    check(&circle, Vec2::new(0.0, 3.0), 2.0);
+    let rectangle = || Rectangle::new(Vec2::new(1.0, 2.0)).into_operation();
+    check(&rectangle, Vec2::new(3.0, 0.0), 2.0);
+    check(&rectangle, Vec2::new(4.0, 6.0), 5.0);
+    check(&rectangle, Vec2::ZERO, -1.0);
+    let round_rectangle = || RoundRectangle::new(Vec2::ONE, 0.5f32).into_operation();
+    check(&round_rectangle, Vec2::new(2.0, 2.0), 4.5f32.sqrt() - 0.5);
+    let oriented =
+        || OrientedRectangle::new(Vec2::ZERO, Vec2::new(0.0, 4.0), 2.0f32).into_operation();
+    check(&oriented, Vec2::new(3.0, 2.0), 2.0);
+    check(&oriented, Vec2::new(0.0, 6.0), 2.0);
+    check(&oriented, Vec2::new(0.0, 2.0), -1.0);
+    let segment = || Segment::new(Vec2::ZERO, Vec2::new(2.0, 0.0)).into_operation();
+    check(&segment, Vec2::new(1.0, 1.0), 1.0);
+    check(&segment, Vec2::new(-3.0, 4.0), 5.0);
+
+    let corners = [Vec2::ZERO, Vec2::new(4.0, 0.0), Vec2::new(0.0, 3.0)];
+    let triangle = || Triangle::new(corners[0], corners[1], corners[2]).into_operation();
+    let reversed = || Triangle::new(corners[2], corners[1], corners[0]).into_operation();
+    let polygon = || Polygon::new(corners.to_vec()).into_operation();
+    for field in [&triangle as &dyn Fn() -> _, &reversed, &polygon] {
+        check(field, Vec2::new(0.0, -2.0), 2.0);
+        check(field, Vec2::new(-1.0, 1.0), 1.0);
+        check(field, Vec2::new(4.0, 3.0), 12.0 / 5.0);
+        check(field, Vec2::ONE, -1.0);
+    }
+    //L shape with a notch at (1, 1)
+    let l_shape = || {
+        Polygon::new(vec![
+            Vec2::ZERO,
+            Vec2::new(2.0, 0.0),
+            Vec2::new(2.0, 1.0),
+            Vec2::ONE,
+            Vec2::new(1.0, 2.0),
+            Vec2::new(0.0, 2.0),
+        ])
+        .into_operation()
+    };
+    check(&l_shape, Vec2::splat(1.5), 0.5);
+    check(&l_shape, Vec2::new(1.25, 1.25), 0.25);
+    check(&l_shape, Vec2::splat(0.5), -0.5);
+    check(&l_shape, Vec2::new(0.5, 1.75), -0.25);
+
+    let arc = || Arc::new(std::f32::consts::FRAC_PI_2, 2.0f32, 0.25f32).into_operation();
+    check(&arc, Vec2::new(0.0, 3.0), 0.75);
+    check(&arc, Vec2::new(-3.0, -1.0), 2.0f32.sqrt() - 0.25);
+    let ring = || Ring::new(2.0f32, 0.5f32).into_operation();
+    check(&ring, Vec2::ZERO, 1.5);
+    check(&ring, Vec2::new(0.0, -2.0), -0.5);
+
+    //With a density of 2, the star is a regular polygon.
+    let pentagon = || Star::new(1.0f32, 5, 2.0f32).into_operation();
+    check(&pentagon, Vec2::new(0.0, 3.0), 2.0);
+    let apothem = (std::f32::consts::PI / 5.0).cos();
+    check(&pentagon, Vec2::new(0.0, -0.5), 0.5 - apothem);
+    let star = || Star::new(1.0f32, 5, 3.0f32).into_operation();
+    check(&star, Vec2::new(0.0, 3.0), 2.0);
+    check(&star, Vec2::Y, 0.0);
+    assert!(distance_2d(&mut evaluator, star(), Vec2::new(0.0, 0.1)) < 0.0);
+    //The pentagon's edge lies between the tips of the star.
+    assert!(distance_2d(&mut evaluator, star(), Vec2::new(0.0, -apothem)) > 0.0);
+}
+
+#[test]
+fn sdf_2d_composition() {
+    let mut evaluator = Evaluator::new();
+    let rotated = Rotate::new(
+        Segment::new(Vec2::ZERO, Vec2::new(2.0, 0.0)),
+        Mat2::from_angle(std::f32::consts::FRAC_PI_2),
+    );
+    let result = distance_2d(&mut evaluator, rotated, Vec2::new(0.0, 3.0));
+    assert!((result - 1.0).abs() < 1e-5, "{}", result);
+
+    let circles = SmoothUnion::new(
+        Circle::new(1.0f32),
+        Translate::new(Circle::new(1.0f32), Vec2::new(2.0, 0.0)),
+        1.0f32,
+    );
+    assert_eq!(distance_2d(&mut evaluator, circles, Vec2::X), -0.25);
+    let repeated = Repeat::new(Ring::new(1.0f32, 0.5f32), Vec2::splat(4.0));
+    assert_eq!(
+        distance_2d(&mut evaluator, repeated, Vec2::new(8.0, 4.0)),
+        0.5
+    );
+}
@@ -0,0 +1,304 @@
+//! Checks the noise operations against a plain Rust implementation of the same noise.
+use algae::{
+    glam::{Vec2, Vec3},
+    noise::{Fbm, PerlinNoise, SimplexNoise, ValueNoise, WorleyNoise},
+    operations::{Constant, WithInput},
+    sdf::Parameter,
+    DataId, Evaluator,
+};
+
+fn pcg(v: u32) -> u32 {
+    let state = v.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
+
+fn hash(point: &[f32], seed: u32) -> u32 {
+    point
+        .iter()
+        .fold(seed, |hash, c| pcg(hash.wrapping_add(*c as i32 as u32)))
+}
+
+fn random(hash: u32, shift: u32, bits: u32) -> f32 {
+    ((hash >> shift) & ((1 << bits) - 1)) as f32 * (1.0 / (1u32 << bits) as f32)
+}
+
+fn gradient(hash: u32, dimensions: usize) -> Vec<f32> {
+    (0..dimensions as u32)
+        .map(|i| random(hash, i * 10, 10) * 2.0 - 1.0)
+        .collect()
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn mix(a: f32, b: f32, t: f32) -> f32 {
+    a * (1.0 - t) + b * t
+}
+
+///Offsets of the corners of a cell, the offset on axis `i` of corner `c` is bit `i` of `c`.
+fn corners(dimensions: usize) -> Vec<Vec<f32>> {
+    (0..1u32 << dimensions)
+        .map(|c| (0..dimensions).map(|i| ((c >> i) & 1) as f32).collect())
+        .collect()
+}
+
+fn interpolate(values: &[f32], weights: &[f32]) -> f32 {
+    let mut values = values.to_vec();
+    for weight in weights.iter().rev() {
+        let upper = values.split_off(values.len() / 2);
+        values = values
+            .iter()
+            .zip(upper)
+            .map(|(lower, upper)| mix(*lower, upper, *weight))
+            .collect();
+    }
+    values[0]
+}
+
+fn add(a: &[f32], b: &[f32]) -> Vec<f32> {
+    a.iter().zip(b).map(|(a, b)| a + b).collect()
+}
+
+fn value_noise(p: &[f32], seed: u32) -> f32 {
+    let cell: Vec<f32> = p.iter().map(|p| p.floor()).collect();
+    let weights: Vec<f32> = p.iter().zip(&cell).map(|(p, c)| fade(p - c)).collect();
+    let values: Vec<f32> = corners(p.len())
+        .iter()
+        .map(|corner| random(hash(&add(&cell, corner), seed), 8, 24) * 2.0 - 1.0)
+        .collect();
+    interpolate(&values, &weights)
+}
+
+fn perlin_noise(p: &[f32], seed: u32) -> f32 {
+    let cell: Vec<f32> = p.iter().map(|p| p.floor()).collect();
+    let fraction: Vec<f32> = p.iter().zip(&cell).map(|(p, c)| p - c).collect();
+    let weights: Vec<f32> = fraction.iter().map(|f| fade(*f)).collect();
+    let values: Vec<f32> = corners(p.len())
+        .iter()
+        .map(|corner| {
+            let gradient = gradient(hash(&add(&cell, corner), seed), p.len());
+            (0..p.len())
+                .map(|i| gradient[i] * (fraction[i] - corner[i]))
+                .reduce(|a, b| a + b)
+                .unwrap()
+        })
+        .collect();
+    interpolate(&values, &weights) * [2.6, 1.5, 1.15][p.len() - 1]
+}
+
+fn simplex_noise(p: &[f32], seed: u32) -> f32 {
+    let n = p.len();
+    let (skew, unskew, radius, scale) = [
+        (0.0, 0.0, 1.0, 4.0),
+        (0.3660254, 0.21132487, 0.5, 72.0),
+        (1.0 / 3.0, 1.0 / 6.0, 0.5, 64.0),
+    ][n - 1];
+    let s = p.iter().copied().reduce(|a, b| a + b).unwrap() * skew;
+    let cell: Vec<f32> = p.iter().map(|p| (p + s).floor()).collect();
+    let t = cell.iter().copied().reduce(|a, b| a + b).unwrap() * unskew;
+    let first: Vec<f32> = p.iter().zip(&cell).map(|(p, c)| p - c + t).collect();
+    //Axes sorted by decreasing component, ties are broken by the axis index.
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|a, b| first[*b].partial_cmp(&first[*a]).unwrap().then(a.cmp(b)));
+    let mut offset = vec![0.0; n];
+    let mut noise = 0.0;
+    for corner in 0..=n {
+        if corner > 0 {
+            offset[order[corner - 1]] = 1.0;
+        }
+        let gradient = gradient(hash(&add(&cell, &offset), seed), n);
+        let relative: Vec<f32> = (0..n)
+            .map(|i| first[i] - offset[i] + corner as f32 * unskew)
+            .collect();
+        let falloff =
+            (radius - relative.iter().map(|r| r * r).reduce(|a, b| a + b).unwrap()).max(0.0);
+        let falloff = falloff * falloff;
+        let dot = (0..n)
+            .map(|i| gradient[i] * relative[i])
+            .reduce(|a, b| a + b)
+            .unwrap();
+        noise += falloff * falloff * dot;
+    }
+    noise * scale
+}
+
+fn worley_noise(p: &[f32], seed: u32) -> f32 {
+    let n = p.len();
+    let cell: Vec<f32> = p.iter().map(|p| p.floor()).collect();
+    let mut nearest = f32::INFINITY;
+    for neighbour in 0..3u32.pow(n as u32) {
+        let neighbour: Vec<f32> = (0..n as u32)
+            .map(|i| (neighbour / 3u32.pow(i) % 3) as f32 - 1.0)
+            .collect();
+        let hash = hash(&add(&cell, &neighbour), seed);
+        let distance = (0..n)
+            .map(|i| {
+                let delta = neighbour[i] + random(hash, i as u32 * 10, 10) - (p[i] - cell[i]);
+                delta * delta
+            })
+            .reduce(|a, b| a + b)
+            .unwrap();
+        nearest = nearest.min(distance);
+    }
+    nearest.sqrt()
+}
+
+fn fbm(p: &[f32], octaves: u32, noise: impl Fn(&[f32]) -> f32) -> f32 {
+    let (sum, weights) = (0..octaves as i32).fold((0.0, 0.0), |(sum, weights), octave| {
+        let position: Vec<f32> = p.iter().map(|p| p * 2.0f32.powi(octave)).collect();
+        let weight = 0.5f32.powi(octave);
+        (sum + noise(&position) * weight, weights + weight)
+    });
+    sum * (1.0 / weights)
+}
+
+///Positions in `[-8, 8)`, including negative and integer coordinates.
+fn positions() -> impl Iterator<Item = Vec3> {
+    (0..2000).map(|i| {
+        let h = pcg(i);
+        let coordinate = |shift: u32| ((h >> shift) & 0x3ff) as f32 / 64.0 - 8.0;
+        Vec3::new(coordinate(0), coordinate(10), coordinate(20))
+    })
+}
+
+fn sample<V: Copy + algae::spv_fi::IntoSpvType + 'static>(
+    noise: impl Parameter<DataId<V>, f32>,
+    position: V,
+) -> f32 {
+    let mut operation = WithInput {
+        input: Box::new(Constant::new(position)),
+        inner: noise.into_operation(),
+    };
+    Evaluator::new().evaluate(&mut operation, ())
+}
+
+///Checks `noise` at all positions against the `reference`, for all dimensions.
+fn check<N1, N2, N3>(
+    noise_1d: impl Fn() -> N1,
+    noise_2d: impl Fn() -> N2,
+    noise_3d: impl Fn() -> N3,
+    reference: impl Fn(&[f32]) -> f32,
+    range: std::ops::RangeInclusive<f32>,
+) where
+    N1: Parameter<DataId<f32>, f32>,
+    N2: Parameter<DataId<Vec2>, f32>,
+    N3: Parameter<DataId<Vec3>, f32>,
+{
+    for p in positions() {
+        let results = [
+            (sample(noise_1d(), p.x), reference(&[p.x])),
+            (sample(noise_2d(), p.truncate()), reference(&[p.x, p.y])),
+            (sample(noise_3d(), p), reference(&[p.x, p.y, p.z])),
+        ];
+        for (result, expected) in results {
+            assert!(
+                (result - expected).abs() < 1e-5,
+                "{} != {} at {}",
+                result,
+                expected,
+                p
+            );
+            assert!(range.contains(&result), "{} out of range at {}", result, p);
+        }
+    }
+}
+
+#[test]
+fn value_noise_matches_reference() {
+    check(
+        || ValueNoise::<f32>::new(3),
+        || ValueNoise::<Vec2>::new(3),
+        || ValueNoise::<Vec3>::new(3),
+        |p| value_noise(p, 3),
+        -1.0..=1.0,
+    );
+}
+
+#[test]
+fn perlin_noise_matches_reference() {
+    check(
+        || PerlinNoise::<f32>::new(3),
+        || PerlinNoise::<Vec2>::new(3),
+        || PerlinNoise::<Vec3>::new(3),
+        |p| perlin_noise(p, 3),
+        -1.0..=1.0,
+    );
+}
+
+#[test]
+fn simplex_noise_matches_reference() {
+    check(
+        || SimplexNoise::<f32>::new(3),
+        || SimplexNoise::<Vec2>::new(3),
+        || SimplexNoise::<Vec3>::new(3),
+        |p| simplex_noise(p, 3),
+        -1.0..=1.0,
+    );
+}
+
+#[test]
+fn worley_noise_matches_reference() {
+    check(
+        || WorleyNoise::<f32>::new(3),
+        || WorleyNoise::<Vec2>::new(3),
+        || WorleyNoise::<Vec3>::new(3),
+        |p| worley_noise(p, 3),
+        0.0..=3.0f32.sqrt(),
+    );
+}
+
+#[test]
+fn fbm_matches_reference() {
+    check(
+        || Fbm::<f32>::new(PerlinNoise::<f32>::new(1), 4),
+        || Fbm::<Vec2>::new(ValueNoise::<Vec2>::new(1), 4),
+        || Fbm::<Vec3>::new(SimplexNoise::<Vec3>::new(1), 4),
+        |p| match p.len() {
+            1 => fbm(p, 4, |p| perlin_noise(p, 1)),
+            2 => fbm(p, 4, |p| value_noise(p, 1)),
+            _ => fbm(p, 4, |p| simplex_noise(p, 1)),
+        },
+        -1.0..=1.0,
+    );
+}
+
+#[test]
+fn noise_properties() {
+    for x in -3..3 {
+        let point = Vec3::new(x as f32, 2.0, -5.0);
+        //Gradient noise vanishes at lattice points, value noise is the random value of the lattice point.
+        assert_eq!(sample(PerlinNoise::<Vec3>::new(0), point), 0.0);
+        assert_eq!(sample(SimplexNoise::<f32>::new(0), point.x), 0.0);
+        assert_eq!(
+            sample(ValueNoise::<f32>::new(0), point.x),
+            random(hash(&[point.x], 0), 8, 24) * 2.0 - 1.0
+        );
+        //Worley noise is 0 at its feature points.
+        let cell = hash(&[point.x, point.y], 5);
+        let feature = point.truncate() + Vec2::new(random(cell, 0, 10), random(cell, 10, 10));
+        assert_eq!(sample(WorleyNoise::<Vec2>::new(5), feature), 0.0);
+    }
+
+    //Noise is continuous and seeds result in different noise.
+    let p = Vec2::new(0.3, 0.7);
+    let step = Vec2::new(1e-3, 0.0);
+    let difference = |a: f32, b: f32| (a - b).abs();
+    assert!(
+        difference(
+            sample(WorleyNoise::<Vec2>::new(0), p),
+            sample(WorleyNoise::<Vec2>::new(0), p + step)
+        ) <= 1e-3
+    );
+    assert!(
+        difference(
+            sample(SimplexNoise::<Vec2>::new(0), p),
+            sample(SimplexNoise::<Vec2>::new(0), p + step)
+        ) < 1e-2
+    );
+    assert_ne!(
+        sample(ValueNoise::<Vec2>::new(0), p),
+        sample(ValueNoise::<Vec2>::new(1), p)
+    );
+}
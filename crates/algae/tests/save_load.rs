@@ -0,0 +1,74 @@
+#![cfg(feature = "serialization")]
+
+use algae::{
+    dynamic::{DynOp, DynOperation, DynValue},
+    glam::{BVec3, Vec3},
+    spv_fi::IntoSpvType,
+    Evaluator,
+};
+
+///Sphere of radius `r` around the origin, that is only returned if `inside` is all true. Otherwise the distance to the origin,
+/// which is bound, since it is used twice.
+fn sdf() -> DynOperation {
+    let p = DynOperation::variable("p", Vec3::ZERO);
+    let length = DynOperation::new(DynOp::Length, vec![p]).unwrap();
+    let distance = || {
+        DynOperation::new(
+            DynOp::Binding {
+                id: 0,
+                ty: f32::into_spv_type(),
+            },
+            Vec::new(),
+        )
+        .unwrap()
+    };
+    let sphere = DynOperation::new(
+        DynOp::Sub,
+        vec![distance(), DynOperation::variable("r", 1.0f32)],
+    )
+    .unwrap();
+    let inside = DynOperation::new(
+        DynOp::All,
+        vec![DynOperation::constant(BVec3::new(true, true, true))],
+    )
+    .unwrap();
+    let select = DynOperation::new(DynOp::Select, vec![inside, sphere, distance()]).unwrap();
+    DynOperation::new(DynOp::Let(0), vec![length, select]).unwrap()
+}
+
+fn evaluate(operation: &mut DynOperation) -> DynValue {
+    let mut evaluator = Evaluator::new();
+    evaluator.set_variable("p", Vec3::new(0.0, 4.0, 0.0));
+    evaluator.set_variable("r", 0.5f32);
+    evaluator.evaluate_dyn(operation)
+}
+
+#[test]
+fn save_and_load_ron() {
+    let mut operation = sdf();
+    let saved = ron::to_string(&operation).unwrap();
+    let mut loaded: DynOperation = ron::from_str(&saved).unwrap();
+
+    assert_eq!(loaded, operation);
+    assert_eq!(evaluate(&mut loaded), evaluate(&mut operation));
+    assert_eq!(evaluate(&mut loaded), DynValue::F32(3.5));
+}
+
+#[test]
+fn save_and_load_json() {
+    let operation = sdf();
+    let saved = serde_json::to_string(&operation).unwrap();
+    let loaded: DynOperation = serde_json::from_str(&saved).unwrap();
+    assert_eq!(loaded, operation);
+    assert_eq!(loaded.ty(), operation.ty());
+}
+
+#[test]
+fn loading_checks_types() {
+    let saved = r#"(op: Add, args: [
+        (op: Constant(F32(1.0)), args: []),
+        (op: Variable(name: "n", default: U32(1)), args: []),
+    ])"#;
+    let error = ron::from_str::<DynOperation>(saved).unwrap_err();
+    assert!(error.to_string().contains("not implemented"));
+}
@@ -0,0 +1,762 @@
+use std::collections::HashSet;
+
+use algae::{
+    dynamic::{DynOp, DynOperation},
+    glam::{Mat2, Mat3, Vec2, Vec3, Vec4},
+    noise::{Fbm, PerlinNoise, SimplexNoise, ValueNoise, WorleyNoise},
+    operations::{
+        Abs, AccessOrDefault, AccessResult, Addition, Constant, IfElse, Less, Let, Loop, Min,
+        Multiplication, OrderedOperations, ResultContext, Sqrt, Square, Variable, VecSelectElement,
+        WithInput,
+    },
+    rspirv::{
+        dr::{Builder, Module, Operand},
+        spirv::{FunctionControl, Op, Word},
+    },
+    sdf::{
+        Arc, Capsule, Circle, Cone, Cuboid, Cylinder, Difference, Intersection, Mirror,
+        OrientedRectangle, Parameter, Plane, Polygon, Rectangle, Repeat, Ring, Rotate, RoundCuboid,
+        RoundRectangle, Scale, Segment, SmoothDifference, SmoothIntersection, SmoothUnion, Sphere,
+        Star, Torus, Translate, Triangle, Union,
+    },
+    spv_fi::{IntoSpvType, SpvFi},
+    BoxOperation, DataId, Evaluator, Operation, SerializeError, Serializer,
+};
+
+///Serializes `operation` into the body of an otherwise empty function and returns the resulting module.
+fn serialize<O>(operation: &mut dyn Operation<Input = (), Output = DataId<O>>) -> Module {
+    serialize_with_folding(operation, true)
+}
+
+///Like [serialize], but allows disabling folding. Needed, since all variables fall back to their constant default value.
+fn serialize_with_folding<O>(
+    operation: &mut dyn Operation<Input = (), Output = DataId<O>>,
+    fold: bool,
+) -> Module {
+    try_serialize(operation, fold).unwrap().0
+}
+
+///Like [serialize_with_folding], but returns the error if `operation` can't be serialized. Otherwise returns the module and
+/// the id of the operation's result.
+fn try_serialize<O>(
+    operation: &mut dyn Operation<Input = (), Output = DataId<O>>,
+    fold: bool,
+) -> Result<(Module, Word), SerializeError> {
+    let mut builder = Builder::new();
+    let t_void = builder.type_void();
+    let t_function = builder.type_function(t_void, []);
+    builder
+        .begin_function(t_void, None, FunctionControl::NONE, t_function)
+        .unwrap();
+    builder.begin_block(None).unwrap();
+
+    let interface = SpvFi {
+        parameter: Vec::new(),
+    };
+    let mut serializer = Serializer::new(&mut builder, &interface);
+    serializer.set_folding(fold);
+    let result = operation.serialize(&mut serializer, ())?;
+
+    builder.ret().unwrap();
+    builder.end_function().unwrap();
+    Ok((builder.module(), result.id))
+}
+
+#[test]
+fn if_else_is_structured() {
+    let mut op = IfElse::<(), f32> {
+        condition: Box::new(Less::<(), f32> {
+            a: Box::new(Variable::new("x", 0.0f32)),
+            b: Box::new(Constant::new(1.0f32)),
+        }),
+        true_branch: Box::new(Constant::new(2.0f32)),
+        false_branch: Box::new(Constant::new(3.0f32)),
+    };
+
+    let module = serialize(&mut op);
+    let blocks = &module.functions[0].blocks;
+    //header, both branches and the merge block
+    assert_eq!(blocks.len(), 4);
+
+    let header_ops: Vec<Op> = blocks[0]
+        .instructions
+        .iter()
+        .map(|i| i.class.opcode)
+        .collect();
+    assert!(header_ops.ends_with(&[Op::SelectionMerge, Op::BranchConditional]));
+
+    assert_eq!(blocks[3].instructions[0].class.opcode, Op::Phi);
+}
+
+#[test]
+fn loop_is_structured() {
+    let mut op = Loop::<(), f32>::new(
+        Box::new(Variable::new("octaves", 4u32)),
+        Box::new(Constant::new(0.0f32)),
+        Box::new(Addition {
+            a: Box::new(AccessResult::<f32>::new(
+                Loop::<(), f32>::DEFAULT_VALUE_NAME,
+            )),
+            b: Box::new(Constant::new(0.5f32)),
+        }),
+    );
+
+    let module = serialize(&mut op);
+    let blocks = &module.functions[0].blocks;
+    //entry, header, body, continue and merge block
+    assert_eq!(blocks.len(), 5);
+
+    let header_ops: Vec<Op> = blocks[1]
+        .instructions
+        .iter()
+        .map(|i| i.class.opcode)
+        .collect();
+    assert_eq!(&header_ops[..2], &[Op::Phi, Op::Phi]);
+    assert!(header_ops.ends_with(&[Op::LoopMerge, Op::BranchConditional]));
+
+    //continue block branches back to the header
+    let continue_ops = &blocks[3].instructions;
+    assert_eq!(continue_ops.last().unwrap().class.opcode, Op::Branch);
+    assert_eq!(
+        continue_ops.last().unwrap().operands[0],
+        Operand::IdRef(blocks[1].label.as_ref().unwrap().result_id.unwrap())
+    );
+}
+
+#[test]
+fn vector_arithmetic_is_native() {
+    let mut op = Addition::<(), Vec4> {
+        a: Box::new(Variable::new("a", Vec4::ONE)),
+        b: Box::new(Constant::new(Vec4::ONE)),
+    };
+
+    let module = serialize_with_folding(&mut op, false);
+    let ops: Vec<Op> = module.functions[0].blocks[0]
+        .instructions
+        .iter()
+        .map(|i| i.class.opcode)
+        .collect();
+    assert_eq!(ops.iter().filter(|op| **op == Op::FAdd).count(), 1);
+    assert!(!ops.contains(&Op::CompositeExtract));
+    assert!(!ops.contains(&Op::CompositeConstruct));
+}
+
+#[test]
+fn identical_values_are_reused() {
+    //(x * 2) + (x * 2), where both constants and both multiplications should be emitted only once.
+    let mut op = Addition::<(), f32> {
+        a: Box::new(Multiplication {
+            a: Box::new(Variable::new("x", 1.0f32)),
+            b: Box::new(Constant::new(2.0f32)),
+        }),
+        b: Box::new(Multiplication {
+            a: Box::new(Variable::new("x", 1.0f32)),
+            b: Box::new(Constant::new(2.0f32)),
+        }),
+    };
+
+    let module = serialize_with_folding(&mut op, false);
+    let ops: Vec<Op> = module.functions[0].blocks[0]
+        .instructions
+        .iter()
+        .map(|i| i.class.opcode)
+        .collect();
+    assert_eq!(ops, [Op::FMul, Op::FAdd, Op::Return]);
+
+    let constants = module
+        .types_global_values
+        .iter()
+        .filter(|i| i.class.opcode == Op::Constant)
+        .count();
+    //1.0 as default value of x and 2.0
+    assert_eq!(constants, 2);
+}
+
+#[test]
+fn branch_values_are_not_reused_after_merge() {
+    let sqrt = || -> Box<Sqrt<()>> {
+        Box::new(Sqrt {
+            inner: Box::new(Variable::new("x", 1.0f32)),
+        })
+    };
+    let mut op = Addition::<(), f32> {
+        a: Box::new(IfElse::<(), f32> {
+            condition: Box::new(Constant::new(true)),
+            true_branch: sqrt(),
+            false_branch: sqrt(),
+        }),
+        b: sqrt(),
+    };
+
+    let module = serialize_with_folding(&mut op, false);
+    let sqrt_count = module.functions[0]
+        .blocks
+        .iter()
+        .flat_map(|b| b.instructions.iter())
+        .filter(|i| i.class.opcode == Op::ExtInst)
+        .count();
+    //Once per branch, once after the merge
+    assert_eq!(sqrt_count, 3);
+}
+
+///Returns the opcodes of all instructions within the function's blocks.
+fn function_ops(module: &Module) -> Vec<Op> {
+    module.functions[0]
+        .blocks
+        .iter()
+        .flat_map(|b| b.instructions.iter())
+        .map(|i| i.class.opcode)
+        .collect()
+}
+
+#[test]
+fn constant_subtrees_are_folded() {
+    //sqrt(2 * 8) + 1
+    let mut op = Addition::<(), f32> {
+        a: Box::new(Sqrt {
+            inner: Box::new(Multiplication {
+                a: Box::new(Constant::new(2.0f32)),
+                b: Box::new(Constant::new(8.0f32)),
+            }),
+        }),
+        b: Box::new(Constant::new(1.0f32)),
+    };
+
+    let module = serialize(&mut op);
+    assert_eq!(function_ops(&module), [Op::Return]);
+    assert!(module.types_global_values.iter().any(|i| {
+        i.class.opcode == Op::Constant && i.operands[0] == Operand::LiteralFloat32(5.0)
+    }));
+}
+
+#[test]
+fn identities_are_simplified() {
+    //The loop value is the only non constant value, so nothing except the identities is simplified.
+    let value = || {
+        Box::new(AccessResult::<f32>::new(
+            Loop::<(), f32>::DEFAULT_VALUE_NAME,
+        ))
+    };
+    let mut op = Loop::<(), f32>::new(
+        Box::new(Variable::new("octaves", 4u32)),
+        Box::new(Constant::new(1.0f32)),
+        //min(sqrt(square(abs(abs(x * 1 + -0)))), sqrt(square(abs(x))))
+        Box::new(Min::<ResultContext, f32> {
+            a: Box::new(Sqrt {
+                inner: Box::new(Square {
+                    inner: Box::new(Abs {
+                        inner: Box::new(Abs {
+                            inner: Box::new(Addition {
+                                a: Box::new(Multiplication {
+                                    a: value(),
+                                    b: Box::new(Constant::new(1.0f32)),
+                                }),
+                                b: Box::new(Constant::new(-0.0f32)),
+                            }),
+                        }),
+                    }),
+                }),
+            }),
+            b: Box::new(Sqrt {
+                inner: Box::new(Square {
+                    inner: Box::new(Abs { inner: value() }),
+                }),
+            }),
+        }),
+    );
+
+    let module = serialize(&mut op);
+    let ops = function_ops(&module);
+    //Only abs(x) and the square remain, since sqrt(square(abs(x))) = abs(abs(x)) = abs(x) and min(x, x) = x
+    assert_eq!(ops.iter().filter(|op| **op == Op::ExtInst).count(), 1);
+    assert_eq!(ops.iter().filter(|op| **op == Op::FMul).count(), 1);
+    assert!(!ops.contains(&Op::FAdd));
+}
+
+#[test]
+fn positive_zero_addend_is_kept() {
+    //-0 + 0 is +0, therefore x + 0 is not x for every x.
+    let mut op = Loop::<(), f32>::new(
+        Box::new(Variable::new("octaves", 4u32)),
+        Box::new(Constant::new(-0.0f32)),
+        Box::new(Addition::<ResultContext, f32> {
+            a: Box::new(AccessResult::<f32>::new(
+                Loop::<(), f32>::DEFAULT_VALUE_NAME,
+            )),
+            b: Box::new(Constant::new(0.0f32)),
+        }),
+    );
+
+    let module = serialize(&mut op);
+    assert!(function_ops(&module).contains(&Op::FAdd));
+}
+
+#[test]
+fn dynamic_operation_is_serialized() {
+    //dot(p, p) - 1
+    let p = || DynOperation::variable("p", Vec3::ONE);
+    let dot = DynOperation::new(DynOp::Dot, vec![p(), p()]).unwrap();
+    let mut op = DynOperation::new(DynOp::Sub, vec![dot, DynOperation::constant(1.0f32)])
+        .unwrap()
+        .into_typed::<(), f32>()
+        .unwrap();
+
+    let module = serialize_with_folding(&mut op, false);
+    assert_eq!(function_ops(&module), [Op::Dot, Op::FSub, Op::Return]);
+}
+
+#[test]
+fn unknown_result_is_an_error() {
+    let mut op =
+        OrderedOperations::<(), f32>::new("a", Box::new(AccessResult::<f32>::new("missing")));
+
+    match try_serialize(&mut op, true) {
+        Err(SerializeError::UnknownResult { name, .. }) => assert_eq!(name, "missing"),
+        other => panic!("Expected unknown result, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn failed_chain_can_be_serialized_again() {
+    let mut op =
+        OrderedOperations::<(), f32>::new("a", Box::new(AccessResult::<f32>::new("missing")));
+
+    for _ in 0..2 {
+        assert!(matches!(
+            try_serialize(&mut op, true),
+            Err(SerializeError::UnknownResult { .. })
+        ));
+    }
+}
+
+#[test]
+fn mismatched_result_type_is_an_error() {
+    let mut op = OrderedOperations::<(), u32>::new("a", Box::new(Constant::new(1u32)))
+        .push("b", Box::new(AccessResult::<f32>::new("a")));
+
+    let error = try_serialize(&mut op, true).unwrap_err();
+    match &error {
+        SerializeError::TypeMismatch {
+            name,
+            expected,
+            found,
+            ..
+        } => {
+            assert_eq!(name, "a");
+            assert_eq!(expected, "f32");
+            assert_eq!(found, "u32");
+        }
+        other => panic!("Expected type mismatch, got {:?}", other),
+    }
+    assert!(error
+        .to_string()
+        .contains("expected result a of type f32, but found u32"));
+}
+
+#[test]
+fn shadowed_result_is_not_a_fallback() {
+    let mut op = OrderedOperations::<(), u32>::new("a", Box::new(Constant::new(1u32)))
+        .push("a", Box::new(Constant::new(2.0f32)))
+        .push("b", Box::new(AccessOrDefault::new("a", 0u32)));
+
+    assert!(matches!(
+        try_serialize(&mut op, true),
+        Err(SerializeError::TypeMismatch { .. })
+    ));
+}
+
+#[test]
+fn selecting_missing_element_is_an_error() {
+    let mut op = VecSelectElement::<Vec2, ()> {
+        element: 2,
+        inner: Box::new(Constant::new(Vec2::ONE)),
+    };
+
+    match try_serialize(&mut op, true) {
+        Err(SerializeError::ElementOutOfBounds {
+            element, elements, ..
+        }) => assert_eq!((element, elements), (2, 2)),
+        other => panic!(
+            "Expected element out of bounds, got {:?}",
+            other.map(|_| ())
+        ),
+    }
+}
+
+#[test]
+fn serializing_outside_of_a_block_is_an_error() {
+    let mut builder = Builder::new();
+    let interface = SpvFi {
+        parameter: Vec::new(),
+    };
+    let mut serializer = Serializer::new(&mut builder, &interface);
+    serializer.set_folding(false);
+
+    let mut op = Addition::<(), f32> {
+        a: Box::new(Constant::new(1.0f32)),
+        b: Box::new(Constant::new(2.0f32)),
+    };
+    assert!(matches!(
+        op.serialize(&mut serializer, ()),
+        Err(SerializeError::Builder { .. })
+    ));
+}
+
+#[test]
+fn escaped_binding_is_an_error() {
+    let mut escaped = None;
+    let _bound = Let::<(), f32, f32>::new("d", Box::new(Constant::new(1.0f32)), |d| {
+        escaped = Some(d);
+        Box::new(d)
+    });
+    let mut op = OrderedOperations::<(), f32>::new("e", Box::new(escaped.unwrap()));
+
+    assert!(matches!(
+        try_serialize(&mut op, true),
+        Err(SerializeError::UnknownResult { .. })
+    ));
+}
+
+///Checks that each id the function refers to is defined before, either globally or within the function. Phis may refer to
+/// values of later blocks, therefore their operands are skipped.
+fn assert_ids_defined(module: &Module) {
+    let function = &module.functions[0];
+    let mut defined: HashSet<Word> = module
+        .ext_inst_imports
+        .iter()
+        .chain(&module.types_global_values)
+        .filter_map(|i| i.result_id)
+        .chain(
+            function
+                .blocks
+                .iter()
+                .filter_map(|b| b.label.as_ref()?.result_id),
+        )
+        .collect();
+    for instruction in function.blocks.iter().flat_map(|b| &b.instructions) {
+        if instruction.class.opcode != Op::Phi {
+            let ids = instruction
+                .operands
+                .iter()
+                .filter_map(Operand::id_ref_any)
+                .chain(instruction.result_type);
+            for id in ids {
+                assert!(
+                    defined.contains(&id),
+                    "{:?} uses undefined id {}",
+                    instruction.class.opcode,
+                    id
+                );
+            }
+        }
+        defined.extend(instruction.result_id);
+    }
+}
+
+///Returns the value of the `f32` constant `id`, or `None` if `id` is calculated at runtime.
+fn float_constant(module: &Module, id: Word) -> Option<f32> {
+    module
+        .types_global_values
+        .iter()
+        .find(|i| i.result_id == Some(id) && i.class.opcode == Op::Constant)
+        .and_then(|i| match i.operands[0] {
+            Operand::LiteralFloat32(value) => Some(value),
+            _ => None,
+        })
+}
+
+///Serializes `field` at the constant `position` with folding enabled. Since all parameters are constant as well, the distance
+/// is usually folded into a constant, which must match the evaluated distance. Returns whether the distance was folded.
+fn serializes_like_evaluated<V: IntoSpvType + Copy + 'static>(
+    name: &str,
+    field: impl Fn() -> BoxOperation<DataId<V>, f32>,
+    position: V,
+) -> bool {
+    let sampled = || WithInput {
+        input: Box::new(Constant::new(position)),
+        inner: field(),
+    };
+    let expected = Evaluator::new().evaluate(&mut sampled(), ());
+    let (module, distance) = try_serialize(&mut sampled(), true).unwrap();
+    assert_ids_defined(&module);
+    match float_constant(&module, distance) {
+        Some(folded) => {
+            assert!(
+                (folded - expected).abs() < 1e-5,
+                "{}: {} != {}",
+                name,
+                folded,
+                expected
+            );
+            true
+        }
+        None => false,
+    }
+}
+
+#[test]
+fn sdf_fields_fold_to_evaluated_distance() {
+    let p = Vec3::new(0.3, 1.7, -0.6);
+    let check = |name, field: &dyn Fn() -> BoxOperation<DataId<Vec3>, f32>| {
+        assert!(
+            serializes_like_evaluated(name, field, p),
+            "{} was not folded",
+            name
+        )
+    };
+    check("sphere", &|| Sphere::new(1.0f32).into_operation());
+    check("cuboid", &|| {
+        Cuboid::new(Vec3::new(1.0, 2.0, 3.0)).into_operation()
+    });
+    check("round cuboid", &|| {
+        RoundCuboid::new(Vec3::ONE, 0.5f32).into_operation()
+    });
+    check("torus", &|| Torus::new(2.0f32, 0.5f32).into_operation());
+    check("capsule", &|| {
+        Capsule::new(Vec3::new(0.0, -1.0, 0.0), Vec3::Y, 0.5f32).into_operation()
+    });
+    check("cylinder", &|| {
+        Cylinder::new(1.0f32, 2.0f32).into_operation()
+    });
+    check("cone", &|| Cone::new(1.0f32, 1.0f32).into_operation());
+    check("plane", &|| Plane::new(Vec3::Y, 1.0f32).into_operation());
+
+    let cuboid = || Cuboid::new(Vec3::ONE);
+    let sphere = || Translate::new(Sphere::new(1.0f32), Vec3::new(1.0, 0.5, 0.0));
+    check("union", &|| Union::new(cuboid(), sphere()).into_operation());
+    check("intersection", &|| {
+        Intersection::new(cuboid(), sphere()).into_operation()
+    });
+    check("difference", &|| {
+        Difference::new(cuboid(), sphere()).into_operation()
+    });
+    check("smooth union", &|| {
+        SmoothUnion::new(cuboid(), sphere(), 0.5f32).into_operation()
+    });
+    check("smooth intersection", &|| {
+        SmoothIntersection::new(cuboid(), sphere(), 0.5f32).into_operation()
+    });
+    check("smooth difference", &|| {
+        SmoothDifference::new(cuboid(), sphere(), 0.5f32).into_operation()
+    });
+
+    check("translate", &|| {
+        Translate::new(cuboid(), Vec3::new(0.0, 2.0, 0.0)).into_operation()
+    });
+    //Matrix products are not folded, but the rotated field must still be serializable
+    serializes_like_evaluated(
+        "rotate",
+        || {
+            Rotate::new(cuboid(), Mat3::from_rotation_z(std::f32::consts::FRAC_PI_4))
+                .into_operation()
+        },
+        p,
+    );
+    check("scale", &|| Scale::new(cuboid(), 2.0f32).into_operation());
+    check("mirror", &|| {
+        Mirror::new(sphere(), Vec3::X).into_operation()
+    });
+    check("repeat", &|| {
+        Repeat::new(sphere(), Vec3::splat(4.0)).into_operation()
+    });
+}
+
+#[test]
+fn sdf_2d_fields_fold_to_evaluated_distance() {
+    let p = Vec2::new(0.7, -1.3);
+    let check = |name, field: &dyn Fn() -> BoxOperation<DataId<Vec2>, f32>| {
+        assert!(
+            serializes_like_evaluated(name, field, p),
+            "{} was not folded",
+            name
+        )
+    };
+    //Comparisons and matrix products are not folded, but those fields must still be serializable
+    let check_unfolded = |name, field: &dyn Fn() -> BoxOperation<DataId<Vec2>, f32>| {
+        serializes_like_evaluated(name, field, p);
+    };
+    check("circle", &|| Circle::new(1.0f32).into_operation());
+    check("rectangle", &|| {
+        Rectangle::new(Vec2::new(1.0, 2.0)).into_operation()
+    });
+    check("round rectangle", &|| {
+        RoundRectangle::new(Vec2::ONE, 0.25f32).into_operation()
+    });
+    check("oriented rectangle", &|| {
+        OrientedRectangle::new(Vec2::new(-1.0, 0.0), Vec2::new(1.0, -1.0), 0.5f32).into_operation()
+    });
+    check("segment", &|| {
+        Segment::new(Vec2::ZERO, Vec2::new(2.0, -1.0)).into_operation()
+    });
+    check("triangle", &|| {
+        Triangle::new(Vec2::new(-1.0, -1.0), Vec2::new(1.0, -1.0), Vec2::Y).into_operation()
+    });
+    check_unfolded("polygon", &|| {
+        Polygon::new(vec![
+            Vec2::new(-1.0, -1.0),
+            Vec2::new(1.0, -1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(-1.0, 0.5),
+        ])
+        .into_operation()
+    });
+    check_unfolded("arc", &|| Arc::new(1.0f32, 1.5f32, 0.2f32).into_operation());
+    check("ring", &|| Ring::new(1.0f32, 0.2f32).into_operation());
+    check("star", &|| Star::new(1.0f32, 5, 2.0f32).into_operation());
+
+    let rectangle = || Rectangle::new(Vec2::ONE);
+    let circle = || Translate::new(Circle::new(1.0f32), Vec2::new(1.0, -0.5));
+    check("union", &|| {
+        Union::new(rectangle(), circle()).into_operation()
+    });
+    check("intersection", &|| {
+        Intersection::new(rectangle(), circle()).into_operation()
+    });
+    check("difference", &|| {
+        Difference::new(rectangle(), circle()).into_operation()
+    });
+    check("smooth union", &|| {
+        SmoothUnion::new(rectangle(), circle(), 0.5f32).into_operation()
+    });
+    check("smooth intersection", &|| {
+        SmoothIntersection::new(rectangle(), circle(), 0.5f32).into_operation()
+    });
+    check("smooth difference", &|| {
+        SmoothDifference::new(rectangle(), circle(), 0.5f32).into_operation()
+    });
+
+    check("translate", &|| {
+        Translate::new(rectangle(), Vec2::new(0.0, 2.0)).into_operation()
+    });
+    check_unfolded("rotate", &|| {
+        Rotate::new(rectangle(), Mat2::from_angle(0.5)).into_operation()
+    });
+    check("scale", &|| {
+        Scale::new(rectangle(), 2.0f32).into_operation()
+    });
+    check("mirror", &|| {
+        Mirror::new(circle(), Vec2::X).into_operation()
+    });
+    check("repeat", &|| {
+        Repeat::new(circle(), Vec2::splat(4.0)).into_operation()
+    });
+}
+
+///Serializes `noise` at a runtime `position`, with and without folding. Both must result in a well formed `f32`.
+fn assert_noise_serializes<V: IntoSpvType + Copy + 'static>(
+    name: &str,
+    noise: impl Fn() -> BoxOperation<DataId<V>, f32>,
+    position: V,
+) {
+    for fold in [false, true] {
+        let mut sampled = WithInput {
+            input: Box::new(Variable::new("position", position)),
+            inner: noise(),
+        };
+        let (module, result) = try_serialize(&mut sampled, fold).unwrap();
+        assert_ids_defined(&module);
+        let ty = module.functions[0]
+            .blocks
+            .iter()
+            .flat_map(|b| &b.instructions)
+            .find(|i| i.result_id == Some(result))
+            .and_then(|i| i.result_type)
+            .unwrap();
+        let ty = module
+            .types_global_values
+            .iter()
+            .find(|i| i.result_id == Some(ty))
+            .unwrap();
+        assert_eq!(
+            (ty.class.opcode, &ty.operands[..]),
+            (Op::TypeFloat, &[Operand::LiteralInt32(32)][..]),
+            "{} is not a f32",
+            name
+        );
+    }
+}
+
+#[test]
+fn noise_serializes() {
+    let p = Vec3::new(0.3, 1.7, -0.6);
+    assert_noise_serializes(
+        "value f32",
+        || ValueNoise::<f32>::new(3).into_operation(),
+        p.x,
+    );
+    assert_noise_serializes(
+        "value Vec2",
+        || ValueNoise::<Vec2>::new(3).into_operation(),
+        p.truncate(),
+    );
+    assert_noise_serializes(
+        "value Vec3",
+        || ValueNoise::<Vec3>::new(3).into_operation(),
+        p,
+    );
+    assert_noise_serializes(
+        "perlin f32",
+        || PerlinNoise::<f32>::new(3).into_operation(),
+        p.x,
+    );
+    assert_noise_serializes(
+        "perlin Vec2",
+        || PerlinNoise::<Vec2>::new(3).into_operation(),
+        p.truncate(),
+    );
+    assert_noise_serializes(
+        "perlin Vec3",
+        || PerlinNoise::<Vec3>::new(3).into_operation(),
+        p,
+    );
+    assert_noise_serializes(
+        "simplex f32",
+        || SimplexNoise::<f32>::new(3).into_operation(),
+        p.x,
+    );
+    assert_noise_serializes(
+        "simplex Vec2",
+        || SimplexNoise::<Vec2>::new(3).into_operation(),
+        p.truncate(),
+    );
+    assert_noise_serializes(
+        "simplex Vec3",
+        || SimplexNoise::<Vec3>::new(3).into_operation(),
+        p,
+    );
+    assert_noise_serializes(
+        "worley f32",
+        || WorleyNoise::<f32>::new(3).into_operation(),
+        p.x,
+    );
+    assert_noise_serializes(
+        "worley Vec2",
+        || WorleyNoise::<Vec2>::new(3).into_operation(),
+        p.truncate(),
+    );
+    assert_noise_serializes(
+        "worley Vec3",
+        || WorleyNoise::<Vec3>::new(3).into_operation(),
+        p,
+    );
+}
+
+#[test]
+fn fbm_serializes() {
+    let p = Vec3::new(0.3, 1.7, -0.6);
+    assert_noise_serializes(
+        "fbm f32",
+        || Fbm::<f32>::new(PerlinNoise::<f32>::new(1), 4).into_operation(),
+        p.x,
+    );
+    assert_noise_serializes(
+        "fbm Vec2",
+        || Fbm::<Vec2>::new(ValueNoise::<Vec2>::new(1), 4).into_operation(),
+        p.truncate(),
+    );
+    assert_noise_serializes(
+        "fbm Vec3",
+        || Fbm::<Vec3>::new(SimplexNoise::<Vec3>::new(1), 4).into_operation(),
+        p,
+    );
+}
@@ -0,0 +1,649 @@
+use fxhash::FxHashMap;
+use proc_macro2::{Literal, Span, TokenStream as TokenStream2};
+use quote::{quote, ToTokens};
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream},
+    Error, Ident, Lit, Result, Token, Type,
+};
+
+///Types a formula can work with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Ty {
+    F32,
+    ///Float vector with the given number of components.
+    Vec(u32),
+    ///Square float matrix with the given number of columns.
+    Mat(u32),
+}
+
+impl Ty {
+    fn from_name(name: &str) -> Option<Self> {
+        let ty = match name {
+            "f32" => Ty::F32,
+            "Vec2" => Ty::Vec(2),
+            "Vec3" => Ty::Vec(3),
+            "Vec4" => Ty::Vec(4),
+            "Mat2" => Ty::Mat(2),
+            "Mat3" => Ty::Mat(3),
+            "Mat4" => Ty::Mat(4),
+            _ => return None,
+        };
+        Some(ty)
+    }
+
+    ///True for f32 and float vectors, which support all component-wise operations.
+    fn is_componentwise(&self) -> bool {
+        matches!(self, Ty::F32 | Ty::Vec(_))
+    }
+}
+
+impl std::fmt::Display for Ty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Ty::F32 => write!(f, "f32"),
+            Ty::Vec(n) => write!(f, "Vec{}", n),
+            Ty::Mat(n) => write!(f, "Mat{}", n),
+        }
+    }
+}
+
+impl ToTokens for Ty {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        match self {
+            Ty::F32 => tokens.extend(quote!(f32)),
+            _ => {
+                let ident = Ident::new(&self.to_string(), Span::call_site());
+                tokens.extend(quote!(algae::glam::#ident))
+            }
+        }
+    }
+}
+
+///Typed sub expression of a formula. `tokens` construct the (unboxed) operation that calculates the sub expression.
+struct Node {
+    tokens: TokenStream2,
+    ty: Ty,
+}
+
+impl Node {
+    fn constant(value: f32) -> Self {
+        let value = Literal::f32_suffixed(value);
+        Node {
+            tokens: quote!(algae::operations::Constant::new(#value)),
+            ty: Ty::F32,
+        }
+    }
+
+    ///Builds `operation` from `fields`, each initialized with one of `args`.
+    fn operation(
+        operation: &str,
+        generics: TokenStream2,
+        fields: &[&str],
+        args: Vec<Node>,
+        ty: Ty,
+    ) -> Self {
+        let operation = Ident::new(operation, Span::call_site());
+        let fields = fields.iter().map(|f| Ident::new(f, Span::call_site()));
+        let args = args.into_iter().map(|a| a.tokens);
+        Node {
+            tokens: quote! {
+                algae::operations::#operation::#generics {
+                    #(#fields: Box::new(#args)),*
+                }
+            },
+            ty,
+        }
+    }
+
+    ///Broadcasts a `f32` node into a vector of `len` components.
+    fn splat(self, len: u32) -> Self {
+        let ty = Ty::Vec(len);
+        let inner = self.tokens;
+        Node {
+            tokens: quote!(algae::operations::Splat::<#ty, _>::new(Box::new(#inner))),
+            ty,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinOp {
+    fn symbol(&self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Rem => "%",
+        }
+    }
+
+    ///Operation and its fields if both operands are of the same type.
+    fn operation(&self) -> (&'static str, [&'static str; 2]) {
+        match self {
+            BinOp::Add => ("Addition", ["a", "b"]),
+            BinOp::Sub => ("Subtraction", ["minuent", "subtrahend"]),
+            BinOp::Mul => ("Multiplication", ["a", "b"]),
+            BinOp::Div => ("Division", ["dividend", "divisor"]),
+            BinOp::Rem => ("Remainder", ["dividend", "divisor"]),
+        }
+    }
+
+    ///Operation that applies a scalar to each component of a vector, if there is one.
+    fn vector_scalar_operation(&self) -> Option<&'static str> {
+        match self {
+            BinOp::Add => Some("VectorPlusScalar"),
+            BinOp::Sub => Some("VectorMinusScalar"),
+            BinOp::Mul => Some("VectorTimesScalar"),
+            BinOp::Div => Some("VectorDividedByScalar"),
+            BinOp::Rem => None,
+        }
+    }
+
+    fn is_commutative(&self) -> bool {
+        matches!(self, BinOp::Add | BinOp::Mul)
+    }
+
+    fn build(&self, a: Node, b: Node, span: Span) -> Result<Node> {
+        let (operation, fields) = self.operation();
+        let node = match (a.ty, b.ty) {
+            (x, y) if x == y && x.is_componentwise() => {
+                Node::operation(operation, quote!(<_, #x>), &fields, vec![a, b], x)
+            }
+            (Ty::Mat(n), Ty::Mat(m)) if n == m && matches!(self, BinOp::Mul) => {
+                let ty = a.ty;
+                Node::operation(
+                    "MatrixTimesMatrix",
+                    quote!(<#ty, _>),
+                    &["a", "b"],
+                    vec![a, b],
+                    ty,
+                )
+            }
+            (Ty::Mat(n), Ty::Vec(m)) if n == m && matches!(self, BinOp::Mul) => {
+                let (mat, vec) = (a.ty, b.ty);
+                Node::operation(
+                    "MatrixTimesVector",
+                    quote!(<#mat, #vec, _>),
+                    &["matrix", "vector"],
+                    vec![a, b],
+                    vec,
+                )
+            }
+            (Ty::Vec(n), Ty::Mat(m)) if n == m && matches!(self, BinOp::Mul) => {
+                let (vec, mat) = (a.ty, b.ty);
+                Node::operation(
+                    "VectorTimesMatrix",
+                    quote!(<#mat, #vec, _>),
+                    &["vector", "matrix"],
+                    vec![a, b],
+                    vec,
+                )
+            }
+            (Ty::Vec(n), Ty::F32) => match self.vector_scalar_operation() {
+                Some(vs) => {
+                    let ty = a.ty;
+                    Node::operation(vs, quote!(<_, #ty>), &["vector", "scalar"], vec![a, b], ty)
+                }
+                None => return self.build(a, b.splat(n), span),
+            },
+            (Ty::F32, Ty::Vec(n)) => match self.vector_scalar_operation() {
+                Some(vs) if self.is_commutative() => {
+                    let ty = b.ty;
+                    Node::operation(vs, quote!(<_, #ty>), &["vector", "scalar"], vec![b, a], ty)
+                }
+                _ => return self.build(a.splat(n), b, span),
+            },
+            (x, y) => {
+                return Err(Error::new(
+                    span,
+                    format!("`{}` is not defined for {} and {}", self.symbol(), x, y),
+                ))
+            }
+        };
+        Ok(node)
+    }
+}
+
+///Argument and result types of a function.
+#[derive(Clone, Copy)]
+enum Signature {
+    ///Arguments and result are of the same f32 or float vector type. If `broadcast` is set, f32 arguments are splatted
+    /// to the type of the vector arguments, like GLSL's `max(vec3, float)`.
+    Componentwise { broadcast: bool },
+    ///Only defined for f32.
+    Scalar,
+    ///Arguments are float vectors of the same type, the result is a f32.
+    VectorToScalar,
+    ///Arguments and result are float vectors of the same type.
+    Vector,
+    ///Like [Vector](Signature::Vector), but the last argument is a f32.
+    Refract,
+    ///Arguments and result are Vec3.
+    Cross,
+    ///Argument and result are matrices of the same type.
+    Matrix,
+    ///Argument is a matrix, the result is a f32.
+    Determinant,
+}
+
+///Returns the operation, its fields and signature of the function `name`. The fields are in the order of the function's
+/// arguments.
+fn function(name: &str) -> Option<(&'static str, &'static [&'static str], Signature)> {
+    use Signature::*;
+    const CW: Signature = Componentwise { broadcast: false };
+    const CWB: Signature = Componentwise { broadcast: true };
+    let f: (&'static str, &'static [&'static str], Signature) = match name {
+        "abs" => ("Abs", &["inner"], CW),
+        "floor" => ("Floor", &["inner"], CW),
+        "ceil" => ("Ceil", &["inner"], CW),
+        "fract" => ("Fract", &["inner"], CW),
+        "round" => ("Round", &["inner"], CW),
+        "trunc" => ("Trunc", &["inner"], CW),
+        "sign" => ("Sign", &["inner"], CW),
+        "exp" => ("Exp", &["inner"], CW),
+        "exp2" => ("Exp2", &["inner"], CW),
+        "log" => ("Log", &["inner"], CW),
+        "log2" => ("Log2", &["inner"], CW),
+        "inversesqrt" => ("InverseSqrt", &["inner"], CW),
+        "asin" => ("ArcSine", &["inner"], CW),
+        "acos" => ("ArcCosine", &["inner"], CW),
+        "atan" => ("ArcTangent", &["inner"], CW),
+        "atan2" => ("ArcTangent2", &["y", "x"], CW),
+        "pow" => ("Pow", &["base", "exponent"], CW),
+        "fma" => ("Fma", &["a", "b", "c"], CW),
+        "mod" => ("Modulo", &["dividend", "divisor"], CWB),
+        "min" => ("Min", &["a", "b"], CWB),
+        "max" => ("Max", &["a", "b"], CWB),
+        "step" => ("Step", &["edge", "x"], CWB),
+        "clamp" => ("Clamp", &["inner", "min", "max"], CWB),
+        "mix" => ("Mix", &["a", "b", "t"], CWB),
+        "smoothstep" => ("SmoothStep", &["edge0", "edge1", "x"], CWB),
+        "sin" => ("Sine", &["inner"], Scalar),
+        "cos" => ("Cosine", &["inner"], Scalar),
+        "tan" => ("Tangent", &["inner"], Scalar),
+        "sqrt" => ("Sqrt", &["inner"], Scalar),
+        "length" => ("Length", &["inner"], VectorToScalar),
+        "dot" => ("Dot", &["a", "b"], VectorToScalar),
+        "distance" => ("Distance", &["a", "b"], VectorToScalar),
+        "normalize" => ("Normalize", &["inner"], Vector),
+        "reflect" => ("Reflect", &["incident", "normal"], Vector),
+        "faceforward" => ("FaceForward", &["normal", "incident", "reference"], Vector),
+        "refract" => ("Refract", &["incident", "normal", "eta"], Refract),
+        "cross" => ("Cross", &["a", "b"], Cross),
+        "transpose" => ("Transpose", &["inner"], Matrix),
+        "inverse" => ("MatrixInverse", &["inner"], Matrix),
+        "determinant" => ("Determinant", &["inner"], Determinant),
+        _ => return None,
+    };
+    Some(f)
+}
+
+///Checks the `args` of the function `name` and builds its operation.
+fn call(name: &Ident, mut args: Vec<Node>) -> Result<Node> {
+    let span = name.span();
+    let name_str = name.to_string();
+    let types = args
+        .iter()
+        .map(|a| a.ty.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let unsupported = || {
+        Err(Error::new(
+            span,
+            format!("`{}` is not defined for ({})", name_str, types),
+        ))
+    };
+
+    //Vector constructors
+    if let Some(len) = name_str
+        .strip_prefix("vec")
+        .and_then(|n| n.parse::<u32>().ok())
+    {
+        let ty = Ty::Vec(len);
+        if !(2..=4).contains(&len) || args.iter().any(|a| a.ty != Ty::F32) {
+            return unsupported();
+        }
+        return match args.len() {
+            1 => Ok(args.remove(0).splat(len)),
+            n if n == len as usize => {
+                let elements = args.into_iter().map(|a| a.tokens);
+                Ok(Node {
+                    tokens: quote! {
+                        algae::operations::VecConstruct::<#ty, _>::new(vec![#(Box::new(#elements)),*])
+                    },
+                    ty,
+                })
+            }
+            n => Err(Error::new(
+                span,
+                format!("`{}` takes 1 or {} arguments, found {}", name_str, len, n),
+            )),
+        };
+    }
+
+    let (operation, fields, signature) = match function(&name_str) {
+        Some(f) => f,
+        None => return Err(Error::new(span, format!("Unknown function `{}`", name_str))),
+    };
+    if args.len() != fields.len() {
+        return Err(Error::new(
+            span,
+            format!(
+                "`{}` takes {} argument(s), found {}",
+                name_str,
+                fields.len(),
+                args.len()
+            ),
+        ));
+    }
+
+    if let Signature::Componentwise { broadcast: true } = signature {
+        if let Some(len) = args.iter().find_map(|a| match a.ty {
+            Ty::Vec(n) => Some(n),
+            _ => None,
+        }) {
+            args = args
+                .into_iter()
+                .map(|a| if a.ty == Ty::F32 { a.splat(len) } else { a })
+                .collect();
+        }
+    }
+
+    let first = args[0].ty;
+    let all_first = args.iter().all(|a| a.ty == first);
+    let (generics, ty) = match (signature, first) {
+        (Signature::Componentwise { .. }, t) if all_first && t.is_componentwise() => {
+            (quote!(<_, #t>), t)
+        }
+        (Signature::Scalar, Ty::F32) if all_first => (quote!(<_>), Ty::F32),
+        (Signature::VectorToScalar, Ty::Vec(_)) if all_first => (quote!(<#first, _>), Ty::F32),
+        (Signature::Vector, Ty::Vec(_)) if all_first => (quote!(<#first, _>), first),
+        (Signature::Refract, Ty::Vec(_)) if args[1].ty == first && args[2].ty == Ty::F32 => {
+            (quote!(<#first, _>), first)
+        }
+        (Signature::Cross, Ty::Vec(3)) if all_first => (quote!(<_>), first),
+        (Signature::Matrix, Ty::Mat(_)) => (quote!(<#first, _>), first),
+        (Signature::Determinant, Ty::Mat(_)) => (quote!(<#first, _>), Ty::F32),
+        _ => return unsupported(),
+    };
+
+    Ok(Node::operation(operation, generics, fields, args, ty))
+}
+
+///Converts a swizzle like `xzy` or `bgr` into component indices.
+fn swizzle_components(swizzle: &str) -> Option<Vec<u32>> {
+    swizzle
+        .chars()
+        .map(|c| match c {
+            'x' | 'r' => Some(0),
+            'y' | 'g' => Some(1),
+            'z' | 'b' => Some(2),
+            'w' | 'a' => Some(3),
+            _ => None,
+        })
+        .collect()
+}
+
+///Infix math formula. The formula starts with a closure-like header that declares the variables it uses, followed by the
+/// expression:
+///
+/// ```ignore
+/// |p: Vec2, b: Vec2| length(max(|p| - b, 0.0)) + min(max((|p| - b).x, (|p| - b).y), 0.0)
+/// ```
+///
+/// Each variable becomes a [Variable](algae::operations::Variable) of the given type, which defaults to zero. Supported
+/// types are `f32`, `Vec2`, `Vec3`, `Vec4`, `Mat2`, `Mat3` and `Mat4`. The expression knows the following, from the lowest
+/// to the highest precedence:
+///
+/// - `a + b`, `a - b`
+/// - `a * b`, `a / b`, `a % b`
+/// - `-a`
+/// - `a.x`, `a.xzy`: component access and swizzles, using `xyzw` or `rgba`.
+/// - Number literals, which are f32 constants, variables, function calls like `max(a, b)`, `(a)` and `|a|` for the absolute
+///   value.
+///
+/// Functions use GLSL's names. Like in GLSL, vectors and scalars can be mixed in arithmetic and functions like `min`, `max` or
+/// `clamp`. `vec2`, `vec3` and `vec4` construct a vector from one f32 per component, or splat a single f32.
+pub struct Formula {
+    expression: Node,
+}
+
+impl Parse for Formula {
+    fn parse(stream: ParseStream) -> Result<Self> {
+        let mut variables = FxHashMap::default();
+        if stream.peek(Token![||]) {
+            stream.parse::<Token![||]>()?;
+        } else {
+            stream.parse::<Token![|]>()?;
+            while !stream.peek(Token![|]) {
+                let name = stream.parse::<Ident>()?;
+                stream.parse::<Token![:]>()?;
+                let ty = stream.parse::<Type>()?;
+                let ty_name = match &ty {
+                    Type::Path(p) => p.path.segments.last().map(|s| s.ident.to_string()),
+                    _ => None,
+                };
+                match ty_name.as_deref().and_then(Ty::from_name) {
+                    Some(ty) => {
+                        variables.insert(name, ty);
+                    }
+                    None => {
+                        return Err(Error::new_spanned(
+                            ty,
+                            "Expected one of f32, Vec2, Vec3, Vec4, Mat2, Mat3 or Mat4",
+                        ))
+                    }
+                }
+                if !stream.peek(Token![|]) {
+                    stream.parse::<Token![,]>()?;
+                }
+            }
+            stream.parse::<Token![|]>()?;
+        }
+
+        let parser = FormulaParser { variables };
+        let expression = parser.expression(stream)?;
+        if !stream.is_empty() {
+            return Err(stream.error("Expected operator or end of formula"));
+        }
+        Ok(Formula { expression })
+    }
+}
+
+impl ToTokens for Formula {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        tokens.extend(self.expression.tokens.clone());
+    }
+}
+
+///Recursive descent parser of a formula's expression.
+struct FormulaParser {
+    variables: FxHashMap<Ident, Ty>,
+}
+
+impl FormulaParser {
+    fn expression(&self, stream: ParseStream) -> Result<Node> {
+        let mut lhs = self.term(stream)?;
+        loop {
+            let (op, span) = if stream.peek(Token![+]) {
+                (BinOp::Add, stream.parse::<Token![+]>()?.span)
+            } else if stream.peek(Token![-]) {
+                (BinOp::Sub, stream.parse::<Token![-]>()?.span)
+            } else {
+                return Ok(lhs);
+            };
+            let rhs = self.term(stream)?;
+            lhs = op.build(lhs, rhs, span)?;
+        }
+    }
+
+    fn term(&self, stream: ParseStream) -> Result<Node> {
+        let mut lhs = self.unary(stream)?;
+        loop {
+            let (op, span) = if stream.peek(Token![*]) {
+                (BinOp::Mul, stream.parse::<Token![*]>()?.span)
+            } else if stream.peek(Token![/]) {
+                (BinOp::Div, stream.parse::<Token![/]>()?.span)
+            } else if stream.peek(Token![%]) {
+                (BinOp::Rem, stream.parse::<Token![%]>()?.span)
+            } else {
+                return Ok(lhs);
+            };
+            let rhs = self.unary(stream)?;
+            lhs = op.build(lhs, rhs, span)?;
+        }
+    }
+
+    fn unary(&self, stream: ParseStream) -> Result<Node> {
+        if !stream.peek(Token![-]) {
+            return self.postfix(stream);
+        }
+        let minus = stream.parse::<Token![-]>()?;
+        //Fold negative literals into the constant
+        if stream.peek(Lit) {
+            return Ok(Node::constant(-self.literal(stream)?));
+        }
+        let inner = self.unary(stream)?;
+        if !inner.ty.is_componentwise() {
+            return Err(Error::new(
+                minus.span,
+                format!("`-` is not defined for {}", inner.ty),
+            ));
+        }
+        let ty = inner.ty;
+        Ok(Node::operation(
+            "Negate",
+            quote!(<_, #ty>),
+            &["inner"],
+            vec![inner],
+            ty,
+        ))
+    }
+
+    fn postfix(&self, stream: ParseStream) -> Result<Node> {
+        let mut inner = self.primary(stream)?;
+        while stream.peek(Token![.]) {
+            stream.parse::<Token![.]>()?;
+            let swizzle = stream.parse::<Ident>()?;
+            let len = match inner.ty {
+                Ty::Vec(n) => n,
+                ty => {
+                    return Err(Error::new(
+                        swizzle.span(),
+                        format!("Can not access components of {}", ty),
+                    ))
+                }
+            };
+            let components = swizzle_components(&swizzle.to_string())
+                .filter(|c| (1..=4).contains(&c.len()) && c.iter().all(|c| *c < len))
+                .ok_or_else(|| {
+                    Error::new(
+                        swizzle.span(),
+                        format!("Invalid swizzle `{}` for Vec{}", swizzle, len),
+                    )
+                })?;
+
+            let vecty = inner.ty;
+            let tokens = inner.tokens;
+            inner = if let [element] = components.as_slice() {
+                Node {
+                    tokens: quote! {
+                        algae::operations::VecSelectElement::<#vecty, _> {
+                            element: #element,
+                            inner: Box::new(#tokens),
+                        }
+                    },
+                    ty: Ty::F32,
+                }
+            } else {
+                let ty = Ty::Vec(components.len() as u32);
+                let pattern: String = components
+                    .iter()
+                    .map(|c| ['x', 'y', 'z', 'w'][*c as usize])
+                    .collect();
+                Node {
+                    tokens: quote! {
+                        algae::operations::Swizzle::<#vecty, #ty, _>::new(#pattern, Box::new(#tokens))
+                    },
+                    ty,
+                }
+            };
+        }
+        Ok(inner)
+    }
+
+    fn primary(&self, stream: ParseStream) -> Result<Node> {
+        if stream.peek(Lit) {
+            return Ok(Node::constant(self.literal(stream)?));
+        }
+        if stream.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in stream);
+            let inner = self.expression(&content)?;
+            if !content.is_empty() {
+                return Err(content.error("Expected operator or `)`"));
+            }
+            return Ok(inner);
+        }
+        if stream.peek(Token![|]) {
+            let open = stream.parse::<Token![|]>()?;
+            let inner = self.expression(stream)?;
+            stream.parse::<Token![|]>()?;
+            return call(&Ident::new("abs", open.span), vec![inner]);
+        }
+
+        let name = stream.parse::<Ident>()?;
+        if stream.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in stream);
+            let mut args = Vec::new();
+            while !content.is_empty() {
+                args.push(self.expression(&content)?);
+                if !content.is_empty() {
+                    content.parse::<Token![,]>()?;
+                }
+            }
+            return call(&name, args);
+        }
+
+        match self.variables.get(&name) {
+            Some(ty) => Ok(Node {
+                tokens: quote! {
+                    algae::operations::Variable::new(stringify!(#name), <#ty as Default>::default())
+                },
+                ty: *ty,
+            }),
+            None => Err(Error::new(
+                name.span(),
+                format!(
+                    "Unknown variable `{}`, variables must be declared in the formula's header, like `|{}: f32|`",
+                    name, name
+                ),
+            )),
+        }
+    }
+
+    ///Parses a number literal into a f32.
+    fn literal(&self, stream: ParseStream) -> Result<f32> {
+        let lit = stream.parse::<Lit>()?;
+        let (value, suffix) = match &lit {
+            Lit::Float(f) => (f.base10_parse::<f32>(), f.suffix()),
+            Lit::Int(i) => (i.base10_parse::<f32>(), i.suffix()),
+            _ => return Err(Error::new(lit.span(), "Expected a number")),
+        };
+        if !suffix.is_empty() && suffix != "f32" {
+            return Err(Error::new(lit.span(), "Formulas only support f32 numbers"));
+        }
+        value
+    }
+}
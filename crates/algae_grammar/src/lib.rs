@@ -13,6 +13,7 @@ use proc_macro2::TokenStream as TokenStream2;
 use proc_macro_error::abort_call_site;
 use proc_macro_error::proc_macro_error;
 
+use crate::formula::Formula;
 use crate::rexpr::RExpr;
 
 ///s-expression parser
@@ -21,13 +22,38 @@ mod sexpr;
 ///rust expression parser
 mod rexpr;
 
+///infix formula parser
+mod formula;
+
 ///Algae grammar macro. Allows writing human readable math function that are turned into an algae serializeable [Operation](algae::Operation) at runtime.
+///
+/// The formula declares its variables in a closure-like header, followed by the expression in infix notation. The result is the
+/// operation that calculates the expression.
+///
+/// Each variable becomes a [Variable](algae::operations::Variable) of the given type, which defaults to zero. Supported
+/// types are `f32`, `Vec2`, `Vec3`, `Vec4`, `Mat2`, `Mat3` and `Mat4`. The expression supports `+`, `-`, `*`, `/` and `%`
+/// with the usual precedence, unary `-`, `|a|` for the absolute value, component access and swizzles like `a.x` or `a.xzy`,
+/// f32 number literals and function calls with GLSL's names like `max(a, b)` or `vec3(x, y, z)`. Like in GLSL, vectors and
+/// scalars can be mixed in arithmetic and functions like `min`, `max` or `clamp`.
+///
+/// # Example
+/// ```rust
+/// use algae::{glam::Vec2, Evaluator};
+///
+/// //Distance to the outside of a box with the extent `b`
+/// let mut op = algae_grammar::formula!(|p: Vec2, b: Vec2| length(max(|p| - b, 0.0)));
+///
+/// let mut evaluator = Evaluator::new();
+/// evaluator.set_variable("p", Vec2::new(3.0, 0.0));
+/// evaluator.set_variable("b", Vec2::new(1.0, 1.0));
+/// assert_eq!(evaluator.evaluate(&mut op, ()), 2.0);
+/// ```
 #[proc_macro]
 #[proc_macro_error]
 pub fn formula(input: TokenStream) -> TokenStream {
-
+    let formula = syn::parse_macro_input!(input as Formula);
     let token_stream = quote!{
-        println!("Hello from macro!");
+        #formula
     };
     TokenStream::from(token_stream)
 }
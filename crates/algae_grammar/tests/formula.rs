@@ -0,0 +1,55 @@
+use algae::{
+    glam::{Mat3, Vec2, Vec3},
+    Evaluator,
+};
+use algae_grammar::formula;
+
+#[test]
+fn box_sdf() {
+    let mut op =
+        formula!(|p: Vec3, b: Vec3, d: Vec2| length(max(|p| -b, 0.0)) + min(max(d.x, d.y), 0.0));
+
+    let mut evaluator = Evaluator::new();
+    evaluator.set_variable("p", Vec3::new(-4.0, 0.0, 5.0));
+    evaluator.set_variable("b", Vec3::ONE);
+    evaluator.set_variable("d", Vec2::new(-1.0, -2.0));
+    assert_eq!(evaluator.evaluate(&mut op, ()), 4.0);
+}
+
+#[test]
+fn precedence() {
+    let mut evaluator = Evaluator::new();
+    evaluator.set_variable("x", 2.0f32);
+
+    let mut op = formula!(|x: f32| 1 + 2 * x - -x / 4 * 2);
+    assert_eq!(evaluator.evaluate(&mut op, ()), 6.0);
+    let mut op = formula!(|x: f32| (1 + 2) * -(x - 3) % 2);
+    assert_eq!(evaluator.evaluate(&mut op, ()), 1.0);
+    let mut op = formula!(|x: f32| -|1 - x * 2| + sqrt(x * 8));
+    assert_eq!(evaluator.evaluate(&mut op, ()), 1.0);
+    let mut op = formula!(|| sin(0.0) + cos(0.0) * -1.5e1);
+    assert_eq!(evaluator.evaluate(&mut op, ()), -15.0);
+}
+
+#[test]
+fn vectors_and_scalars() {
+    let mut evaluator = Evaluator::new();
+    evaluator.set_variable("p", Vec3::new(1.0, 2.0, 4.0));
+
+    let mut op = formula!(|p: Vec3| p.zx * 2 - 1 + vec2(p.y));
+    assert_eq!(evaluator.evaluate(&mut op, ()), Vec2::new(9.0, 3.0));
+    let mut op = formula!(|p: Vec3| 8 / p - 2 * p.bgr + clamp(p, 1.5, 3.0));
+    assert_eq!(evaluator.evaluate(&mut op, ()), Vec3::new(1.5, 2.0, 3.0));
+    let mut op = formula!(|p: Vec3| dot(cross(p, vec3(0, 0, 1)), p.yxz) - p.x);
+    assert_eq!(evaluator.evaluate(&mut op, ()), 2.0);
+}
+
+#[test]
+fn matrices() {
+    let mut evaluator = Evaluator::new();
+    evaluator.set_variable("m", Mat3::from_diagonal(Vec3::new(1.0, 2.0, 3.0)));
+    evaluator.set_variable("v", Vec3::ONE);
+
+    let mut op = formula!(|m: Mat3, v: Vec3| m * v + v * transpose(m) * determinant(m));
+    assert_eq!(evaluator.evaluate(&mut op, ()), Vec3::new(7.0, 14.0, 21.0));
+}
@@ -7,7 +7,7 @@ use std::{
 
 use algae::{
     spv_fi::{SpvError, SpvFi},
-    DataId, Operation, Serializer,
+    DataId, Operation, SerializeError, Serializer,
 };
 
 use rspirv::{
@@ -21,6 +21,8 @@ pub enum JitError {
     ///Happens if the SpirvBinary is valid, but there are errors in the algae specific entry point.
     FailedToParseEntrypoint(SpvError),
     CouldNotFindFunction(String),
+    ///Happens if the injected operation can't be serialized.
+    FailedToSerialize(SerializeError),
 }
 
 impl core::fmt::Display for JitError {
@@ -29,13 +31,17 @@ impl core::fmt::Display for JitError {
             JitError::FailedToParseSpirvBinary => write!(f, "Failed to parse SpirV Binary"),
             JitError::CouldNotFindFunction(fname) => write!(f,"Failed to find function with name: {} in spirv binary. Is the spirv binary compiled with debug information enabled?", fname),
             JitError::FailedToParseEntrypoint(e) => write!(f,"Failed to parse entry point: {e}"),
+            JitError::FailedToSerialize(e) => write!(f, "Failed to serialize function: {e}"),
         }
     }
 }
 
 impl Error for JitError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
+        match self {
+            JitError::FailedToSerialize(e) => Some(e),
+            _ => None,
+        }
     }
 }
 
@@ -116,6 +122,14 @@ fn diff(s0: &str, s1: &str) {
     );
 }
 
+///Wraps the `error` the builder returned while emitting `operation` of the injected function.
+fn builder_error(operation: &str, error: rspirv::dr::Error) -> JitError {
+    JitError::FailedToSerialize(SerializeError::Builder {
+        operation: operation.to_string(),
+        error,
+    })
+}
+
 ///Keeps track where in `src` injection points are located
 #[derive(Clone)]
 pub struct Injector {
@@ -129,12 +143,13 @@ pub struct Injector {
 }
 
 impl Injector {
-    ///Tries to inject a function with the given input/output signature
+    ///Tries to inject a function with the given input/output signature. If the function can't be serialized, an error is
+    /// returned and the module stays unchanged.
     pub fn inject<I, O>(
         &mut self,
         input: I,
         function: &mut dyn Operation<Input = I, Output = DataId<O>>,
-    ) {
+    ) -> Result<(), JitError> {
         let mut working_builder = rspirv::dr::Builder::new_from_module(self.module.clone());
 
         //move to inject function. This should not fail, since the fi would otherwise not exist.
@@ -142,37 +157,46 @@ impl Injector {
             .select_function(Some(self.fid))
             .expect("Failed to select inject function!");
 
-        //Start out by creating a new blog in our builder.
-        let new_block_id = working_builder.begin_block(None).unwrap();
+        //Start out by creating a new blog in our builder. Every block before this one belongs to the original function.
+        let new_block_id = working_builder
+            .begin_block(None)
+            .map_err(|error| builder_error("inject block", error))?;
         let inject_block = working_builder.selected_block().unwrap();
 
         //Now setup the serializer and start serializing the function
         let mut serializer = Serializer::new(&mut working_builder, &self.interface);
 
         //Serialize into function
-        let return_value = function.serialize(&mut serializer, input);
+        let return_value = function
+            .serialize(&mut serializer, input)
+            .map_err(JitError::FailedToSerialize)?;
 
         //Append the return value
-        let ret = serializer.builder_mut().ret_value(return_value.id).unwrap();
+        let ret = serializer
+            .builder_mut()
+            .ret_value(return_value.id)
+            .map_err(|error| builder_error("return value", error))?;
 
         #[cfg(feature = "logging")]
         log::info!("Writing to block {}, id={}", inject_block, new_block_id);
 
-        //We do now is inserting our payload as a second block into the function, afterwards we remove the original first block
+        //The payload was appended to the function's original blocks. It might consist of several blocks if
+        //the function contains control flow. We therefore remove all original blocks, which makes the first injected block
+        //the entry block of the function.
         let function_index = working_builder.selected_function().unwrap();
 
         //Swap out blocks
         let mut new_module = working_builder.module();
-        let injected_block = new_module.functions[function_index]
+        new_module.functions[function_index]
             .blocks
-            .remove(inject_block);
-        new_module.functions[function_index].blocks[0] = injected_block;
+            .drain(0..inject_block);
 
         let after_injection_module = new_module.clone().disassemble();
 
         diff(&self.module.disassemble(), &after_injection_module);
         //Switch module to the most recent one
         self.module = new_module;
+        Ok(())
     }
 
     pub fn new(module: rspirv::dr::Module, inject_function_name: &str) -> Result<Self, JitError> {
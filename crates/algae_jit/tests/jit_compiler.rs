@@ -1,3 +1,11 @@
+use algae::{
+    operations::{Constant, IfElse, Less},
+    rspirv::{
+        binary::Assemble,
+        dr::{load_words, Block, Builder, Module},
+        spirv::{AddressingModel, Capability, ExecutionModel, FunctionControl, MemoryModel, Op},
+    },
+};
 use algae_jit::AlgaeJit;
 
 #[test]
@@ -7,3 +15,94 @@ fn jit_creation() {
         "Creating a module from directory should return error"
     );
 }
+
+///Module with an injection point that consists of two blocks, and a shader that calls it. Returns the module as well as
+/// the labels of the injection point's blocks.
+fn injection_module() -> (Module, Vec<u32>) {
+    let mut builder = Builder::new();
+    builder.capability(Capability::Shader);
+    builder.memory_model(AddressingModel::Logical, MemoryModel::GLSL450);
+    let t_void = builder.type_void();
+    let t_f32 = builder.type_float(32);
+    let t_main = builder.type_function(t_void, []);
+    let t_injector = builder.type_function(t_f32, []);
+
+    let injector = builder
+        .begin_function(t_f32, None, FunctionControl::DONT_INLINE, t_injector)
+        .unwrap();
+    builder.name(injector, "test_shader::injector");
+    let entry = builder.begin_block(None).unwrap();
+    let exit = builder.id();
+    builder.branch(exit).unwrap();
+    builder.begin_block(Some(exit)).unwrap();
+    let default = builder.constant_f32(t_f32, 0.0);
+    builder.ret_value(default).unwrap();
+    builder.end_function().unwrap();
+
+    let main = builder
+        .begin_function(t_void, None, FunctionControl::NONE, t_main)
+        .unwrap();
+    builder.begin_block(None).unwrap();
+    builder.function_call(t_f32, None, injector, []).unwrap();
+    builder.ret().unwrap();
+    builder.end_function().unwrap();
+    builder.entry_point(ExecutionModel::Fragment, main, "main", []);
+
+    (builder.module(), vec![entry, exit])
+}
+
+///Id of the label that starts `block`.
+fn label(block: &Block) -> u32 {
+    block.label.as_ref().unwrap().result_id.unwrap()
+}
+
+#[test]
+fn injected_control_flow_replaces_function_body() {
+    let (module, original_labels) = injection_module();
+    let path = std::env::temp_dir().join("algae_jit_injected_control_flow.spv");
+    let bytes: Vec<u8> = module
+        .assemble()
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .collect();
+    std::fs::write(&path, bytes).unwrap();
+
+    let mut jit = AlgaeJit::new(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut payload = IfElse::<(), f32> {
+        condition: Box::new(Less::<(), f32> {
+            a: Box::new(Constant::new(1.0f32)),
+            b: Box::new(Constant::new(2.0f32)),
+        }),
+        true_branch: Box::new(Constant::new(2.0f32)),
+        false_branch: Box::new(Constant::new(3.0f32)),
+    };
+    jit.injector().inject((), &mut payload).unwrap();
+
+    let injected = load_words(jit.get_module()).unwrap();
+    let blocks = &injected.functions[0].blocks;
+
+    //header, both branches and the merge block of the payload, nothing of the original body
+    assert_eq!(blocks.len(), 4);
+    assert!(blocks
+        .iter()
+        .all(|block| !original_labels.contains(&label(block))));
+
+    //The entry block is the payload's header, which is the only block no other block branches to.
+    let header_ops: Vec<Op> = blocks[0]
+        .instructions
+        .iter()
+        .map(|i| i.class.opcode)
+        .collect();
+    assert!(header_ops.ends_with(&[Op::SelectionMerge, Op::BranchConditional]));
+    assert!(blocks.iter().all(|block| block
+        .instructions
+        .iter()
+        .flat_map(|i| &i.operands)
+        .all(|operand| operand.id_ref_any() != Some(label(&blocks[0])))));
+    assert_eq!(
+        blocks[3].instructions.last().unwrap().class.opcode,
+        Op::ReturnValue
+    );
+}
@@ -2,10 +2,8 @@
 
 use algae::{
     glam::Vec2,
-    operations::{
-        Abs, Addition, Constant, Length, MapInput, Max, Min, Subtraction,
-        Variable, VecSelectElement, OrderedOperations, AccessResult,
-    },
+    operations::{Addition, Variable, WithInput},
+    sdf::Rectangle,
 };
 use algae_jit::AlgaeJit;
 use frame_builder::FrameBuilder;
@@ -54,92 +52,16 @@ fn main() {
     };
      */
 
-    /*
-    float sdBox( in vec2 p, in vec2 b )
-    {
-        vec2 d = abs(p)-b;
-        return length(max(d,0.0)) + min(max(d.x,d.y),0.0);
-    }
-    */
-
-    
-    let mut bfunc: OrderedOperations<(), _> = OrderedOperations::new(
-        "d", Box::new(Subtraction {
-            minuent: Box::new(Abs {
-                inner: Box::new(Variable::new("coord", Vec2::ZERO)),
-            }), //abs(p)
-            subtrahend: Box::new(Constant::new(Vec2::new(50.0, 60.0))), //extend b
-        })        
-    ).push(
-        "result", Box::new(Addition {
-            a: Box::new(Length {
-                inner: Box::new(Max {
-                    a: Box::new(AccessResult::<Vec2>::new("d")),
-                    b: Box::new(Constant::new(Vec2::ZERO))
-                }),
-            }),
-            b: Box::new(Min {
-                a: Box::new(Max {
-                    a: Box::new(VecSelectElement::<Vec2, _> {
-                        element: 0,
-                        inner: Box::new(AccessResult::<Vec2>::new("d")),
-                    }),
-                    b: Box::new(VecSelectElement::<Vec2, _> {
-                        element: 1,
-                        inner: Box::new(AccessResult::<Vec2>::new("d")),
-                    }),
-                }),
-                b: Box::new(Constant::new(0.0f32)),
-            }),
-        })
-    );
-     
-
-    let mut op: OrderedOperations<(), _> = algae_grammar::rexpr!{
-        let d: Vec2 = Sub(Abs(Add(Var(coord, Vec2(0.0f32, 0.0f32)), Var(offset, Vec2(0.0f32, 0.0f32)))),  Const(Vec2(200.0f32, 50.0f32)));
-        let res: f32 = Add(Length(Max(d, Const(Vec2(0.0, 0.0)))),  Min(Max(VecSelectElement(d, 0), VecSelectElement(d, 1)), Const(0.0f32)));
-	
-        return res;
-    };
-    
-    /*
-    let mut box_function = Link {
-        //Calculate
-        first: Box::new(Subtraction {
-            minuent: Box::new(Abs {
-                inner: Box::new(Variable::new("coord", Vec2::ZERO)),
-            }), //abs(p)
-            subtrahend: Box::new(Constant {
-                value: Vec2::new(50.0, 60.0),
-            }), //extend b
-        }),
-        second: Box::new(Addition {
-            a: Box::new(Length {
-                inner: Box::new(Max {
-                    a: Box::new(ReturnInput::new()),
-                    b: Box::new(MapInput::new(
-                        Box::new(Constant { value: Vec2::ZERO }),
-                        |_i| (), //map to nothing
-                    )),
-                }),
-            }),
-            b: Box::new(Min {
-                a: Box::new(Max {
-                    a: Box::new(VecSelectElement::<Vec2, _> {
-                        element: 0,
-                        inner: Box::new(ReturnInput::new()),
-                    }),
-                    b: Box::new(VecSelectElement::<Vec2, _> {
-                        element: 1,
-                        inner: Box::new(ReturnInput::new()),
-                    }),
-                }),
-                b: Box::new(MapInput::new(Box::new(Constant { value: 0.0f32 }), |_i| ())),
-            }),
+    //Box that is moved by the offset.
+    let mut op = WithInput {
+        input: Box::new(Addition {
+            a: Box::new(Variable::new("coord", Vec2::ZERO)),
+            b: Box::new(Variable::new("offset", Vec2::ZERO)),
         }),
+        inner: Box::new(Rectangle::new(Vec2::new(200.0, 50.0))),
     };
-*/
-    compiler.injector().inject((), &mut op);
+
+    compiler.injector().inject((), &mut op).unwrap();
 
     let mut fb = FrameBuilder::new(&ctx, compiler);
 